@@ -2,24 +2,51 @@
 //! Uses the official Polymarket SDK for authentication
 
 use anyhow::{Context, Result};
+use alloy::primitives::Address;
 use alloy::signers::local::PrivateKeySigner;
 use alloy::signers::Signer as AlloySigner;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use polymarket_client_sdk::auth::{state::Authenticated, Normal};
 use polymarket_client_sdk::clob::{
     Client as PolyClient, Config as PolyConfig,
-    types::{OrderBookSummaryRequestBuilder, Amount, Side as PolySide, PostOrderResponse},
+    types::{
+        AssetType, BalanceAllowanceRequestBuilder, MarketResponse,
+        OrderBookSummaryRequestBuilder, OrderType as PolyOrderType, Side as PolySide,
+        PostOrderResponse,
+    },
 };
 use polymarket_client_sdk::error::Error as PolyError;
 use polymarket_client_sdk::POLYGON;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::HashMap;
 use std::str::FromStr;
 use tracing::{debug, error, info, warn};
 
+use super::ctf::{CtfClient, CtfConfig, CTF_EXCHANGE_ADDRESS, NEG_RISK_ADAPTER_ADDRESS, NEG_RISK_EXCHANGE_ADDRESS};
+use super::exchange::Exchange;
 use super::types::*;
 
-/// CLOB API endpoints
-const CLOB_HOST: &str = "https://clob.polymarket.com";
+/// Default CLOB API endpoint
+pub const CLOB_HOST: &str = "https://clob.polymarket.com";
+
+/// Default data API endpoint, for account positions
+pub const DATA_API_HOST: &str = "https://data-api.polymarket.com";
+
+/// Default Gamma API endpoint, for market liquidity and volume
+pub const GAMMA_HOST: &str = "https://gamma-api.polymarket.com";
+
+/// Markets per Gamma API request
+const GAMMA_BATCH_SIZE: usize = 50;
+
+/// Cursor the CLOB API returns after the last page of a listing
+const END_CURSOR: &str = "LTE=";
+
+/// Page size for the data API positions endpoint
+const POSITIONS_PAGE_SIZE: usize = 500;
+
+/// USDC has 6 decimals; balances and allowances come back in base units
+const USDC_DECIMALS: u32 = 6;
 
 /// High-performance CLOB client using official SDK
 pub struct ClobClient {
@@ -29,11 +56,17 @@ pub struct ClobClient {
     signer: PrivateKeySigner,
     /// Wallet address
     address: String,
+    /// Plain HTTP client for the data API
+    http: reqwest::Client,
+    /// Data API base URL
+    data_api_host: String,
+    /// Gamma API base URL
+    gamma_host: String,
 }
 
 impl ClobClient {
-    /// Create new client with authentication
-    pub async fn new(private_key: &str) -> Result<Self> {
+    /// Create new client with authentication against `clob_host`
+    pub async fn new(private_key: &str, clob_host: &str, data_api_host: &str, gamma_host: &str) -> Result<Self> {
         info!("Initializing Polymarket client with official SDK...");
 
         // Create signer with alloy (PrivateKeySigner is a type alias for LocalSigner<SigningKey>)
@@ -46,7 +79,7 @@ impl ClobClient {
         info!("Wallet address: {}", address);
 
         // Create and authenticate client using official SDK
-        let client = PolyClient::new(CLOB_HOST, PolyConfig::default())
+        let client = PolyClient::new(clob_host, PolyConfig::default())
             .context("Failed to create Polymarket client")?
             .authentication_builder(&signer)
             .authenticate()
@@ -55,83 +88,273 @@ impl ClobClient {
 
         info!("Successfully authenticated with Polymarket API");
 
-        Ok(Self { client, signer, address })
+        Ok(Self {
+            client,
+            signer,
+            address,
+            http: reqwest::Client::new(),
+            data_api_host: data_api_host.trim_end_matches('/').to_string(),
+            gamma_host: gamma_host.trim_end_matches('/').to_string(),
+        })
     }
 
-    /// Get wallet address
-    pub fn address(&self) -> &str {
-        &self.address
+    /// On-chain CTF access for merges and redemptions, signing with this client's wallet
+    pub fn ctf_client(&self, config: CtfConfig) -> Result<CtfClient> {
+        info!("Connecting to CTF via {}", config.rpc_url);
+        CtfClient::new(&self.signer, config)
     }
 
-    /// Get all active markets
-    pub async fn get_markets(&self) -> Result<Vec<Market>> {
-        info!("Fetching markets from API...");
+    /// Build and sign a price-capped limit order for `size` shares
+    ///
+    /// With FOK (always used for arbitrage legs) the order either fills at
+    /// or better than `price` or not at all.
+    async fn build_and_sign_order(
+        &self,
+        request: &OrderRequest,
+        order_type: OrderType,
+    ) -> Result<polymarket_client_sdk::clob::types::SignedOrder> {
+        let OrderRequest { token_id, price, size, side, .. } = request;
+        let order_type = match order_type {
+            OrderType::Gtc => PolyOrderType::GTC,
+            OrderType::Fok => PolyOrderType::FOK,
+            OrderType::Ioc => PolyOrderType::FAK,
+        };
+
+        let order = self.client
+            .limit_order()
+            .token_id(token_id)
+            .price(*price)
+            .size(size.round_dp_with_strategy(2, RoundingStrategy::ToZero))
+            .side(to_poly_side(*side))
+            .order_type(order_type)
+            .build()
+            .await
+            .context("Failed to build limit order")?;
+
+        // Sign the order
+        let signed = self.client
+            .sign(&self.signer, order)
+            .await
+            .context("Failed to sign order")?;
+
+        Ok(signed)
+    }
 
+    /// Convert SDK response to our OrderResponse type
+    /// Note: post_order returns Vec<PostOrderResponse> but we only send one order
+    fn convert_response(
+        &self,
+        response: std::result::Result<Vec<PostOrderResponse>, PolyError>,
+        side_name: &str,
+    ) -> OrderResponse {
+        match response {
+            Ok(responses) => {
+                // We send one order at a time, so expect one response
+                if let Some(resp) = responses.into_iter().next() {
+                    if resp.success {
+                        // Only FOK acceptance means a full fill; report what was matched
+                        info!(
+                            "{} order accepted: gave {} for {} (order_id={})",
+                            side_name,
+                            resp.making_amount,
+                            resp.taking_amount,
+                            resp.order_id
+                        );
+                    } else {
+                        warn!(
+                            "{} order failed: {:?}",
+                            side_name,
+                            resp.error_msg
+                        );
+                    }
+                    OrderResponse {
+                        success: resp.success,
+                        error_msg: resp.error_msg,
+                        order_id: Some(resp.order_id),
+                        transaction_hashes: if resp.transaction_hashes.is_empty() {
+                            None
+                        } else {
+                            Some(resp.transaction_hashes)
+                        },
+                        making_amount: Some(resp.making_amount),
+                        taking_amount: Some(resp.taking_amount),
+                    }
+                } else {
+                    error!("{} order returned empty response", side_name);
+                    OrderResponse {
+                        success: false,
+                        error_msg: Some("Empty response from server".to_string()),
+                        order_id: None,
+                        transaction_hashes: None,
+                        making_amount: None,
+                        taking_amount: None,
+                    }
+                }
+            }
+            Err(e) => {
+                error!("{} order error: {}", side_name, e);
+                OrderResponse {
+                    success: false,
+                    error_msg: Some(e.to_string()),
+                    order_id: None,
+                    transaction_hashes: None,
+                    making_amount: None,
+                    taking_amount: None,
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Exchange for ClobClient {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Actively traded markets from the `sampling_markets` endpoint
+    async fn get_markets_page(&self, cursor: Option<&str>) -> Result<MarketsResponse> {
         // Use sampling_markets which returns actively traded markets
-        let markets_response = self.client.sampling_markets(None).await
+        let page = self.client.sampling_markets(cursor.map(str::to_string)).await
             .context("Failed to fetch sampling markets")?;
 
-        info!("API returned {} markets in this page", markets_response.data.len());
+        debug!("API returned {} markets in this page", page.data.len());
 
-        // Convert SDK response to our types (Page has .data field)
         // Filter to only include active, non-closed markets accepting orders
-        let markets: Vec<Market> = markets_response.data
+        let data: Vec<Market> = page.data
             .into_iter()
             .filter(|m| m.active && !m.closed && m.accepting_orders)
-            .map(|m| Market {
-                condition_id: m.condition_id,
-                question_id: m.question_id,
-                tokens: m.tokens.into_iter().map(|t| Token {
-                    token_id: t.token_id,
-                    outcome: t.outcome,
-                    price: Some(t.price),
-                    winner: t.winner,
-                }).collect(),
-                minimum_order_size: m.minimum_order_size,
-                minimum_tick_size: m.minimum_tick_size,
-                description: Some(m.description),
-                category: None, // SDK doesn't have this field directly
-                end_date_iso: m.end_date_iso.map(|d: DateTime<Utc>| d.to_rfc3339()),
-                game_start_time: m.game_start_time.map(|d: DateTime<Utc>| d.to_rfc3339()),
-                question: Some(m.question),
-                market_slug: Some(m.market_slug),
-                active: m.active,
-                closed: m.closed,
-                accepting_orders: m.accepting_orders,
-            })
+            .map(convert_market)
             .collect();
 
-        info!("Fetched {} active markets (filtered from response)", markets.len());
+        let next_cursor = Some(page.next_cursor).filter(|c| !c.is_empty() && c != END_CURSOR);
+        Ok(MarketsResponse { data, next_cursor })
+    }
+
+    async fn get_market(&self, condition_id: &str) -> Result<Market> {
+        let market = self.client.market(condition_id).await
+            .with_context(|| format!("Failed to fetch market {}", condition_id))?;
+        Ok(convert_market(market))
+    }
+
+    /// Slugs are resolved to condition ids through the Gamma API, which knows
+    /// markets as soon as they're created; the markets themselves come from the CLOB
+    async fn get_markets_by_slug(&self, slugs: &[String]) -> Result<Vec<Market>> {
+        let mut condition_ids = Vec::with_capacity(slugs.len());
+
+        for batch in slugs.chunks(GAMMA_BATCH_SIZE) {
+            let mut query: Vec<(&str, &str)> = batch.iter().map(|slug| ("slug", slug.as_str())).collect();
+            let limit = batch.len().to_string();
+            query.push(("limit", &limit));
+
+            let markets: Vec<GammaMarket> = self.http
+                .get(format!("{}/markets", self.gamma_host))
+                .query(&query)
+                .send()
+                .await
+                .context("Failed to look up markets by slug")?
+                .error_for_status()
+                .context("Failed to look up markets by slug")?
+                .json()
+                .await
+                .context("Failed to parse markets by slug")?;
+
+            condition_ids.extend(markets.into_iter().map(|m| m.condition_id));
+        }
+
+        let results = futures_util::future::join_all(
+            condition_ids.iter().map(|id| self.get_market(id))
+        ).await;
+
+        let mut markets = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                Ok(market) => markets.push(market),
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+
+        debug!("Found {} of {} markets by slug", markets.len(), slugs.len());
         Ok(markets)
     }
 
-    /// Get crypto markets (filter by question for crypto-related content)
-    pub async fn get_crypto_markets(&self) -> Result<Vec<Market>> {
-        let markets = self.get_markets().await?;
+    /// From the Gamma API, one request per group in parallel; groups that
+    /// fail to load are left out
+    async fn get_group_members(&self, group_ids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+        let results = futures_util::future::join_all(group_ids.iter().map(|group_id| async move {
+            let markets: Vec<GammaMarket> = self.http
+                .get(format!("{}/markets", self.gamma_host))
+                .query(&[
+                    ("neg_risk_market_id", group_id.as_str()),
+                    ("closed", "false"),
+                    ("limit", "500"),
+                ])
+                .send()
+                .await
+                .with_context(|| format!("Failed to fetch members of group {}", group_id))?
+                .error_for_status()
+                .with_context(|| format!("Failed to fetch members of group {}", group_id))?
+                .json()
+                .await
+                .with_context(|| format!("Failed to parse members of group {}", group_id))?;
+
+            let condition_ids: Vec<String> = markets
+                .into_iter()
+                .filter(|m| m.neg_risk_market_id.as_deref() == Some(group_id.as_str()))
+                .map(|m| m.condition_id)
+                .collect();
+            anyhow::Ok((group_id.clone(), condition_ids))
+        })).await;
+
+        let mut members = HashMap::with_capacity(results.len());
+        for result in results {
+            match result {
+                Ok((group_id, condition_ids)) if !condition_ids.is_empty() => {
+                    members.insert(group_id, condition_ids);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+
+        debug!("Fetched members of {} of {} groups", members.len(), group_ids.len());
+        Ok(members)
+    }
+
+    /// From the Gamma API, which the CLOB doesn't mirror these fields to
+    async fn get_market_stats(&self, condition_ids: &[String]) -> Result<Vec<MarketStats>> {
+        let mut stats = Vec::with_capacity(condition_ids.len());
+
+        for batch in condition_ids.chunks(GAMMA_BATCH_SIZE) {
+            let mut query: Vec<(&str, &str)> = batch.iter().map(|id| ("condition_ids", id.as_str())).collect();
+            let limit = batch.len().to_string();
+            query.push(("limit", &limit));
+
+            let markets: Vec<GammaMarket> = self.http
+                .get(format!("{}/markets", self.gamma_host))
+                .query(&query)
+                .send()
+                .await
+                .context("Failed to fetch market stats")?
+                .error_for_status()
+                .context("Failed to fetch market stats")?
+                .json()
+                .await
+                .context("Failed to parse market stats")?;
+
+            stats.extend(markets.into_iter().map(|m| MarketStats {
+                condition_id: m.condition_id,
+                liquidity: m.liquidity_num.unwrap_or_default(),
+                volume: m.volume_num.unwrap_or_default(),
+            }));
+        }
 
-        Ok(markets
-            .into_iter()
-            .filter(|m| {
-                m.active
-                    && !m.closed
-                    && m.accepting_orders
-                    && m.question
-                        .as_ref()
-                        .map(|q| {
-                            let q_lower = q.to_lowercase();
-                            q_lower.contains("bitcoin")
-                                || q_lower.contains("btc")
-                                || q_lower.contains("ethereum")
-                                || q_lower.contains("eth")
-                                || q_lower.contains("crypto")
-                        })
-                        .unwrap_or(false)
-            })
-            .collect())
+        debug!("Fetched stats for {} of {} markets", stats.len(), condition_ids.len());
+        Ok(stats)
     }
 
-    /// Get multiple order books (fetch in parallel)
-    pub async fn get_order_books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>> {
+    /// Fetched individually and in parallel
+    async fn get_order_books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>> {
         if token_ids.is_empty() {
             return Ok(Vec::new());
         }
@@ -195,206 +418,167 @@ impl ClobClient {
         Ok(books)
     }
 
-    /// Execute arbitrage: buy YES + NO simultaneously using parallel execution
-    ///
-    /// This is the critical path - both orders are submitted in parallel using tokio::join!
-    /// to minimize the time window where prices could move against us.
-    pub async fn execute_arbitrage(
-        &self,
-        opportunity: &ArbitrageOpportunity,
-        size: Decimal,
-    ) -> Result<(OrderResponse, OrderResponse)> {
-        info!(
-            "Executing PARALLEL arbitrage on market {} - profit per share: ${}",
-            opportunity.market_id,
-            opportunity.profit_per_share
-        );
-
-        // Calculate the USDC amount based on size and prices
-        // We're buying `size` shares of each outcome
-        let yes_usdc = size * opportunity.yes_ask_price;
-        let no_usdc = size * opportunity.no_ask_price;
-
-        info!(
-            "Placing parallel orders: YES ${} @ {} | NO ${} @ {}",
-            yes_usdc, opportunity.yes_ask_price,
-            no_usdc, opportunity.no_ask_price
-        );
-
-        // Build both orders in parallel (order building is async)
-        let (yes_order_result, no_order_result) = tokio::join!(
-            self.build_and_sign_order(
-                &opportunity.yes_token_id,
-                yes_usdc,
-                PolySide::Buy,
-            ),
-            self.build_and_sign_order(
-                &opportunity.no_token_id,
-                no_usdc,
-                PolySide::Buy,
-            )
-        );
-
-        // Check if both orders were built successfully
-        let yes_signed = yes_order_result.context("Failed to build YES order")?;
-        let no_signed = no_order_result.context("Failed to build NO order")?;
-
-        info!("Both orders built and signed, submitting in parallel...");
-
-        // Submit both orders in parallel - this is the critical section!
-        let (yes_response, no_response) = tokio::join!(
-            self.client.post_order(yes_signed),
-            self.client.post_order(no_signed)
-        );
-
-        // Convert responses
-        let yes_result = self.convert_response(yes_response, "YES");
-        let no_result = self.convert_response(no_response, "NO");
-
-        // Log results
-        if yes_result.success && no_result.success {
-            info!("Both orders submitted successfully!");
-        } else {
-            warn!(
-                "Order submission results - YES: {} | NO: {}",
-                if yes_result.success { "OK" } else { "FAILED" },
-                if no_result.success { "OK" } else { "FAILED" }
-            );
-
-            // Handle partial fill scenario
-            if yes_result.success != no_result.success {
-                self.handle_partial_execution(&yes_result, &no_result, opportunity).await;
-            }
+    async fn post_orders(&self, requests: &[OrderRequest], order_type: OrderType) -> Result<Vec<OrderResponse>> {
+        // Build every order in parallel, bailing before submission if any fails
+        let built = futures_util::future::join_all(
+            requests.iter().map(|request| self.build_and_sign_order(request, order_type)),
+        )
+        .await;
+
+        let mut signed = Vec::with_capacity(built.len());
+        for (request, result) in requests.iter().zip(built) {
+            signed.push(result.with_context(|| format!("Failed to build {} order", request.label))?);
         }
 
-        Ok((yes_result, no_result))
-    }
+        if signed.len() > 1 {
+            info!("All {} orders built and signed, submitting in parallel...", signed.len());
+        }
 
-    /// Build and sign a market order
-    async fn build_and_sign_order(
-        &self,
-        token_id: &str,
-        usdc_amount: Decimal,
-        side: PolySide,
-    ) -> Result<polymarket_client_sdk::clob::types::SignedOrder> {
-        // Build a market order (FOK - Fill or Kill for immediate execution)
-        let amount = Amount::usdc(usdc_amount)
-            .context("Failed to create USDC amount")?;
+        let responses = futures_util::future::join_all(
+            signed.into_iter().map(|order| self.client.post_order(order)),
+        )
+        .await;
 
-        let order = self.client
-            .market_order()
-            .token_id(token_id)
-            .amount(amount)
-            .side(side)
-            .build()
-            .await
-            .context("Failed to build market order")?;
+        Ok(requests
+            .iter()
+            .zip(responses)
+            .map(|(request, response)| self.convert_response(response, &request.label))
+            .collect())
+    }
 
-        // Sign the order
-        let signed = self.client
-            .sign(&self.signer, order)
-            .await
-            .context("Failed to sign order")?;
+    async fn cancel_all_orders(&self) -> Result<usize> {
+        let response = self.client.cancel_all_orders().await
+            .context("Failed to cancel open orders")?;
 
-        Ok(signed)
+        for (order_id, reason) in &response.not_canceled {
+            warn!("Order {} not cancelled: {}", order_id, reason);
+        }
+        Ok(response.canceled.len())
     }
 
-    /// Convert SDK response to our OrderResponse type
-    /// Note: post_order returns Vec<PostOrderResponse> but we only send one order
-    fn convert_response(
-        &self,
-        response: std::result::Result<Vec<PostOrderResponse>, PolyError>,
-        side_name: &str,
-    ) -> OrderResponse {
-        match response {
-            Ok(responses) => {
-                // We send one order at a time, so expect one response
-                if let Some(resp) = responses.into_iter().next() {
-                    if resp.success {
-                        info!(
-                            "{} order filled: {} shares @ order_id={}",
-                            side_name,
-                            resp.making_amount,
-                            resp.order_id
-                        );
-                    } else {
-                        warn!(
-                            "{} order failed: {:?}",
-                            side_name,
-                            resp.error_msg
-                        );
-                    }
-                    OrderResponse {
-                        success: resp.success,
-                        error_msg: resp.error_msg,
-                        order_id: Some(resp.order_id),
-                        transaction_hashes: if resp.transaction_hashes.is_empty() {
-                            None
-                        } else {
-                            Some(resp.transaction_hashes)
-                        },
-                    }
-                } else {
-                    error!("{} order returned empty response", side_name);
-                    OrderResponse {
-                        success: false,
-                        error_msg: Some("Empty response from server".to_string()),
-                        order_id: None,
-                        transaction_hashes: None,
-                    }
-                }
-            }
-            Err(e) => {
-                error!("{} order error: {}", side_name, e);
-                OrderResponse {
-                    success: false,
-                    error_msg: Some(e.to_string()),
-                    order_id: None,
-                    transaction_hashes: None,
-                }
+    async fn get_funding(&self) -> Result<Funding> {
+        let request = BalanceAllowanceRequestBuilder::default()
+            .asset_type(AssetType::Collateral)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build balance request: {}", e))?;
+
+        let response = self.client.balance_allowance(&request).await
+            .context("Failed to fetch USDC balance and allowance")?;
+
+        // Each market type trades through its own contracts, so keep their allowances apart
+        let allowance_of = |contract: &str| {
+            let address = Address::from_str(contract).expect("valid exchange address");
+            response.allowances
+                .get(&address)
+                .map(|a| parse_allowance(a))
+                .unwrap_or(Decimal::ZERO)
+        };
+        let neg_risk_allowance = allowance_of(NEG_RISK_EXCHANGE_ADDRESS)
+            .min(allowance_of(NEG_RISK_ADAPTER_ADDRESS));
+
+        Ok(Funding {
+            balance: from_usdc_units(response.balance),
+            allowance: from_usdc_units(allowance_of(CTF_EXCHANGE_ADDRESS)),
+            neg_risk_allowance: from_usdc_units(neg_risk_allowance),
+        })
+    }
+
+    /// Fetched from the data API, which pages by offset
+    async fn get_account_positions(&self) -> Result<Vec<AccountPosition>> {
+        let mut positions = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let page: Vec<AccountPosition> = self.http
+                .get(format!("{}/positions", self.data_api_host))
+                .query(&[
+                    ("user", self.address.as_str()),
+                    ("limit", &POSITIONS_PAGE_SIZE.to_string()),
+                    ("offset", &offset.to_string()),
+                ])
+                .send()
+                .await
+                .context("Failed to fetch account positions")?
+                .error_for_status()
+                .context("Positions request rejected")?
+                .json()
+                .await
+                .context("Failed to parse account positions")?;
+
+            let count = page.len();
+            positions.extend(page);
+            if count < POSITIONS_PAGE_SIZE {
+                break;
             }
+            offset += count;
         }
+
+        debug!("Account holds {} token positions", positions.len());
+        Ok(positions)
     }
+}
 
-    /// Handle partial execution scenario (one order succeeded, one failed)
-    ///
-    /// In arbitrage, if only one side executes, we're exposed to market risk.
-    /// This function attempts to mitigate by either:
-    /// 1. Retrying the failed order
-    /// 2. Unwinding the successful order if retry fails
-    async fn handle_partial_execution(
-        &self,
-        yes_result: &OrderResponse,
-        no_result: &OrderResponse,
-        opportunity: &ArbitrageOpportunity,
-    ) {
-        warn!("PARTIAL EXECUTION DETECTED - attempting recovery");
-
-        // Determine which side failed
-        let (failed_side, failed_token) = if yes_result.success {
-            ("NO", &opportunity.no_token_id)
-        } else {
-            ("YES", &opportunity.yes_token_id)
-        };
+/// Convert our order side to the SDK's
+fn to_poly_side(side: Side) -> PolySide {
+    match side {
+        Side::Buy => PolySide::Buy,
+        Side::Sell => PolySide::Sell,
+    }
+}
+
+/// The fields of a Gamma API market the client reads
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GammaMarket {
+    condition_id: String,
+    #[serde(default, rename = "negRiskMarketID")]
+    neg_risk_market_id: Option<String>,
+    #[serde(default)]
+    liquidity_num: Option<Decimal>,
+    #[serde(default)]
+    volume_num: Option<Decimal>,
+}
+
+/// Convert an SDK market to our Market type
+fn convert_market(m: MarketResponse) -> Market {
+    Market {
+        condition_id: m.condition_id,
+        question_id: m.question_id,
+        tokens: m.tokens.into_iter().map(|t| Token {
+            token_id: t.token_id,
+            outcome: t.outcome,
+            price: Some(t.price),
+            winner: t.winner,
+        }).collect(),
+        minimum_order_size: m.minimum_order_size,
+        minimum_tick_size: m.minimum_tick_size,
+        taker_base_fee: m.taker_base_fee,
+        description: Some(m.description),
+        category: None, // SDK doesn't have this field directly
+        tags: m.tags,
+        end_date_iso: m.end_date_iso.map(|d: DateTime<Utc>| d.to_rfc3339()),
+        game_start_time: m.game_start_time.map(|d: DateTime<Utc>| d.to_rfc3339()),
+        question: Some(m.question),
+        market_slug: Some(m.market_slug),
+        active: m.active,
+        closed: m.closed,
+        accepting_orders: m.accepting_orders,
+        neg_risk: m.neg_risk,
+        neg_risk_market_id: Some(m.neg_risk_market_id).filter(|id| !id.is_empty()),
+    }
+}
 
-        warn!(
-            "{} order failed, {} order succeeded. Manual intervention may be required.",
-            failed_side,
-            if failed_side == "NO" { "YES" } else { "NO" }
-        );
-
-        // Log the situation for manual review
-        error!(
-            "ALERT: Partial arbitrage execution on market {}. {} position is unhedged!",
-            opportunity.market_id,
-            if failed_side == "NO" { "YES" } else { "NO" }
-        );
-
-        // In a production system, we would:
-        // 1. Retry the failed order with updated prices
-        // 2. If retry fails, place a market order to unwind the successful position
-        // 3. Send alerts to monitoring systems
-        //
-        // For now, we just log the issue prominently
+/// Parse an allowance in base units; unlimited approvals (2^256 - 1)
+/// overflow Decimal and are treated as the maximum
+fn parse_allowance(raw: &str) -> Decimal {
+    match Decimal::from_str(raw) {
+        Ok(allowance) => allowance,
+        Err(_) if !raw.is_empty() && raw.chars().all(|c| c.is_ascii_digit()) => Decimal::MAX,
+        Err(_) => Decimal::ZERO,
     }
 }
+
+/// Convert USDC base units to dollars
+fn from_usdc_units(units: Decimal) -> Decimal {
+    units / Decimal::from(10u64.pow(USDC_DECIMALS))
+}
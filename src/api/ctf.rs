@@ -0,0 +1,321 @@
+//! On-chain Conditional Tokens (CTF) operations
+//! Merges complete YES+NO sets and redeems resolved positions back into USDC collateral
+
+use alloy::network::EthereumWallet;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer as AlloySigner;
+use alloy::sol;
+use anyhow::{Context, Result};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use super::types::CtfReceipt;
+
+/// Polygon mainnet JSON-RPC endpoint
+pub const POLYGON_RPC_URL: &str = "https://polygon-rpc.com";
+
+/// Conditional Tokens Framework contract on Polygon
+pub const CTF_ADDRESS: &str = "0x4D97DCd97eC945f40cF65F87097ACe5EA0476045";
+
+/// USDC.e collateral used by Polymarket markets on Polygon
+pub const COLLATERAL_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+/// Neg-risk adapter that wraps CTF for neg-risk markets on Polygon
+pub const NEG_RISK_ADAPTER_ADDRESS: &str = "0xd91E80cF2E7be2e162c6513ceD06f1dD0dA35296";
+
+/// CTF Exchange that fills orders on standard markets
+pub const CTF_EXCHANGE_ADDRESS: &str = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E";
+
+/// Exchange that fills orders on neg-risk markets, alongside the adapter
+pub const NEG_RISK_EXCHANGE_ADDRESS: &str = "0xC5d563A36AE78145C45a50134d48A1215220f80a";
+
+/// USDC and outcome tokens both use 6 decimals on chain
+const TOKEN_DECIMALS: u32 = 6;
+
+/// Pause between balance checks while waiting for fills to settle
+const SETTLE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+sol! {
+    #[sol(rpc)]
+    interface IConditionalTokens {
+        function mergePositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] calldata partition,
+            uint256 amount
+        ) external;
+
+        function redeemPositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] calldata indexSets
+        ) external;
+
+        function balanceOf(address owner, uint256 id) external view returns (uint256);
+
+        function isApprovedForAll(address owner, address operator) external view returns (bool);
+
+        function setApprovalForAll(address operator, bool approved) external;
+    }
+
+    #[sol(rpc)]
+    interface INegRiskAdapter {
+        function mergePositions(bytes32 conditionId, uint256 amount) external;
+
+        function redeemPositions(bytes32 conditionId, uint256[] calldata amounts) external;
+    }
+}
+
+/// Contract addresses and RPC endpoint for CTF operations
+///
+/// Defaults target Polygon mainnet; override them to run against a local
+/// chain (e.g. an anvil fork) with the same contracts deployed.
+#[derive(Debug, Clone)]
+pub struct CtfConfig {
+    pub rpc_url: String,
+    pub ctf: Address,
+    pub collateral: Address,
+    pub neg_risk_adapter: Address,
+    /// How long to wait for traded tokens to settle on chain before merging
+    pub settle_timeout: Duration,
+}
+
+impl Default for CtfConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: POLYGON_RPC_URL.to_string(),
+            ctf: Address::from_str(CTF_ADDRESS).unwrap(),
+            collateral: Address::from_str(COLLATERAL_ADDRESS).unwrap(),
+            neg_risk_adapter: Address::from_str(NEG_RISK_ADAPTER_ADDRESS).unwrap(),
+            settle_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Sends CTF transactions from the trading wallet
+pub struct CtfClient {
+    provider: DynProvider,
+    config: CtfConfig,
+    owner: Address,
+}
+
+impl CtfClient {
+    /// Connect to the configured RPC, signing with the trading wallet
+    pub fn new(signer: &PrivateKeySigner, config: CtfConfig) -> Result<Self> {
+        // Let the RPC supply the chain id so the same key works on a local chain
+        let signer = signer.clone().with_chain_id(None);
+        let owner = signer.address();
+
+        let url = config.rpc_url.parse().context("Invalid RPC URL")?;
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(signer))
+            .connect_http(url)
+            .erased();
+
+        Ok(Self { provider, config, owner })
+    }
+
+    /// The wallet's balance of each outcome token, in shares
+    ///
+    /// Token ids are CTF position ids, so these are the ERC-1155 balances
+    /// actually held, whatever the exchange or the ledger reports.
+    pub async fn token_balances(&self, token_ids: &[&str]) -> Result<Vec<Decimal>> {
+        let ctf = IConditionalTokens::new(self.config.ctf, &self.provider);
+        let mut balances = Vec::with_capacity(token_ids.len());
+        for token_id in token_ids {
+            let id = U256::from_str_radix(token_id, 10).context("Invalid token id")?;
+            let units = ctf
+                .balanceOf(self.owner, id)
+                .call()
+                .await
+                .with_context(|| format!("Failed to fetch balance of {}", token_id))?;
+            balances.push(from_token_units(units)?);
+        }
+        Ok(balances)
+    }
+
+    /// Balances of the given tokens once each reaches `shares`, or as they
+    /// stand after `settle_timeout`
+    ///
+    /// Exchange fills settle on chain a few seconds after they're reported.
+    pub async fn settled_balances(&self, token_ids: &[&str], shares: Decimal) -> Result<Vec<Decimal>> {
+        let deadline = tokio::time::Instant::now() + self.config.settle_timeout;
+        loop {
+            let balances = self.token_balances(token_ids).await?;
+            if balances.iter().all(|b| *b >= shares) || tokio::time::Instant::now() >= deadline {
+                return Ok(balances);
+            }
+            debug!("Waiting for {} shares to settle, holding {:?}", shares, balances);
+            tokio::time::sleep(SETTLE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Merge `shares` complete sets of a binary market back into USDC
+    ///
+    /// Neg-risk markets hold collateral in the adapter, so their sets are
+    /// merged through it (after approving it to move our outcome tokens).
+    pub async fn merge_positions(
+        &self,
+        condition_id: &str,
+        shares: Decimal,
+        neg_risk: bool,
+    ) -> Result<CtfReceipt> {
+        let condition = B256::from_str(condition_id).context("Invalid condition id")?;
+        let shares = shares.round_dp_with_strategy(TOKEN_DECIMALS, RoundingStrategy::ToZero);
+        let amount = to_token_units(shares)?;
+
+        info!(
+            "Merging {} sets of {} via {}",
+            shares,
+            condition_id,
+            if neg_risk { "neg-risk adapter" } else { "CTF" }
+        );
+
+        let ctf = IConditionalTokens::new(self.config.ctf, &self.provider);
+
+        let pending = if neg_risk {
+            self.ensure_adapter_approval(&ctf).await?;
+            INegRiskAdapter::new(self.config.neg_risk_adapter, &self.provider)
+                .mergePositions(condition, amount)
+                .send()
+                .await
+                .context("Failed to send neg-risk merge")?
+        } else {
+            // Binary partition: outcome slot 0 (index set 1) and slot 1 (index set 2)
+            ctf.mergePositions(
+                self.config.collateral,
+                B256::ZERO,
+                condition,
+                vec![U256::from(1), U256::from(2)],
+                amount,
+            )
+            .send()
+            .await
+            .context("Failed to send merge")?
+        };
+
+        let receipt = pending
+            .get_receipt()
+            .await
+            .context("Failed to get merge receipt")?;
+
+        let tx_hash = format!("{:?}", receipt.transaction_hash);
+        if !receipt.status() {
+            anyhow::bail!("Merge transaction {} reverted", tx_hash);
+        }
+
+        Ok(CtfReceipt {
+            condition_id: condition_id.to_string(),
+            amount: shares,
+            tx_hash,
+            gas_used: receipt.gas_used,
+        })
+    }
+
+    /// Redeem our outcome tokens of a resolved market for USDC
+    ///
+    /// `amounts` holds the shares we own per outcome slot, in the market's
+    /// token order. CTF redeems the full balance of every slot; the neg-risk
+    /// adapter needs the amounts explicitly. `payout` is the USDC the winning
+    /// shares are worth and is only used for the receipt.
+    pub async fn redeem_positions(
+        &self,
+        condition_id: &str,
+        amounts: &[Decimal],
+        payout: Decimal,
+        neg_risk: bool,
+    ) -> Result<CtfReceipt> {
+        let condition = B256::from_str(condition_id).context("Invalid condition id")?;
+
+        info!(
+            "Redeeming {} via {} (payout ${})",
+            condition_id,
+            if neg_risk { "neg-risk adapter" } else { "CTF" },
+            payout
+        );
+
+        let ctf = IConditionalTokens::new(self.config.ctf, &self.provider);
+
+        let pending = if neg_risk {
+            self.ensure_adapter_approval(&ctf).await?;
+            let amounts = amounts
+                .iter()
+                .map(|a| to_token_units(a.round_dp_with_strategy(TOKEN_DECIMALS, RoundingStrategy::ToZero)))
+                .collect::<Result<Vec<_>>>()?;
+            INegRiskAdapter::new(self.config.neg_risk_adapter, &self.provider)
+                .redeemPositions(condition, amounts)
+                .send()
+                .await
+                .context("Failed to send neg-risk redemption")?
+        } else {
+            // One index set per outcome slot: 1, 2, 4, ...
+            let index_sets = (0..amounts.len()).map(|i| U256::from(1u64 << i)).collect();
+            ctf.redeemPositions(self.config.collateral, B256::ZERO, condition, index_sets)
+                .send()
+                .await
+                .context("Failed to send redemption")?
+        };
+
+        let receipt = pending
+            .get_receipt()
+            .await
+            .context("Failed to get redemption receipt")?;
+
+        let tx_hash = format!("{:?}", receipt.transaction_hash);
+        if !receipt.status() {
+            anyhow::bail!("Redemption transaction {} reverted", tx_hash);
+        }
+
+        Ok(CtfReceipt {
+            condition_id: condition_id.to_string(),
+            amount: payout,
+            tx_hash,
+            gas_used: receipt.gas_used,
+        })
+    }
+
+    /// Approve the neg-risk adapter to move our CTF tokens, once per wallet
+    async fn ensure_adapter_approval(&self, ctf: &IConditionalTokens::IConditionalTokensInstance<&DynProvider>) -> Result<()> {
+        let operator = self.config.neg_risk_adapter;
+        let approved = ctf
+            .isApprovedForAll(self.owner, operator)
+            .call()
+            .await
+            .context("Failed to check adapter approval")?;
+        if approved {
+            return Ok(());
+        }
+
+        debug!("Approving neg-risk adapter {} for CTF tokens", operator);
+        ctf.setApprovalForAll(operator, true)
+            .send()
+            .await
+            .context("Failed to send adapter approval")?
+            .get_receipt()
+            .await
+            .context("Failed to get approval receipt")?;
+        Ok(())
+    }
+}
+
+/// Convert 6-decimal on-chain units to a share/USDC amount
+fn from_token_units(units: U256) -> Result<Decimal> {
+    let units = i128::try_from(units).context("Amount out of range")?;
+    Ok(Decimal::from_i128_with_scale(units, TOKEN_DECIMALS))
+}
+
+/// Convert a share/USDC amount to 6-decimal on-chain units
+fn to_token_units(amount: Decimal) -> Result<U256> {
+    let units = (amount * Decimal::from(10u64.pow(TOKEN_DECIMALS)))
+        .trunc()
+        .to_u128()
+        .context("Amount out of range")?;
+    Ok(U256::from(units))
+}
@@ -0,0 +1,53 @@
+//! Exchange abstraction
+//! Market listing, order books and order entry behind a trait, so execution
+//! logic runs the same against the live CLOB or an in-memory mock
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use super::types::*;
+
+/// A venue the bot can read markets from and trade on
+#[async_trait]
+pub trait Exchange: Send + Sync {
+    /// Wallet address orders are placed from
+    fn address(&self) -> &str;
+
+    /// One page of the active markets accepting orders, from `cursor` (the
+    /// first page when `None`)
+    async fn get_markets_page(&self, cursor: Option<&str>) -> Result<MarketsResponse>;
+
+    /// A single market by condition id, including closed/resolved ones
+    async fn get_market(&self, condition_id: &str) -> Result<Market>;
+
+    /// Markets by slug, including ones not yet in the listing; unknown slugs are left out
+    async fn get_markets_by_slug(&self, slugs: &[String]) -> Result<Vec<Market>>;
+
+    /// Condition ids of every open market in each neg-risk group, keyed by
+    /// `neg_risk_market_id`, including markets missing from the listing;
+    /// unknown groups are left out
+    async fn get_group_members(&self, group_ids: &[String]) -> Result<HashMap<String, Vec<String>>>;
+
+    /// Liquidity and volume of the given markets; unknown markets are left out
+    async fn get_market_stats(&self, condition_ids: &[String]) -> Result<Vec<MarketStats>>;
+
+    /// Order books for the given tokens; books that fail to load are left out
+    async fn get_order_books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>>;
+
+    /// Sign and submit limit orders in parallel, one response per request in order
+    ///
+    /// Fails without submitting anything if any order can't be built, so a
+    /// signing problem never leaves one leg of an arbitrage on the book.
+    /// Once submitted, rejections are reported in the responses.
+    async fn post_orders(&self, requests: &[OrderRequest], order_type: OrderType) -> Result<Vec<OrderResponse>>;
+
+    /// Cancel every resting order for this wallet, returning how many were cancelled
+    async fn cancel_all_orders(&self) -> Result<usize>;
+
+    /// USDC balance and exchange allowance of the wallet
+    async fn get_funding(&self) -> Result<Funding>;
+
+    /// Every token balance the account holds
+    async fn get_account_positions(&self) -> Result<Vec<AccountPosition>>;
+}
@@ -0,0 +1,1015 @@
+//! Arbitrage execution on top of an exchange
+//! Sizes, submits and recovers arbitrage legs while keeping the position
+//! ledger, funding cache, journal and recorder up to date
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use super::ctf::CtfClient;
+use super::exchange::Exchange;
+use super::types::*;
+use crate::fees::CostModel;
+use crate::journal::Journal;
+use crate::positions::{Discrepancy, PositionLedger};
+use crate::recording::Recorder;
+use crate::risk::scale_to_budget;
+
+/// How long a fetched USDC balance/allowance is trusted without a fill
+const FUNDING_TTL: Duration = Duration::from_secs(30);
+
+/// Markets listed so far and where the listing left off
+#[derive(Default)]
+struct MarketCatalog {
+    /// In listing order
+    markets: Vec<Market>,
+    /// Cursor of the last page read (or the first unread one, if the page
+    /// cap stopped the listing), where an incremental refresh resumes
+    resume_cursor: Option<String>,
+    /// When the listing was last read from the first page
+    listed_at: Option<Instant>,
+    /// Condition ids the last call read from the exchange, rather than
+    /// carried over from an earlier listing
+    confirmed: HashSet<String>,
+}
+
+/// Executes arbitrages against any `Exchange`, live or mock
+pub struct Executor {
+    /// Venue for market data and orders
+    exchange: Arc<dyn Exchange>,
+    /// Limits for recovering one-legged fills
+    recovery: RecoveryPolicy,
+    /// Order type and price tolerance for arbitrage legs
+    orders: OrderPolicy,
+    /// Page cap and full-refresh interval for the markets listing
+    listing: MarketListing,
+    /// Every market listed so far, for incremental refreshes
+    catalog: parking_lot::Mutex<MarketCatalog>,
+    /// On-chain CTF access for merging sets, if enabled
+    ctf: Option<CtfClient>,
+    /// Tokens held, updated from every fill, merge and redemption
+    ledger: PositionLedger,
+    /// Last fetched USDC balance/allowance, cleared whenever funds move
+    funding: parking_lot::RwLock<Option<(Instant, Funding)>>,
+    /// Journal every submitted order is recorded to, if any
+    journal: Option<Arc<Journal>>,
+    /// Recorder every fetched order book is written to, if any
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl Executor {
+    pub fn new(exchange: Arc<dyn Exchange>) -> Self {
+        Self {
+            exchange,
+            recovery: RecoveryPolicy::default(),
+            orders: OrderPolicy::default(),
+            listing: MarketListing::default(),
+            catalog: parking_lot::Mutex::new(MarketCatalog::default()),
+            ctf: None,
+            ledger: PositionLedger::new(),
+            funding: parking_lot::RwLock::new(None),
+            journal: None,
+            recorder: None,
+        }
+    }
+
+    /// Set the order type and slippage tolerance used for arbitrage legs
+    pub fn with_order_policy(mut self, orders: OrderPolicy) -> Self {
+        self.orders = orders;
+        self
+    }
+
+    /// Set the limits used when recovering one-legged fills
+    pub fn with_recovery_policy(mut self, recovery: RecoveryPolicy) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Set how many pages of markets are read per refresh and how often in full
+    pub fn with_market_listing(mut self, listing: MarketListing) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// Record every submitted order and its response to `journal`
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Record every fetched order book (and, via the scanner, market list) to `recorder`
+    pub fn with_recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Market data recorder, if recording is enabled
+    pub fn recorder(&self) -> Option<&Arc<Recorder>> {
+        self.recorder.as_ref()
+    }
+
+    /// Enable on-chain merges and redemptions
+    pub fn with_ctf(mut self, ctf: CtfClient) -> Self {
+        self.ctf = Some(ctf);
+        self
+    }
+
+    /// Merge complete YES+NO sets bought by an arbitrage back into USDC
+    ///
+    /// Frees the capital immediately instead of waiting for resolution. Only
+    /// sets the wallet holds on chain are merged, so the receipt can cover
+    /// fewer than `sets`; the rest stay held until resolution.
+    pub async fn merge_complete_sets(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        sets: Decimal,
+    ) -> Result<CtfReceipt> {
+        let ctf = self.ctf.as_ref().context("CTF access is not enabled")?;
+
+        // A merge of tokens the wallet doesn't hold reverts and still costs gas
+        let held = ctf
+            .settled_balances(&[&opportunity.yes_token_id, &opportunity.no_token_id], sets)
+            .await
+            .context("Failed to check token balances before merging")?;
+        let mergeable = held.iter().fold(sets, |sets, balance| sets.min(*balance));
+        if mergeable <= Decimal::ZERO {
+            anyhow::bail!("No complete sets of {} held on chain", opportunity.market_id);
+        }
+        if mergeable < sets {
+            warn!(
+                "Only {} of {} sets of {} held on chain, merging those",
+                mergeable, sets, opportunity.market_id
+            );
+        }
+
+        let receipt = ctf.merge_positions(&opportunity.market_id, mergeable, opportunity.neg_risk)
+            .await
+            .with_context(|| format!("Failed to merge sets for {}", opportunity.market_id))?;
+        self.ledger.record_merge(&opportunity.market_id, receipt.amount);
+        self.invalidate_funding();
+        Ok(receipt)
+    }
+
+    /// Redeem held outcome tokens of a resolved market for USDC
+    pub async fn redeem_positions(
+        &self,
+        market: &Market,
+        amounts: &[Decimal],
+        payout: Decimal,
+    ) -> Result<CtfReceipt> {
+        let ctf = self.ctf.as_ref().context("CTF access is not enabled")?;
+        let receipt = ctf.redeem_positions(&market.condition_id, amounts, payout, market.neg_risk)
+            .await
+            .with_context(|| format!("Failed to redeem {}", market.condition_id))?;
+        self.ledger.record_redemption(&market.condition_id);
+        self.invalidate_funding();
+        Ok(receipt)
+    }
+
+    /// Fetch the wallet's USDC balance and exchange allowance
+    pub async fn refresh_funding(&self) -> Result<Funding> {
+        let funding = self.exchange.get_funding().await?;
+        debug!(
+            "USDC balance ${}, allowance ${} (neg-risk ${})",
+            funding.balance, funding.allowance, funding.neg_risk_allowance
+        );
+
+        *self.funding.write() = Some((Instant::now(), funding.clone()));
+        Ok(funding)
+    }
+
+    /// Cached USDC balance and allowance, refetched once stale
+    pub async fn funding(&self) -> Result<Funding> {
+        if let Some((fetched_at, funding)) = self.funding.read().as_ref() {
+            if fetched_at.elapsed() < FUNDING_TTL {
+                return Ok(funding.clone());
+            }
+        }
+        self.refresh_funding().await
+    }
+
+    /// Largest size up to `size` that the wallet can fund
+    ///
+    /// Buys are limited by the USDC the market's exchange may spend, fees
+    /// included; sells by the sets held in the ledger.
+    pub async fn fundable_size(&self, opportunity: &ArbitrageOpportunity, size: Decimal) -> Result<Decimal> {
+        let fundable = match opportunity.direction {
+            ArbDirection::BuyBoth => {
+                let available = self.funding().await?.available(opportunity.neg_risk);
+                scale_to_budget(size, opportunity.fill_cost(size), available)
+            }
+            ArbDirection::SellBoth => size
+                .min(self.ledger.shares(&opportunity.yes_token_id))
+                .min(self.ledger.shares(&opportunity.no_token_id)),
+        };
+        Ok(fundable)
+    }
+
+    /// Largest basket size up to `size` that spendable USDC can fund, fees included
+    pub async fn fundable_basket_size(&self, opportunity: &BasketOpportunity, size: Decimal) -> Result<Decimal> {
+        let available = self.funding().await?.available(opportunity.neg_risk);
+        Ok(scale_to_budget(size, opportunity.fill_cost(size), available))
+    }
+
+    /// Cancel every resting order for this wallet, returning how many were cancelled
+    pub async fn cancel_all_orders(&self) -> Result<usize> {
+        self.exchange.cancel_all_orders().await
+    }
+
+    /// Drop the cached balance so the next check refetches it
+    fn invalidate_funding(&self) {
+        *self.funding.write() = None;
+    }
+
+    /// Ledger of tokens held by this wallet
+    pub fn positions(&self) -> &PositionLedger {
+        &self.ledger
+    }
+
+    /// Reconcile the ledger against the account's real balances
+    pub async fn reconcile_positions(&self) -> Result<Vec<Discrepancy>> {
+        let actual = self.exchange.get_account_positions().await?;
+        Ok(self.ledger.reconcile(&actual))
+    }
+
+    /// Record a successful order in the ledger
+    ///
+    /// `estimate` is the (shares, USDC) used if the server omitted the amounts.
+    fn record_fill(
+        &self,
+        condition_id: &str,
+        token_id: &str,
+        label: &str,
+        side: Side,
+        response: &OrderResponse,
+        estimate: (Decimal, Decimal),
+    ) {
+        if !response.success {
+            return;
+        }
+        self.invalidate_funding();
+        let (shares, usdc) = estimate;
+        self.ledger.record_fill(
+            condition_id,
+            token_id,
+            label,
+            side,
+            response.share_amount(side).unwrap_or(shares),
+            response.usdc_amount(side).unwrap_or(usdc),
+        );
+    }
+
+    /// Journal an order request alongside its response
+    fn journal_order(&self, opportunity_id: &str, condition_id: &str, request: &OrderRequest, response: &OrderResponse) {
+        if let Some(journal) = &self.journal {
+            journal.record_order(opportunity_id, condition_id, request, response);
+        }
+    }
+
+    /// Get wallet address
+    pub fn address(&self) -> &str {
+        self.exchange.address()
+    }
+
+    /// Get all active markets, following the listing's cursor across pages
+    ///
+    /// The first call, and every call once `full_refresh` has passed, reads
+    /// the listing from the first page. Calls in between resume from the last
+    /// page read, where new markets are appended, and merge what they find
+    /// into the markets already known, so markets on earlier pages keep the
+    /// state they were listed with (see `confirmed_market_ids`). A listing
+    /// longer than `max_pages` carries on from where it stopped on the next call.
+    pub async fn get_markets(&self) -> Result<Vec<Market>> {
+        let resume = {
+            let catalog = self.catalog.lock();
+            match catalog.listed_at {
+                Some(at) if at.elapsed() < self.listing.full_refresh => catalog.resume_cursor.clone(),
+                _ => None,
+            }
+        };
+        let incremental = resume.is_some();
+
+        let (markets, resume_cursor) = self.list_markets(resume).await?;
+
+        let mut catalog = self.catalog.lock();
+        catalog.confirmed = markets.iter().map(|m| m.condition_id.clone()).collect();
+        if incremental {
+            let mut added = 0;
+            for market in markets {
+                match catalog.markets.iter_mut().find(|m| m.condition_id == market.condition_id) {
+                    Some(known) => *known = market,
+                    None => {
+                        catalog.markets.push(market);
+                        added += 1;
+                    }
+                }
+            }
+            info!("Market listing updated: {} new, {} total", added, catalog.markets.len());
+        } else {
+            info!("Listed {} active markets", markets.len());
+            catalog.markets = markets;
+            catalog.listed_at = Some(Instant::now());
+        }
+        if resume_cursor.is_some() {
+            catalog.resume_cursor = resume_cursor;
+        }
+
+        Ok(catalog.markets.clone())
+    }
+
+    /// Condition ids the last `get_markets` call actually read from the
+    /// exchange; the rest of its markets may have closed or stopped
+    /// accepting orders since they were listed
+    pub fn confirmed_market_ids(&self) -> HashSet<String> {
+        self.catalog.lock().confirmed.clone()
+    }
+
+    /// Read pages from `cursor` until the last one or the page cap
+    ///
+    /// Returns the markets and the cursor to resume from next time: the
+    /// last page read, or the first unread one if the cap was hit.
+    async fn list_markets(&self, mut cursor: Option<String>) -> Result<(Vec<Market>, Option<String>)> {
+        let mut markets = Vec::new();
+
+        for _ in 0..self.listing.max_pages.max(1) {
+            let page = self.exchange.get_markets_page(cursor.as_deref()).await?;
+            markets.extend(page.data);
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok((markets, cursor)),
+            }
+        }
+
+        warn!(
+            "Market listing has more than {} pages; the rest is read on the next refreshes (raise MAX_MARKET_PAGES)",
+            self.listing.max_pages
+        );
+        Ok((markets, cursor))
+    }
+
+    /// Fetch a single market by condition id, including closed/resolved ones
+    pub async fn get_market(&self, condition_id: &str) -> Result<Market> {
+        self.exchange.get_market(condition_id).await
+    }
+
+    /// Markets by slug; slugs the exchange doesn't know yet are left out
+    pub async fn get_markets_by_slug(&self, slugs: &[String]) -> Result<Vec<Market>> {
+        if slugs.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.exchange.get_markets_by_slug(slugs).await
+    }
+
+    /// Condition ids of every open member of the given neg-risk groups
+    pub async fn get_group_members(&self, group_ids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+        if group_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.exchange.get_group_members(group_ids).await
+    }
+
+    /// Liquidity and volume of the given markets
+    pub async fn get_market_stats(&self, condition_ids: &[String]) -> Result<Vec<MarketStats>> {
+        self.exchange.get_market_stats(condition_ids).await
+    }
+
+    /// Get multiple order books (fetch in parallel)
+    pub async fn get_order_books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>> {
+        if token_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let books = self.exchange.get_order_books(token_ids).await?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_books(&books);
+        }
+
+        Ok(books)
+    }
+
+    /// Execute arbitrage: buy YES + NO in a single submission
+    ///
+    /// This is the critical path - both orders are built and signed first, then
+    /// sent together in one `Exchange::post_orders` batch to minimize the time
+    /// window where prices could move against us. Each leg is a limit order
+    /// capped at the worst detected level plus the slippage tolerance.
+    pub async fn execute_arbitrage(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        size: Decimal,
+    ) -> Result<ArbExecution> {
+        info!(
+            "Executing PARALLEL arbitrage on market {} - profit per share: ${}",
+            opportunity.market_id,
+            opportunity.profit_per_share
+        );
+
+        // Cap each leg at the worst level needed to fill `size` shares
+        let (yes_level, no_level) = opportunity.marginal_prices(size);
+        let yes_limit = self.orders.limit_price(Side::Buy, yes_level, &opportunity.costs);
+        let no_limit = self.orders.limit_price(Side::Buy, no_level, &opportunity.costs);
+        let (yes_usdc, no_usdc) = opportunity.fill_notional(size);
+
+        info!(
+            "Placing parallel {} orders: {} {} @ <= {} (~${}) | {} {} @ <= {} (~${})",
+            OrderPolicy::ORDER_TYPE,
+            size, opportunity.yes_outcome, yes_limit, yes_usdc,
+            size, opportunity.no_outcome, no_limit, no_usdc
+        );
+
+        self.submit_pair(opportunity, size, yes_limit, no_limit, Side::Buy).await
+    }
+
+    /// Execute sell-side arbitrage: sell YES + NO into the bids simultaneously
+    ///
+    /// The wallet must already hold `size` complete sets (or have split USDC into them).
+    pub async fn execute_sell_arbitrage(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        size: Decimal,
+    ) -> Result<ArbExecution> {
+        info!(
+            "Executing PARALLEL sell arbitrage on market {} - profit per share: ${}",
+            opportunity.market_id,
+            opportunity.profit_per_share
+        );
+
+        // Floor each leg at the worst bid needed to sell `size` shares
+        let (yes_level, no_level) = opportunity.marginal_prices(size);
+        let yes_limit = self.orders.limit_price(Side::Sell, yes_level, &opportunity.costs);
+        let no_limit = self.orders.limit_price(Side::Sell, no_level, &opportunity.costs);
+        let (yes_usdc, no_usdc) = opportunity.fill_notional(size);
+
+        info!(
+            "Placing parallel {} sells: {} {} @ >= {} (~${}) | {} {} @ >= {} (~${})",
+            OrderPolicy::ORDER_TYPE,
+            size, opportunity.yes_outcome, yes_limit, yes_usdc,
+            size, opportunity.no_outcome, no_limit, no_usdc
+        );
+
+        self.submit_pair(opportunity, size, yes_limit, no_limit, Side::Sell).await
+    }
+
+    /// Submit both legs of an arbitrage in parallel
+    async fn submit_pair(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        size: Decimal,
+        yes_limit: Decimal,
+        no_limit: Decimal,
+        side: Side,
+    ) -> Result<ArbExecution> {
+        let requests = [
+            OrderRequest {
+                token_id: opportunity.yes_token_id.clone(),
+                label: opportunity.yes_outcome.clone(),
+                side,
+                price: yes_limit,
+                size,
+            },
+            OrderRequest {
+                token_id: opportunity.no_token_id.clone(),
+                label: opportunity.no_outcome.clone(),
+                side,
+                price: no_limit,
+                size,
+            },
+        ];
+
+        // Both orders are built and signed before either is submitted - this is the critical section!
+        let responses = self.exchange.post_orders(&requests, OrderPolicy::ORDER_TYPE).await?;
+        let [yes_result, no_result]: [OrderResponse; 2] = responses
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Expected one response per leg"))?;
+
+        self.journal_order(&opportunity.id, &opportunity.market_id, &requests[0], &yes_result);
+        self.journal_order(&opportunity.id, &opportunity.market_id, &requests[1], &no_result);
+
+        let (yes_usdc, no_usdc) = opportunity.fill_notional(size);
+        self.record_fill(
+            &opportunity.market_id, &opportunity.yes_token_id, &opportunity.yes_outcome,
+            side, &yes_result, (size, yes_usdc),
+        );
+        self.record_fill(
+            &opportunity.market_id, &opportunity.no_token_id, &opportunity.no_outcome,
+            side, &no_result, (size, no_usdc),
+        );
+
+        // Log results
+        let mut recovery = None;
+        if yes_result.success && no_result.success {
+            info!("Both orders submitted successfully!");
+        } else {
+            warn!(
+                "Order submission results - {}: {} | {}: {}",
+                opportunity.yes_outcome,
+                if yes_result.success { "OK" } else { "FAILED" },
+                opportunity.no_outcome,
+                if no_result.success { "OK" } else { "FAILED" }
+            );
+
+            // Handle partial fill scenario
+            if yes_result.success != no_result.success {
+                recovery = Some(
+                    self.handle_partial_execution(&yes_result, &no_result, opportunity, size).await,
+                );
+            }
+        }
+
+        Ok(ArbExecution { yes: yes_result, no: no_result, recovery })
+    }
+
+    /// Execute a multi-outcome basket: buy every outcome in parallel
+    ///
+    /// If only some legs fill, the missing ones are retried within the
+    /// slippage budget and, failing that, the filled ones are unwound (see
+    /// `recover_basket`).
+    pub async fn execute_basket(
+        &self,
+        opportunity: &BasketOpportunity,
+        size: Decimal,
+    ) -> Result<BasketExecution> {
+        info!(
+            "Executing PARALLEL {}-leg basket on group {} - profit per share: ${}",
+            opportunity.legs.len(),
+            opportunity.group_id,
+            opportunity.profit_per_share
+        );
+
+        let notional = opportunity.fill_notional(size);
+        let limits: Vec<Decimal> = opportunity
+            .marginal_prices(size)
+            .into_iter()
+            .map(|price| self.orders.limit_price(Side::Buy, price, &opportunity.costs))
+            .collect();
+
+        for ((leg, usdc), limit) in opportunity.legs.iter().zip(&notional).zip(&limits) {
+            info!(
+                "  {} {} @ <= {} (~${}, best {} / worst {})",
+                size, leg.label, limit, usdc, leg.best_ask, leg.marginal_price
+            );
+        }
+
+        let requests: Vec<OrderRequest> = opportunity
+            .legs
+            .iter()
+            .zip(&limits)
+            .map(|(leg, limit)| OrderRequest {
+                token_id: leg.token_id.clone(),
+                label: leg.label.clone(),
+                side: Side::Buy,
+                price: *limit,
+                size,
+            })
+            .collect();
+
+        // Every leg is built before any is submitted
+        let results = self.exchange.post_orders(&requests, OrderPolicy::ORDER_TYPE).await?;
+
+        let mut held = Vec::with_capacity(results.len());
+        let mut cost = Vec::with_capacity(results.len());
+        for (((leg, request), result), usdc) in opportunity.legs.iter().zip(&requests).zip(&results).zip(&notional) {
+            self.journal_order(&opportunity.id, &leg.condition_id, request, result);
+            self.record_fill(&leg.condition_id, &leg.token_id, &leg.label, Side::Buy, result, (size, *usdc));
+            if result.success {
+                held.push(size);
+                cost.push(after_fees(&opportunity.costs, Side::Buy, size, result.usdc_amount(Side::Buy).unwrap_or(*usdc)));
+            } else {
+                held.push(Decimal::ZERO);
+                cost.push(Decimal::ZERO);
+            }
+        }
+
+        let filled = results.iter().filter(|r| r.success).count();
+        let mut recovery = Vec::new();
+        if filled == results.len() {
+            info!("All {} basket legs submitted successfully!", filled);
+        } else if filled > 0 {
+            warn!(
+                "PARTIAL BASKET EXECUTION on group {}: {}/{} legs filled - attempting recovery",
+                opportunity.group_id,
+                filled,
+                results.len()
+            );
+            recovery = self.recover_basket(opportunity, size, &mut held, &mut cost).await;
+        } else {
+            warn!("No basket legs filled on group {}", opportunity.group_id);
+        }
+
+        Ok(BasketExecution { legs: results, recovery, held, cost })
+    }
+
+    /// Recover a basket where only some legs filled
+    ///
+    /// Runs the same states as `handle_partial_execution`, across legs:
+    /// 1. Retry every missing leg while its book stays within the slippage
+    ///    budget of its marginal price, up to `max_retries` rounds
+    /// 2. If the set is still incomplete, sell every held leg back into the book
+    /// 3. Legs that can't be sold stay held and are reported unhedged
+    ///
+    /// `held` and `cost` are updated in place. Returns one report per leg
+    /// retried (hedged once the set completes) or unwound.
+    async fn recover_basket(
+        &self,
+        opportunity: &BasketOpportunity,
+        size: Decimal,
+        held: &mut [Decimal],
+        cost: &mut [Decimal],
+    ) -> Vec<RecoveryReport> {
+        let costs = &opportunity.costs;
+        let mut orders: Vec<Vec<OrderResponse>> = vec![Vec::new(); opportunity.legs.len()];
+        let mut hedged_at: Vec<Option<Decimal>> = vec![None; opportunity.legs.len()];
+        let mut attempts = 0;
+
+        // State 1: retry the missing legs within the slippage budget
+        while attempts < self.recovery.max_retries && held.iter().any(|shares| shares.is_zero()) {
+            attempts += 1;
+
+            for (i, leg) in opportunity.legs.iter().enumerate() {
+                if !held[i].is_zero() {
+                    continue;
+                }
+                let limit = self.recovery.limit_price(Side::Buy, leg.marginal_price, costs);
+                match self.best_level(&leg.token_id, Side::Buy).await {
+                    Ok(Some(level)) if level.price <= limit => {
+                        info!(
+                            "Basket recovery attempt {}/{}: buy {} {} @ {} (limit {})",
+                            attempts, self.recovery.max_retries, size, leg.label, level.price, limit
+                        );
+                        let response = self
+                            .place_order(
+                                &opportunity.id,
+                                &leg.condition_id,
+                                OrderRequest {
+                                    token_id: leg.token_id.clone(),
+                                    label: leg.label.clone(),
+                                    side: Side::Buy,
+                                    price: limit,
+                                    size,
+                                },
+                            )
+                            .await;
+                        if response.success {
+                            held[i] = size;
+                            cost[i] = after_fees(
+                                costs,
+                                Side::Buy,
+                                size,
+                                response.usdc_amount(Side::Buy).unwrap_or(size * level.price),
+                            );
+                            hedged_at[i] = Some(level.price);
+                        }
+                        orders[i].push(response);
+                    }
+                    Ok(Some(level)) => warn!(
+                        "Basket recovery attempt {}/{}: {} best {} outside slippage budget (limit {})",
+                        attempts, self.recovery.max_retries, leg.label, level.price, limit
+                    ),
+                    Ok(None) => warn!(
+                        "Basket recovery attempt {}/{}: no liquidity for {}",
+                        attempts, self.recovery.max_retries, leg.label
+                    ),
+                    Err(e) => warn!(
+                        "Basket recovery attempt {}/{}: failed to fetch book: {}",
+                        attempts, self.recovery.max_retries, e
+                    ),
+                }
+            }
+
+            if attempts < self.recovery.max_retries && held.iter().any(|shares| shares.is_zero()) {
+                tokio::time::sleep(self.recovery.retry_delay).await;
+            }
+        }
+
+        let mut reports = Vec::new();
+        if held.iter().all(|shares| !shares.is_zero()) {
+            info!(
+                "Basket recovery succeeded on group {} after {} attempt(s)",
+                opportunity.group_id, attempts
+            );
+            for (i, leg) in opportunity.legs.iter().enumerate() {
+                if let Some(price) = hedged_at[i] {
+                    reports.push(RecoveryReport {
+                        action: RecoveryAction::Hedged { price },
+                        exposed_token_id: leg.token_id.clone(),
+                        size,
+                        attempts,
+                        // Completed sets pay out on resolution
+                        realized_pnl: Decimal::ZERO,
+                        orders: std::mem::take(&mut orders[i]),
+                    });
+                }
+            }
+            return reports;
+        }
+
+        // State 2: unwind every held leg
+        warn!("Basket retries exhausted, unwinding filled legs of group {}", opportunity.group_id);
+        for (i, leg) in opportunity.legs.iter().enumerate() {
+            if held[i].is_zero() {
+                continue;
+            }
+            let shares = held[i];
+            let mut action = RecoveryAction::Unhedged;
+            let mut realized_pnl = Decimal::ZERO;
+
+            match self.best_level(&leg.token_id, Side::Sell).await {
+                Ok(Some(level)) => {
+                    let unwind_limit = self.recovery.limit_price(Side::Sell, level.price, costs);
+                    let response = self
+                        .place_order(
+                            &opportunity.id,
+                            &leg.condition_id,
+                            OrderRequest {
+                                token_id: leg.token_id.clone(),
+                                label: leg.label.clone(),
+                                side: Side::Sell,
+                                price: unwind_limit,
+                                size: shares,
+                            },
+                        )
+                        .await;
+                    if response.success {
+                        let proceeds = after_fees(
+                            costs,
+                            Side::Sell,
+                            shares,
+                            response.usdc_amount(Side::Sell).unwrap_or(shares * level.price),
+                        );
+                        realized_pnl = proceeds - cost[i];
+                        warn!("Unwound {} {} @ {}, realized P&L ${}", shares, leg.label, level.price, realized_pnl);
+                        action = RecoveryAction::Unwound { price: level.price };
+                        held[i] = Decimal::ZERO;
+                        cost[i] = Decimal::ZERO;
+                    }
+                    orders[i].push(response);
+                }
+                Ok(None) => error!("No liquidity to unwind {}", leg.label),
+                Err(e) => error!("Failed to fetch book for unwind: {}", e),
+            }
+
+            if action == RecoveryAction::Unhedged {
+                // State 3: the leg stays held until resolution
+                error!(
+                    "ALERT: Partial basket execution on group {}. {} {} position is unhedged! Manual intervention required.",
+                    opportunity.group_id, shares, leg.label
+                );
+            }
+            reports.push(RecoveryReport {
+                action,
+                exposed_token_id: leg.token_id.clone(),
+                size: shares,
+                attempts,
+                realized_pnl,
+                orders: std::mem::take(&mut orders[i]),
+            });
+        }
+
+        reports
+    }
+
+    /// Submit a single order in `condition_id` for an opportunity, folding any error into the response
+    async fn place_order(&self, opportunity_id: &str, condition_id: &str, request: OrderRequest) -> OrderResponse {
+        let response = match self.exchange.post_orders(std::slice::from_ref(&request), OrderPolicy::ORDER_TYPE).await {
+            Ok(responses) => responses
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| OrderResponse::failed("Empty response from server")),
+            Err(e) => {
+                error!("{} order could not be built: {:#}", request.label, e);
+                OrderResponse::failed(e.to_string())
+            }
+        };
+        let OrderRequest { token_id, label, side, price, size } = &request;
+        self.record_fill(condition_id, token_id, label, *side, &response, (*size, *size * *price));
+        self.journal_order(opportunity_id, condition_id, &request, &response);
+        response
+    }
+
+    /// Best level we could trade against right now: lowest ask to buy, highest bid to sell
+    async fn best_level(&self, token_id: &str, side: Side) -> Result<Option<OrderBookEntry>> {
+        let books = self.get_order_books(&[token_id.to_string()]).await?;
+        let book = match books.into_iter().next() {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        Ok(match side {
+            Side::Buy => book.asks.into_iter().min_by(|a, b| a.price.cmp(&b.price)),
+            Side::Sell => book.bids.into_iter().max_by(|a, b| a.price.cmp(&b.price)),
+        })
+    }
+
+    /// Handle partial execution scenario (one order succeeded, one failed)
+    ///
+    /// In arbitrage, if only one side executes, we're exposed to market risk.
+    /// Recovery runs as a small state machine:
+    /// 1. Re-price and retry the failed leg while the book stays within the
+    ///    slippage budget, up to `max_retries` times
+    /// 2. If that fails, unwind the filled leg by trading it back into the book
+    /// 3. If unwinding fails too, raise an alert and report the exposure
+    async fn handle_partial_execution(
+        &self,
+        yes_result: &OrderResponse,
+        no_result: &OrderResponse,
+        opportunity: &ArbitrageOpportunity,
+        size: Decimal,
+    ) -> RecoveryReport {
+        warn!("PARTIAL EXECUTION DETECTED - attempting recovery");
+
+        let side = match opportunity.direction {
+            ArbDirection::BuyBoth => Side::Buy,
+            ArbDirection::SellBoth => Side::Sell,
+        };
+        let unwind_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let (yes_notional, no_notional) = opportunity.fill_notional(size);
+
+        // Determine which side failed: (token, label, marginal price, notional)
+        let (filled, failed, filled_result) = if yes_result.success {
+            (
+                (&opportunity.yes_token_id, &opportunity.yes_outcome, yes_notional),
+                (&opportunity.no_token_id, &opportunity.no_outcome, opportunity.no_marginal_price),
+                yes_result,
+            )
+        } else {
+            (
+                (&opportunity.no_token_id, &opportunity.no_outcome, no_notional),
+                (&opportunity.yes_token_id, &opportunity.yes_outcome, opportunity.yes_marginal_price),
+                no_result,
+            )
+        };
+        let (filled_token, filled_label, filled_notional) = filled;
+        let (failed_token, failed_label, failed_price) = failed;
+        let costs = &opportunity.costs;
+        let filled_usdc = after_fees(costs, side, size, filled_result.usdc_amount(side).unwrap_or(filled_notional));
+
+        warn!(
+            "{} order failed, {} order succeeded ({} for ${}). Retrying {}...",
+            failed_label, filled_label, size, filled_usdc, failed_label
+        );
+
+        let mut orders = Vec::new();
+        let mut attempts = 0;
+
+        // State 1: re-price and retry the failed leg within the slippage budget
+        let limit = self.recovery.limit_price(side, failed_price, costs);
+        while attempts < self.recovery.max_retries {
+            attempts += 1;
+
+            match self.best_level(failed_token, side).await {
+                Ok(Some(level)) if (side == Side::Buy && level.price <= limit)
+                    || (side == Side::Sell && level.price >= limit) =>
+                {
+                    info!(
+                        "Recovery attempt {}/{}: {} {} {} @ {} (limit {})",
+                        attempts, self.recovery.max_retries, side, size, failed_label, level.price, limit
+                    );
+
+                    // Cap the retry at the budget limit rather than the current level
+                    let response = self
+                        .place_order(
+                            &opportunity.id,
+                            &opportunity.market_id,
+                            OrderRequest {
+                                token_id: failed_token.clone(),
+                                label: failed_label.clone(),
+                                side,
+                                price: limit,
+                                size,
+                            },
+                        )
+                        .await;
+                    let success = response.success;
+                    let failed_usdc = after_fees(costs, side, size, response.usdc_amount(side).unwrap_or(size * level.price));
+                    orders.push(response);
+
+                    if success {
+                        let gross = match side {
+                            Side::Buy => size - filled_usdc - failed_usdc,
+                            Side::Sell => filled_usdc + failed_usdc - size,
+                        };
+                        let realized_pnl = gross - costs.fixed_cost;
+                        info!(
+                            "Recovery succeeded: {} hedged @ {} after {} attempt(s), realized P&L ${}",
+                            failed_label, level.price, attempts, realized_pnl
+                        );
+                        return RecoveryReport {
+                            action: RecoveryAction::Hedged { price: level.price },
+                            exposed_token_id: filled_token.clone(),
+                            size,
+                            attempts,
+                            realized_pnl,
+                            orders,
+                        };
+                    }
+                }
+                Ok(Some(level)) => {
+                    warn!(
+                        "Recovery attempt {}/{}: {} best {} outside slippage budget (limit {})",
+                        attempts, self.recovery.max_retries, failed_label, level.price, limit
+                    );
+                }
+                Ok(None) => {
+                    warn!("Recovery attempt {}/{}: no liquidity for {}", attempts, self.recovery.max_retries, failed_label);
+                }
+                Err(e) => {
+                    warn!("Recovery attempt {}/{}: failed to fetch book: {}", attempts, self.recovery.max_retries, e);
+                }
+            }
+
+            if attempts < self.recovery.max_retries {
+                tokio::time::sleep(self.recovery.retry_delay).await;
+            }
+        }
+
+        // State 2: unwind the filled leg by trading it back into the book
+        warn!("Retries exhausted, unwinding {} {} {}", unwind_side, size, filled_label);
+
+        match self.best_level(filled_token, unwind_side).await {
+            Ok(Some(level)) => {
+                // Exiting matters more than price; allow the same slippage past the best level
+                let unwind_limit = self.recovery.limit_price(unwind_side, level.price, costs);
+                let response = self
+                    .place_order(
+                        &opportunity.id,
+                        &opportunity.market_id,
+                        OrderRequest {
+                            token_id: filled_token.clone(),
+                            label: filled_label.clone(),
+                            side: unwind_side,
+                            price: unwind_limit,
+                            size,
+                        },
+                    )
+                    .await;
+                let success = response.success;
+                let unwind_usdc = after_fees(
+                    costs,
+                    unwind_side,
+                    size,
+                    response.usdc_amount(unwind_side).unwrap_or(size * level.price),
+                );
+                orders.push(response);
+
+                if success {
+                    let realized_pnl = match side {
+                        Side::Buy => unwind_usdc - filled_usdc,
+                        Side::Sell => filled_usdc - unwind_usdc,
+                    };
+                    warn!(
+                        "Unwound {} @ {}, realized P&L ${}",
+                        filled_label, level.price, realized_pnl
+                    );
+                    return RecoveryReport {
+                        action: RecoveryAction::Unwound { price: level.price },
+                        exposed_token_id: filled_token.clone(),
+                        size,
+                        attempts,
+                        realized_pnl,
+                        orders,
+                    };
+                }
+            }
+            Ok(None) => error!("No liquidity to unwind {}", filled_label),
+            Err(e) => error!("Failed to fetch book for unwind: {}", e),
+        }
+
+        // State 3: give up and alert
+        error!(
+            "ALERT: Partial arbitrage execution on market {}. {} {} position is unhedged! Manual intervention required.",
+            opportunity.market_id,
+            size,
+            filled_label
+        );
+
+        RecoveryReport {
+            action: RecoveryAction::Unhedged,
+            exposed_token_id: filled_token.clone(),
+            size,
+            attempts,
+            realized_pnl: Decimal::ZERO,
+            orders,
+        }
+    }
+}
+
+/// USDC paid (buys) or received (sells) for `shares` traded for `usdc` before
+/// fees, with the taker fee at the average price added or deducted
+fn after_fees(costs: &CostModel, side: Side, shares: Decimal, usdc: Decimal) -> Decimal {
+    if shares <= Decimal::ZERO {
+        return usdc;
+    }
+    let fee = shares * costs.fee_per_share(usdc / shares);
+    match side {
+        Side::Buy => usdc + fee,
+        Side::Sell => usdc - fee,
+    }
+}
@@ -0,0 +1,292 @@
+//! In-memory mock exchange
+//! Serves scripted markets and order books and fills orders against them offline
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+
+use super::exchange::Exchange;
+use super::types::*;
+use crate::simulation::{take_liquidity, Fill};
+
+/// Markets per page unless `set_page_size` says otherwise
+const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Exchange that fills orders against the books it was given
+///
+/// Orders take liquidity at or better than their limit and consume the depth
+/// they take. FOK orders that can't fill completely are rejected; IOC and GTC
+/// orders fill what they can and the remainder is dropped rather than resting.
+/// Fees aren't charged. Every order is kept for inspection.
+pub struct MockExchange {
+    address: String,
+    markets: RwLock<Vec<Market>>,
+    /// Markets per page of the listing
+    page_size: RwLock<usize>,
+    books: RwLock<HashMap<String, OrderBook>>,
+    /// Liquidity and volume by condition id
+    stats: RwLock<HashMap<String, MarketStats>>,
+    funding: RwLock<Funding>,
+    /// Token balances by token id
+    holdings: RwLock<HashMap<String, AccountPosition>>,
+    /// Tokens whose orders are rejected outright, to script failed legs
+    rejecting: RwLock<HashSet<String>>,
+    orders: RwLock<Vec<(OrderRequest, OrderResponse)>>,
+}
+
+impl MockExchange {
+    /// Empty exchange with `balance` USDC, fully approved
+    pub fn new(balance: Decimal) -> Self {
+        Self {
+            address: "0x000000000000000000000000000000000000dEaD".to_string(),
+            markets: RwLock::new(Vec::new()),
+            page_size: RwLock::new(DEFAULT_PAGE_SIZE),
+            books: RwLock::new(HashMap::new()),
+            stats: RwLock::new(HashMap::new()),
+            funding: RwLock::new(Funding {
+                balance,
+                allowance: Decimal::MAX,
+                neg_risk_allowance: Decimal::MAX,
+            }),
+            holdings: RwLock::new(HashMap::new()),
+            rejecting: RwLock::new(HashSet::new()),
+            orders: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replace the listed markets
+    pub fn set_markets(&self, markets: Vec<Market>) {
+        *self.markets.write() = markets;
+    }
+
+    /// Split the markets listing into pages of `page_size`
+    pub fn set_page_size(&self, page_size: usize) {
+        *self.page_size.write() = page_size.max(1);
+    }
+
+    /// Add or replace the liquidity and volume of `stats.condition_id`
+    pub fn set_market_stats(&self, stats: MarketStats) {
+        self.stats.write().insert(stats.condition_id.clone(), stats);
+    }
+
+    /// Add or replace the book of `book.asset_id`
+    pub fn set_book(&self, book: OrderBook) {
+        self.books.write().insert(book.asset_id.clone(), book);
+    }
+
+    /// Reject every order on `token_id` until `accept_orders` is called
+    pub fn reject_orders(&self, token_id: &str) {
+        self.rejecting.write().insert(token_id.to_string());
+    }
+
+    pub fn accept_orders(&self, token_id: &str) {
+        self.rejecting.write().remove(token_id);
+    }
+
+    /// Set the USDC approved for the CTF Exchange and for the neg-risk contracts
+    pub fn set_allowances(&self, allowance: Decimal, neg_risk_allowance: Decimal) {
+        let mut funding = self.funding.write();
+        funding.allowance = allowance;
+        funding.neg_risk_allowance = neg_risk_allowance;
+    }
+
+    /// USDC balance after every fill so far
+    pub fn balance(&self) -> Decimal {
+        self.funding.read().balance
+    }
+
+    /// Shares held of a token
+    pub fn shares(&self, token_id: &str) -> Decimal {
+        self.holdings.read().get(token_id).map(|p| p.size).unwrap_or_default()
+    }
+
+    /// Every order submitted, with its response, in submission order
+    pub fn orders(&self) -> Vec<(OrderRequest, OrderResponse)> {
+        self.orders.read().clone()
+    }
+
+    /// Match one order against its book, applying the fill to the book and balances
+    fn fill_order(&self, request: &OrderRequest, order_type: OrderType) -> OrderResponse {
+        if self.rejecting.read().contains(&request.token_id) {
+            return OrderResponse::failed("order rejected by mock exchange");
+        }
+
+        let mut books = self.books.write();
+        let Some(book) = books.get_mut(&request.token_id) else {
+            return OrderResponse::failed(format!("no order book for {}", request.token_id));
+        };
+
+        let fill = take_liquidity(book, request.side, request.size, Some(request.price));
+        let shares = fill.shares();
+        if shares.is_zero() {
+            return OrderResponse::failed("no orders found to match");
+        }
+        if order_type == OrderType::Fok && shares < request.size {
+            return OrderResponse::failed("order couldn't be fully filled. FOK orders are fully filled or killed.");
+        }
+
+        let usdc = fill.notional();
+        let neg_risk = self
+            .markets
+            .read()
+            .iter()
+            .any(|m| m.neg_risk && m.tokens.iter().any(|t| t.token_id == request.token_id));
+        let mut funding = self.funding.write();
+        let mut holdings = self.holdings.write();
+        match request.side {
+            Side::Buy if usdc > funding.available(neg_risk) => {
+                return OrderResponse::failed("not enough balance / allowance");
+            }
+            Side::Sell if holdings.get(&request.token_id).map(|p| p.size).unwrap_or_default() < shares => {
+                return OrderResponse::failed("not enough balance / allowance");
+            }
+            _ => {}
+        }
+
+        consume(book, request.side, &fill);
+        let position = holdings
+            .entry(request.token_id.clone())
+            .or_insert_with(|| AccountPosition {
+                asset: request.token_id.clone(),
+                condition_id: book.market.clone(),
+                size: Decimal::ZERO,
+                avg_price: Decimal::ZERO,
+                outcome: request.label.clone(),
+            });
+        let (making_amount, taking_amount) = match request.side {
+            Side::Buy => {
+                funding.balance -= usdc;
+                position.avg_price = (position.avg_price * position.size + usdc) / (position.size + shares);
+                position.size += shares;
+                (usdc, shares)
+            }
+            Side::Sell => {
+                funding.balance += usdc;
+                position.size -= shares;
+                (shares, usdc)
+            }
+        };
+
+        OrderResponse {
+            success: true,
+            error_msg: None,
+            order_id: Some(uuid::Uuid::new_v4().to_string()),
+            transaction_hashes: None,
+            making_amount: Some(making_amount),
+            taking_amount: Some(taking_amount),
+        }
+    }
+}
+
+#[async_trait]
+impl Exchange for MockExchange {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Cursors are plain offsets into the listing
+    async fn get_markets_page(&self, cursor: Option<&str>) -> Result<MarketsResponse> {
+        let offset: usize = match cursor {
+            Some(cursor) => cursor.parse().with_context(|| format!("Invalid cursor {}", cursor))?,
+            None => 0,
+        };
+        let page_size = *self.page_size.read();
+        let markets = self.markets.read();
+
+        let data = markets
+            .iter()
+            .skip(offset)
+            .take(page_size)
+            .filter(|m| m.active && !m.closed && m.accepting_orders)
+            .cloned()
+            .collect();
+        let next = offset + page_size;
+        let next_cursor = (next < markets.len()).then(|| next.to_string());
+        Ok(MarketsResponse { data, next_cursor })
+    }
+
+    async fn get_market(&self, condition_id: &str) -> Result<Market> {
+        self.markets
+            .read()
+            .iter()
+            .find(|m| m.condition_id == condition_id)
+            .cloned()
+            .with_context(|| format!("Failed to fetch market {}", condition_id))
+    }
+
+    async fn get_markets_by_slug(&self, slugs: &[String]) -> Result<Vec<Market>> {
+        let markets = self.markets.read();
+        Ok(slugs
+            .iter()
+            .filter_map(|slug| markets.iter().find(|m| m.market_slug.as_deref() == Some(slug.as_str())))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_group_members(&self, group_ids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+        let mut members: HashMap<String, Vec<String>> = HashMap::new();
+        for market in self.markets.read().iter().filter(|m| !m.closed) {
+            if let Some(group_id) = market.neg_risk_market_id.as_ref().filter(|id| group_ids.contains(id)) {
+                members.entry(group_id.clone()).or_default().push(market.condition_id.clone());
+            }
+        }
+        Ok(members)
+    }
+
+    async fn get_market_stats(&self, condition_ids: &[String]) -> Result<Vec<MarketStats>> {
+        let stats = self.stats.read();
+        Ok(condition_ids.iter().filter_map(|id| stats.get(id).cloned()).collect())
+    }
+
+    async fn get_order_books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>> {
+        let books = self.books.read();
+        Ok(token_ids.iter().filter_map(|id| books.get(id).cloned()).collect())
+    }
+
+    async fn post_orders(&self, requests: &[OrderRequest], order_type: OrderType) -> Result<Vec<OrderResponse>> {
+        let responses: Vec<OrderResponse> = requests
+            .iter()
+            .map(|request| self.fill_order(request, order_type))
+            .collect();
+
+        self.orders
+            .write()
+            .extend(requests.iter().cloned().zip(responses.iter().cloned()));
+        Ok(responses)
+    }
+
+    async fn cancel_all_orders(&self) -> Result<usize> {
+        // Nothing ever rests on the mock book
+        Ok(0)
+    }
+
+    async fn get_funding(&self) -> Result<Funding> {
+        Ok(self.funding.read().clone())
+    }
+
+    async fn get_account_positions(&self) -> Result<Vec<AccountPosition>> {
+        Ok(self
+            .holdings
+            .read()
+            .values()
+            .filter(|p| p.size > Decimal::ZERO)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Remove the liquidity a fill took from the book
+fn consume(book: &mut OrderBook, side: Side, fill: &Fill) {
+    let levels = match side {
+        Side::Buy => &mut book.asks,
+        Side::Sell => &mut book.bids,
+    };
+    for chunk in &fill.chunks {
+        if let Some(level) = levels.iter_mut().find(|l| l.price == chunk.price) {
+            level.size -= chunk.size;
+        }
+    }
+    levels.retain(|l| l.size > Decimal::ZERO);
+}
@@ -1,7 +1,15 @@
 //! Polymarket CLOB API module
 
 pub mod client;
+pub mod ctf;
+pub mod exchange;
+pub mod executor;
+pub mod mock;
 pub mod types;
 
 pub use client::ClobClient;
+pub use ctf::CtfConfig;
+pub use exchange::Exchange;
+pub use executor::Executor;
+pub use mock::MockExchange;
 pub use types::*;
@@ -4,16 +4,22 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use crate::fees::CostModel;
+
 /// Market data from the CLOB API
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Market {
     pub condition_id: String,
     pub question_id: String,
     pub tokens: Vec<Token>,
     pub minimum_order_size: Decimal,
     pub minimum_tick_size: Decimal,
+    #[serde(default)]
+    pub taker_base_fee: Decimal,
     pub description: Option<String>,
     pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub end_date_iso: Option<String>,
     pub game_start_time: Option<String>,
     pub question: Option<String>,
@@ -21,30 +27,36 @@ pub struct Market {
     pub active: bool,
     pub closed: bool,
     pub accepting_orders: bool,
+    #[serde(default)]
+    pub neg_risk: bool,
+    #[serde(default)]
+    pub neg_risk_market_id: Option<String>,
 }
 
 /// Token representing YES or NO outcome
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub token_id: String,
-    pub outcome: String, // "Yes" or "No"
+    pub outcome: String, // "Yes"/"No", or labels like "Up"/"Down"
     pub price: Option<Decimal>,
     pub winner: bool,
 }
 
 /// Order book summary for a token
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBook {
     pub market: String,
     pub asset_id: String,
     pub bids: Vec<OrderBookEntry>,
     pub asks: Vec<OrderBookEntry>,
+    #[serde(default)]
     pub hash: String,
+    #[serde(default)]
     pub timestamp: String,
 }
 
 /// Single order book entry (price level)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookEntry {
     pub price: Decimal,
     pub size: Decimal,
@@ -93,7 +105,7 @@ impl std::fmt::Display for Side {
 }
 
 /// Order type
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum OrderType {
     Gtc, // Good till cancelled
@@ -101,6 +113,62 @@ pub enum OrderType {
     Ioc, // Immediate or cancel
 }
 
+impl std::fmt::Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderType::Gtc => write!(f, "GTC"),
+            OrderType::Fok => write!(f, "FOK"),
+            OrderType::Ioc => write!(f, "IOC"),
+        }
+    }
+}
+
+impl std::str::FromStr for OrderType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "GTC" => Ok(OrderType::Gtc),
+            "FOK" => Ok(OrderType::Fok),
+            "IOC" | "FAK" => Ok(OrderType::Ioc),
+            other => anyhow::bail!("Unknown order type: {}", other),
+        }
+    }
+}
+
+/// How arbitrage legs are priced
+#[derive(Debug, Clone, Default)]
+pub struct OrderPolicy {
+    /// Price tolerance per share beyond the detected level (worse for us)
+    pub slippage: Decimal,
+}
+
+impl OrderPolicy {
+    /// Time in force for every arbitrage and recovery leg
+    ///
+    /// An accepted GTC or IOC order may be only partly matched, so a
+    /// successful response wouldn't mean the leg filled; FOK orders fill
+    /// completely or not at all.
+    pub const ORDER_TYPE: OrderType = OrderType::Fok;
+
+    /// Limit price for a leg: the detected level moved against us by the
+    /// slippage tolerance, rounded to the tick so the cap is never exceeded
+    pub fn limit_price(&self, side: Side, level_price: Decimal, costs: &CostModel) -> Decimal {
+        limit_past(side, level_price, self.slippage, costs)
+    }
+}
+
+/// `level_price` moved against us by `slippage`, rounded inward to the tick
+/// and kept within the tradable range [tick, 1 - tick]
+fn limit_past(side: Side, level_price: Decimal, slippage: Decimal, costs: &CostModel) -> Decimal {
+    let tick = costs.tick_size.max(Decimal::new(1, 3));
+    let price = match side {
+        Side::Buy => costs.round_down_to_tick(level_price + slippage),
+        Side::Sell => costs.round_up_to_tick(level_price - slippage),
+    };
+    price.max(tick).min(Decimal::ONE - tick)
+}
+
 /// Signed order to be submitted
 #[derive(Debug, Clone, Serialize)]
 pub struct SignedOrder {
@@ -151,6 +219,233 @@ pub struct OrderResponse {
     pub order_id: Option<String>,
     #[serde(rename = "transactionsHashes")]
     pub transaction_hashes: Option<Vec<String>>,
+    /// What we gave up: USDC for buys, shares for sells
+    #[serde(rename = "makingAmount", default)]
+    pub making_amount: Option<Decimal>,
+    /// What we received: shares for buys, USDC for sells
+    #[serde(rename = "takingAmount", default)]
+    pub taking_amount: Option<Decimal>,
+}
+
+impl OrderResponse {
+    /// A rejected order that never reached the book
+    pub fn failed(error_msg: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            error_msg: Some(error_msg.into()),
+            order_id: None,
+            transaction_hashes: None,
+            making_amount: None,
+            taking_amount: None,
+        }
+    }
+
+    /// USDC that changed hands in this order, if the server reported it
+    pub fn usdc_amount(&self, side: Side) -> Option<Decimal> {
+        match side {
+            Side::Buy => self.making_amount,
+            Side::Sell => self.taking_amount,
+        }
+    }
+
+    /// Shares that changed hands in this order, if the server reported it
+    pub fn share_amount(&self, side: Side) -> Option<Decimal> {
+        match side {
+            Side::Buy => self.taking_amount,
+            Side::Sell => self.making_amount,
+        }
+    }
+}
+
+/// An order as submitted: the limit price and size behind an `OrderResponse`
+#[derive(Debug, Clone)]
+pub struct OrderRequest {
+    pub token_id: String,
+    pub label: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+/// Limits for recovering from a one-legged fill
+#[derive(Debug, Clone)]
+pub struct RecoveryPolicy {
+    /// Maximum price move per share accepted when retrying the failed leg
+    pub max_slippage: Decimal,
+    /// Number of re-priced retries of the failed leg before unwinding
+    pub max_retries: u32,
+    /// Delay between retries
+    pub retry_delay: std::time::Duration,
+}
+
+impl RecoveryPolicy {
+    /// Limit price for a recovery order: `level_price` moved against us by
+    /// the slippage budget, on the tick like any other leg
+    pub fn limit_price(&self, side: Side, level_price: Decimal, costs: &CostModel) -> Decimal {
+        limit_past(side, level_price, self.max_slippage, costs)
+    }
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_slippage: Decimal::new(2, 2),
+            max_retries: 3,
+            retry_delay: std::time::Duration::from_millis(250),
+        }
+    }
+}
+
+/// How much of the paginated markets listing to read, and how often in full
+#[derive(Debug, Clone)]
+pub struct MarketListing {
+    /// Pages read per refresh; a longer listing carries on from there next time
+    pub max_pages: usize,
+    /// Interval between full listings; refreshes in between only re-read the last page onwards
+    pub full_refresh: std::time::Duration,
+}
+
+impl Default for MarketListing {
+    fn default() -> Self {
+        Self {
+            max_pages: 20,
+            full_refresh: std::time::Duration::from_secs(3600),
+        }
+    }
+}
+
+/// How a one-legged fill was resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecoveryAction {
+    /// The failed leg filled on retry; the arbitrage completed at `price`
+    Hedged { price: Decimal },
+    /// The filled leg was traded back out at `price`
+    Unwound { price: Decimal },
+    /// Neither retry nor unwind succeeded; the filled leg is still exposed
+    Unhedged,
+}
+
+/// Outcome of a recovery attempt, with the P&L it locked in
+#[derive(Debug, Clone)]
+pub struct RecoveryReport {
+    pub action: RecoveryAction,
+    /// Token that was exposed after the partial fill (for baskets, the leg
+    /// the report covers: retried legs are hedged, filled legs unwound)
+    pub exposed_token_id: String,
+    pub size: Decimal,
+    /// Retries of the failed leg that were attempted
+    pub attempts: u32,
+    /// Realized P&L of the whole trade after recovery (zero if unhedged)
+    pub realized_pnl: Decimal,
+    /// Orders placed during recovery
+    pub orders: Vec<OrderResponse>,
+}
+
+/// Result of executing both legs of an arbitrage
+#[derive(Debug, Clone)]
+pub struct ArbExecution {
+    pub yes: OrderResponse,
+    pub no: OrderResponse,
+    /// Present when only one leg filled and recovery was attempted
+    pub recovery: Option<RecoveryReport>,
+}
+
+impl ArbExecution {
+    /// Complete YES+NO sets held after a buy of `size` shares per leg
+    ///
+    /// Uses the filled share amounts when the server reports them, so a
+    /// short fill on either leg only merges what is actually matched.
+    pub fn matched_sets(&self, size: Decimal) -> Decimal {
+        if self.yes.success && self.no.success {
+            let yes = self.yes.share_amount(Side::Buy).unwrap_or(size);
+            let no = self.no.share_amount(Side::Buy).unwrap_or(size);
+            return yes.min(no);
+        }
+        match &self.recovery {
+            Some(RecoveryReport { action: RecoveryAction::Hedged { .. }, size, .. }) => *size,
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+/// Result of executing every leg of a basket
+#[derive(Debug, Clone)]
+pub struct BasketExecution {
+    /// First submission of each leg, in leg order
+    pub legs: Vec<OrderResponse>,
+    /// One report per leg recovery retried or unwound, when only some legs filled
+    pub recovery: Vec<RecoveryReport>,
+    /// Shares of each leg held afterwards, in leg order
+    pub held: Vec<Decimal>,
+    /// USDC paid for the held shares of each leg, fees included
+    pub cost: Vec<Decimal>,
+}
+
+impl BasketExecution {
+    /// Complete sets held: the fewest shares across every leg
+    pub fn matched_sets(&self) -> Decimal {
+        self.held.iter().copied().min().unwrap_or_default()
+    }
+
+    /// True when no leg is held any more
+    pub fn is_flat(&self) -> bool {
+        self.held.iter().all(|shares| shares.is_zero())
+    }
+}
+
+/// USDC the wallet holds and has approved for each exchange
+#[derive(Debug, Clone)]
+pub struct Funding {
+    pub balance: Decimal,
+    /// Allowance of the CTF Exchange, which fills standard markets
+    pub allowance: Decimal,
+    /// Smaller allowance of the neg-risk exchange and adapter, which fill neg-risk markets
+    pub neg_risk_allowance: Decimal,
+}
+
+impl Funding {
+    /// USDC that orders on a standard or neg-risk market can actually spend
+    pub fn available(&self, neg_risk: bool) -> Decimal {
+        let allowance = if neg_risk { self.neg_risk_allowance } else { self.allowance };
+        self.balance.min(allowance)
+    }
+
+    /// True when USDC hasn't been approved for every exchange contract
+    pub fn allowance_missing(&self) -> bool {
+        self.allowance.is_zero() || self.neg_risk_allowance.is_zero()
+    }
+}
+
+/// Token balance held by the account, as reported by the data API
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountPosition {
+    /// Token id
+    pub asset: String,
+    pub condition_id: String,
+    pub size: Decimal,
+    #[serde(default)]
+    pub avg_price: Decimal,
+    #[serde(default)]
+    pub outcome: String,
+}
+
+/// Liquidity and traded volume of a market, in USDC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStats {
+    pub condition_id: String,
+    pub liquidity: Decimal,
+    pub volume: Decimal,
+}
+
+/// Confirmed on-chain merge or redemption that returned USDC
+#[derive(Debug, Clone)]
+pub struct CtfReceipt {
+    pub condition_id: String,
+    /// USDC returned (sets merged, or winning shares redeemed)
+    pub amount: Decimal,
+    pub tx_hash: String,
+    pub gas_used: u64,
 }
 
 /// WebSocket subscription message
@@ -194,30 +489,303 @@ pub enum WsEvent {
     TickSizeChange { asset_id: String, tick_size: Decimal },
 }
 
+/// Which way an arbitrage trades the complete set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArbDirection {
+    /// Buy YES + NO below $1 (complete set costs less than its payout)
+    BuyBoth,
+    /// Sell YES + NO above $1 (requires holding or splitting complete sets)
+    SellBoth,
+}
+
+impl std::fmt::Display for ArbDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArbDirection::BuyBoth => write!(f, "BUY BOTH"),
+            ArbDirection::SellBoth => write!(f, "SELL BOTH"),
+        }
+    }
+}
+
 /// Arbitrage opportunity
+///
+/// Price fields refer to asks for `BuyBoth` and to bids for `SellBoth`. The
+/// `yes_*` side is the YES outcome, or the first outcome for other labels.
 #[derive(Debug, Clone)]
 pub struct ArbitrageOpportunity {
+    /// Unique id linking journal records for this opportunity
+    pub id: String,
     pub market_id: String,
+    pub direction: ArbDirection,
     pub yes_token_id: String,
     pub no_token_id: String,
-    pub yes_ask_price: Decimal,
-    pub no_ask_price: Decimal,
+    /// Neg-risk markets settle through the neg-risk adapter
+    pub neg_risk: bool,
+    /// Original outcome label of the `yes_*` side (e.g. "Yes", "Up")
+    pub yes_outcome: String,
+    /// Original outcome label of the `no_*` side (e.g. "No", "Down")
+    pub no_outcome: String,
+    /// Top of book YES price: the best ask when buying, the best bid when selling
+    pub yes_price: Decimal,
+    /// Top of book NO price: the best ask when buying, the best bid when selling
+    pub no_price: Decimal,
+    /// Top of book YES + NO
     pub combined_price: Decimal,
+    /// Top of book profit per share, net of fees
     pub profit_per_share: Decimal,
+    /// Volume-weighted YES price over `max_size`
+    pub yes_vwap: Decimal,
+    /// Volume-weighted NO price over `max_size`
+    pub no_vwap: Decimal,
+    /// Volume-weighted YES + NO over `max_size`
+    pub combined_vwap: Decimal,
+    /// Worst YES level touched when filling `max_size`
+    pub yes_marginal_price: Decimal,
+    /// Worst NO level touched when filling `max_size`
+    pub no_marginal_price: Decimal,
+    /// Largest size whose combined VWAP stays within the threshold
     pub max_size: Decimal,
+    /// Expected net profit when filling all of `max_size`
+    pub total_expected_profit: Decimal,
+    /// Matched YES+NO chunks, best first
+    pub levels: Vec<DepthLevel>,
+    /// Fees and fixed costs for this market
+    pub costs: CostModel,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// A matched chunk of YES and NO depth traded together
+#[derive(Debug, Clone)]
+pub struct DepthLevel {
+    pub size: Decimal,
+    pub yes_price: Decimal,
+    pub no_price: Decimal,
+}
+
 impl ArbitrageOpportunity {
-    /// Calculate expected profit for a given size
+    /// USDC notional of trading `size` shares of each side by walking the matched depth
+    ///
+    /// This is the cost for buys and the proceeds for sells. Sizes beyond
+    /// `max_size` are capped, since there is no profitable depth past it.
+    pub fn fill_notional(&self, size: Decimal) -> (Decimal, Decimal) {
+        let mut remaining = size.min(self.max_size);
+        let mut yes_notional = Decimal::ZERO;
+        let mut no_notional = Decimal::ZERO;
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            yes_notional += take * level.yes_price;
+            no_notional += take * level.no_price;
+            remaining -= take;
+        }
+
+        (yes_notional, no_notional)
+    }
+
+    /// USDC spent buying `size` of each side through the matched depth, including taker fees
+    pub fn fill_cost(&self, size: Decimal) -> Decimal {
+        let (yes_cost, no_cost) = self.leg_costs(size);
+        yes_cost + no_cost
+    }
+
+    /// USDC spent on each side buying `size` of it through the matched depth, including taker fees
+    pub fn leg_costs(&self, size: Decimal) -> (Decimal, Decimal) {
+        let mut remaining = size.min(self.max_size);
+        let mut yes_cost = Decimal::ZERO;
+        let mut no_cost = Decimal::ZERO;
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            yes_cost += take * self.costs.cost_per_share(level.yes_price);
+            no_cost += take * self.costs.cost_per_share(level.no_price);
+            remaining -= take;
+        }
+
+        (yes_cost, no_cost)
+    }
+
+    /// Worst YES and NO level prices touched when trading `size` through the matched depth
+    pub fn marginal_prices(&self, size: Decimal) -> (Decimal, Decimal) {
+        let mut remaining = size.min(self.max_size);
+        let mut prices = (self.yes_price, self.no_price);
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            prices = (level.yes_price, level.no_price);
+            remaining -= level.size;
+        }
+
+        prices
+    }
+
+    /// Gross profit for a given size, before fees and fixed costs
+    pub fn gross_profit(&self, size: Decimal) -> Decimal {
+        let (yes_notional, no_notional) = self.fill_notional(size);
+        let sets = size.min(self.max_size);
+        match self.direction {
+            ArbDirection::BuyBoth => sets - yes_notional - no_notional,
+            ArbDirection::SellBoth => yes_notional + no_notional - sets,
+        }
+    }
+
+    /// Calculate expected profit for a given size, net of fees and fixed costs
     pub fn expected_profit(&self, size: Decimal) -> Decimal {
-        self.profit_per_share * size
+        let mut remaining = size.min(self.max_size);
+        if remaining <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let mut profit = -self.costs.fixed_cost;
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            profit += take
+                * self.costs.edge_per_share(self.direction, level.yes_price, level.no_price);
+            remaining -= take;
+        }
+
+        profit
+    }
+}
+
+/// A matched chunk of depth across every ladder of a basket
+#[derive(Debug, Clone)]
+pub struct BasketLevel {
+    pub size: Decimal,
+    /// Price per leg, in leg order
+    pub prices: Vec<Decimal>,
+}
+
+/// One leg (outcome) of a multi-outcome basket
+#[derive(Debug, Clone)]
+pub struct BasketLeg {
+    pub condition_id: String,
+    pub token_id: String,
+    pub label: String,
+    /// Best (top of book) ask
+    pub best_ask: Decimal,
+    /// Volume-weighted price over `max_size`
+    pub vwap: Decimal,
+    /// Worst level touched when filling `max_size`
+    pub marginal_price: Decimal,
+}
+
+/// Multi-outcome arbitrage: buy every outcome of a group for less than $1
+#[derive(Debug, Clone)]
+pub struct BasketOpportunity {
+    /// Unique id linking journal records for this opportunity
+    pub id: String,
+    pub group_id: String,
+    pub description: String,
+    /// Neg-risk groups trade through the neg-risk exchange
+    pub neg_risk: bool,
+    pub legs: Vec<BasketLeg>,
+    /// Sum of top of book asks
+    pub combined_price: Decimal,
+    /// Top of book profit per share, net of fees
+    pub profit_per_share: Decimal,
+    /// Sum of leg VWAPs over `max_size`
+    pub combined_vwap: Decimal,
+    /// Largest size whose combined VWAP stays under the threshold
+    pub max_size: Decimal,
+    /// Expected net profit when filling all of `max_size`
+    pub total_expected_profit: Decimal,
+    /// Matched chunks across all legs, cheapest first
+    pub levels: Vec<BasketLevel>,
+    /// Fees and fixed costs for this group
+    pub costs: CostModel,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl BasketOpportunity {
+    /// USDC cost per leg of buying `size` of every outcome by walking the matched depth
+    pub fn fill_notional(&self, size: Decimal) -> Vec<Decimal> {
+        let mut remaining = size.min(self.max_size);
+        let mut notional = vec![Decimal::ZERO; self.legs.len()];
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            for (total, price) in notional.iter_mut().zip(&level.prices) {
+                *total += take * price;
+            }
+            remaining -= take;
+        }
+
+        notional
+    }
+
+    /// USDC spent buying `size` of every outcome through the matched depth, including taker fees
+    pub fn fill_cost(&self, size: Decimal) -> Decimal {
+        let mut remaining = size.min(self.max_size);
+        let mut cost = Decimal::ZERO;
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            cost += take * level.prices.iter().map(|p| self.costs.cost_per_share(*p)).sum::<Decimal>();
+            remaining -= take;
+        }
+
+        cost
+    }
+
+    /// Worst level price per leg touched when buying `size` through the matched depth
+    pub fn marginal_prices(&self, size: Decimal) -> Vec<Decimal> {
+        let mut remaining = size.min(self.max_size);
+        let mut prices: Vec<Decimal> = self.legs.iter().map(|l| l.best_ask).collect();
+
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            prices = level.prices.clone();
+            remaining -= level.size;
+        }
+
+        prices
+    }
+
+    /// Calculate expected profit for a given size, net of fees and fixed costs
+    pub fn expected_profit(&self, size: Decimal) -> Decimal {
+        let mut remaining = size.min(self.max_size);
+        if remaining <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let mut profit = -self.costs.fixed_cost;
+        for level in &self.levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let take = level.size.min(remaining);
+            let cost: Decimal = level.prices.iter().map(|p| self.costs.cost_per_share(*p)).sum();
+            profit += take * (Decimal::ONE - cost);
+            remaining -= take;
+        }
+
+        profit
     }
 }
 
-/// Markets list response with pagination
+/// One page of the markets listing
 #[derive(Debug, Clone, Deserialize)]
 pub struct MarketsResponse {
     pub data: Vec<Market>,
+    /// Cursor of the following page, `None` on the last one
     pub next_cursor: Option<String>,
 }
@@ -0,0 +1,328 @@
+//! Backtesting over recorded market data
+//! Runs the detector over a recording per parameter set and simulates fills against the recorded depth
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::api::{ArbDirection, ArbitrageOpportunity, BasketOpportunity, Side};
+use crate::config::Config;
+use crate::fees::CostModel;
+use crate::orderbook::OrderBookStore;
+use crate::recording::{RecordedData, Replay};
+use crate::scanner::ArbitrageDetector;
+use crate::simulation::{simulate_trade, SimOrder};
+use crate::websocket::WsClient;
+
+/// Detection parameters swept by a backtest
+#[derive(Debug, Clone, Copy)]
+pub struct ParamSet {
+    pub max_combined_price: Decimal,
+    pub min_profit_threshold: Decimal,
+    pub order_size: Decimal,
+}
+
+impl std::fmt::Display for ParamSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "max combined {} | min profit {} | size {}",
+            self.max_combined_price, self.min_profit_threshold, self.order_size
+        )
+    }
+}
+
+/// Execution assumptions shared by every parameter set
+#[derive(Debug, Clone)]
+pub struct SimSettings {
+    /// Delay between detection and the orders reaching the book
+    pub latency: Duration,
+    /// Minimum time between trades on the same market or group
+    pub cooldown: Duration,
+}
+
+/// Results of one parameter set
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub params: ParamSet,
+    pub opportunities: u64,
+    pub trades: u64,
+    /// Every leg filled
+    pub filled: u64,
+    /// Some legs filled and had to be unwound
+    pub partial: u64,
+    /// No leg filled
+    pub missed: u64,
+    /// Profit the detector expected at detection time, for trades that filled
+    pub expected_profit: Decimal,
+    /// Simulated P&L, including unwinds of partial fills and stranded buys at cost
+    pub realized_pnl: Decimal,
+}
+
+impl BacktestReport {
+    fn new(params: ParamSet) -> Self {
+        Self {
+            params,
+            opportunities: 0,
+            trades: 0,
+            filled: 0,
+            partial: 0,
+            missed: 0,
+            expected_profit: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+        }
+    }
+
+    /// Share of trades whose legs all filled
+    pub fn fill_rate(&self) -> Decimal {
+        ratio(self.filled, self.trades)
+    }
+
+    /// Share of trades left one-legged
+    pub fn partial_rate(&self) -> Decimal {
+        ratio(self.partial, self.trades)
+    }
+}
+
+/// One leg of a simulated trade
+#[derive(Debug, Clone)]
+struct SimLeg {
+    token_id: String,
+    side: Side,
+    limit: Decimal,
+}
+
+/// A detected trade waiting out the latency before it hits the book
+#[derive(Debug, Clone)]
+struct PendingTrade {
+    key: String,
+    execute_at: DateTime<Utc>,
+    legs: Vec<SimLeg>,
+    size: Decimal,
+    costs: CostModel,
+    expected_profit: Decimal,
+}
+
+/// Detector, open trades and results for one parameter set
+struct Run {
+    detector: ArbitrageDetector,
+    pending: Vec<PendingTrade>,
+    last_trade: HashMap<String, DateTime<Utc>>,
+    report: BacktestReport,
+}
+
+impl Run {
+    fn new(base: &Config, params: ParamSet) -> Self {
+        let mut config = base.clone();
+        config.max_combined_price = params.max_combined_price;
+        config.min_profit_threshold = params.min_profit_threshold;
+        config.order_size = params.order_size;
+
+        Self {
+            detector: ArbitrageDetector::new(config),
+            pending: Vec::new(),
+            last_trade: HashMap::new(),
+            report: BacktestReport::new(params),
+        }
+    }
+
+    /// Queue a pair trade unless the market is cooling down or already in flight
+    fn on_opportunity(&mut self, opp: &ArbitrageOpportunity, at: DateTime<Utc>, settings: &SimSettings) {
+        self.report.opportunities += 1;
+
+        let size = self.report.params.order_size.min(opp.max_size);
+        if size < Decimal::ONE || !self.ready(&opp.market_id, at, settings) {
+            return;
+        }
+
+        let (side, round): (Side, fn(&CostModel, Decimal) -> Decimal) = match opp.direction {
+            ArbDirection::BuyBoth => (Side::Buy, CostModel::round_down_to_tick),
+            ArbDirection::SellBoth => (Side::Sell, CostModel::round_up_to_tick),
+        };
+        let (yes_level, no_level) = opp.marginal_prices(size);
+
+        self.queue(PendingTrade {
+            key: opp.market_id.clone(),
+            execute_at: at + latency(settings),
+            legs: vec![
+                SimLeg { token_id: opp.yes_token_id.clone(), side, limit: round(&opp.costs, yes_level) },
+                SimLeg { token_id: opp.no_token_id.clone(), side, limit: round(&opp.costs, no_level) },
+            ],
+            size,
+            costs: opp.costs,
+            expected_profit: opp.expected_profit(size),
+        }, at);
+    }
+
+    /// Queue a basket trade unless the group is cooling down or already in flight
+    fn on_basket(&mut self, opp: &BasketOpportunity, at: DateTime<Utc>, settings: &SimSettings) {
+        self.report.opportunities += 1;
+
+        let size = self.report.params.order_size.min(opp.max_size);
+        if size < Decimal::ONE || !self.ready(&opp.group_id, at, settings) {
+            return;
+        }
+
+        let legs = opp
+            .legs
+            .iter()
+            .zip(opp.marginal_prices(size))
+            .map(|(leg, level)| SimLeg {
+                token_id: leg.token_id.clone(),
+                side: Side::Buy,
+                limit: opp.costs.round_down_to_tick(level),
+            })
+            .collect();
+
+        self.queue(PendingTrade {
+            key: opp.group_id.clone(),
+            execute_at: at + latency(settings),
+            legs,
+            size,
+            costs: opp.costs,
+            expected_profit: opp.expected_profit(size),
+        }, at);
+    }
+
+    fn ready(&self, key: &str, at: DateTime<Utc>, settings: &SimSettings) -> bool {
+        let in_flight = self.pending.iter().any(|t| t.key == key);
+        let cooling = self
+            .last_trade
+            .get(key)
+            .is_some_and(|last| (at - *last).to_std().unwrap_or_default() < settings.cooldown);
+        !in_flight && !cooling
+    }
+
+    fn queue(&mut self, trade: PendingTrade, at: DateTime<Utc>) {
+        self.last_trade.insert(trade.key.clone(), at);
+        self.report.trades += 1;
+        self.pending.push(trade);
+    }
+
+    /// Fill every trade due by `now` against the current books
+    fn settle_due(&mut self, now: Option<DateTime<Utc>>, books: &OrderBookStore) {
+        let (due, waiting): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|t| match now {
+                Some(now) => t.execute_at <= now,
+                None => true,
+            });
+        self.pending = waiting;
+        for trade in due {
+            settle(&trade, books, &mut self.report);
+        }
+    }
+}
+
+/// Simulate one trade's FOK legs and any unwind against the books
+///
+/// Fills and settles exactly as paper trading does (see `simulate_trade`),
+/// so stranded buys count at cost rather than as a loss.
+fn settle(trade: &PendingTrade, books: &OrderBookStore, report: &mut BacktestReport) {
+    let fetched: Vec<_> = trade.legs.iter().map(|leg| books.get(&leg.token_id)).collect();
+    let orders: Vec<SimOrder> = trade.legs.iter().map(|leg| SimOrder { side: leg.side, limit: leg.limit }).collect();
+    let sim = simulate_trade(&fetched, &orders, trade.size, &trade.costs);
+
+    if sim.sets >= trade.size {
+        report.filled += 1;
+        report.expected_profit += trade.expected_profit;
+    } else if sim.legs.iter().any(|leg| leg.filled > Decimal::ZERO) {
+        report.partial += 1;
+    } else {
+        report.missed += 1;
+    }
+    report.realized_pnl += sim.pnl();
+}
+
+/// Replay a recording once, running every parameter set side by side
+///
+/// Market data is shared; each parameter set has its own detector, cooldowns
+/// and open trades. Trades fill against the books as they stood `latency`
+/// after detection.
+pub async fn run(
+    path: impl AsRef<Path>,
+    base: &Config,
+    params: &[ParamSet],
+    settings: &SimSettings,
+) -> Result<Vec<BacktestReport>> {
+    let mut runs: Vec<Run> = params.iter().map(|p| Run::new(base, *p)).collect();
+
+    let mut replay = Replay::open(path, 0.0).await?;
+    let books = OrderBookStore::new();
+
+    while let Some(event) = replay.next().await? {
+        // Books only change below, so anything due before this event sees the prior state
+        for run in &mut runs {
+            run.settle_due(Some(event.at), &books);
+            run.detector.set_time(event.at);
+        }
+
+        match event.data {
+            RecordedData::Markets { markets } => {
+                for run in &mut runs {
+                    run.detector.load_markets(&markets);
+                }
+            }
+            RecordedData::Groups { members } => {
+                for run in &mut runs {
+                    run.detector.set_group_members(members.clone());
+                }
+            }
+            RecordedData::Books { books: fetched } => {
+                for book in &fetched {
+                    books.apply_snapshot(
+                        &book.asset_id,
+                        Some(&book.market),
+                        &book.bids,
+                        &book.asks,
+                        Some(&book.hash),
+                        Some(&book.timestamp),
+                    );
+                }
+                for run in &mut runs {
+                    for opp in run.detector.scan_books(&fetched) {
+                        run.on_opportunity(&opp, event.at, settings);
+                    }
+                    for opp in run.detector.scan_basket_books(&fetched) {
+                        run.on_basket(&opp, event.at, settings);
+                    }
+                }
+            }
+            RecordedData::Ws { frame } => {
+                for update in WsClient::handle_message(&frame, &books)? {
+                    for run in &mut runs {
+                        if let Some(opp) = run.detector.check_cached_pair(&update.asset_id, &books) {
+                            run.on_opportunity(&opp, event.at, settings);
+                        }
+                        if let Some(opp) = run.detector.check_cached_group(&update.asset_id, &books) {
+                            run.on_basket(&opp, event.at, settings);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Whatever is still in flight fills against the final books
+    for run in &mut runs {
+        run.settle_due(None, &books);
+    }
+
+    Ok(runs.into_iter().map(|r| r.report).collect())
+}
+
+fn latency(settings: &SimSettings) -> chrono::Duration {
+    chrono::Duration::from_std(settings.latency).unwrap_or_default()
+}
+
+fn ratio(part: u64, whole: u64) -> Decimal {
+    if whole == 0 {
+        Decimal::ZERO
+    } else {
+        Decimal::from(part) / Decimal::from(whole)
+    }
+}
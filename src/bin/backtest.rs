@@ -0,0 +1,131 @@
+//! Backtest arbitrage parameters over a recorded market data session
+//!
+//! Record a session with `RECORD_DIR=recordings cargo run`, then sweep
+//! parameters over it:
+//!
+//! ```bash
+//! cargo run --bin backtest -- recordings/session-20250101-120000.jsonl \
+//!     --max-combined 0.98,0.99 --min-profit 0.005,0.01 --order-size 10,50 --latency-ms 250
+//! ```
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+use polymarket_arb::backtest::{self, ParamSet, SimSettings};
+use polymarket_arb::config::Config;
+
+const USAGE: &str = "Usage: backtest <recording.jsonl> [options]
+
+Options (lists are comma-separated; every combination is run):
+  --max-combined <list>  Trigger when combined price < this (default 0.99)
+  --min-profit <list>    Minimum net profit per share (default 0.01)
+  --order-size <list>    Shares per trade (default 10)
+  --latency-ms <ms>      Detection-to-book delay for simulated orders (default 250)
+  --cooldown-ms <ms>     Minimum time between trades on one market (default 5000)
+  --fixed-cost <usdc>    Fixed cost per trade (default 0.01)
+  --sell-side            Also sell sets when combined bids exceed MIN_COMBINED_SELL_PRICE
+  --multi-outcome        Also trade multi-outcome baskets";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Scanner logs are noise across a whole recording; RUST_LOG can bring them back
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(Level::WARN.into()))
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut max_combined = vec![Decimal::new(99, 2)];
+    let mut min_profit = vec![Decimal::new(1, 2)];
+    let mut order_size = vec![Decimal::from(10)];
+    let mut settings = SimSettings {
+        latency: Duration::from_millis(250),
+        cooldown: Duration::from_secs(5),
+    };
+    let mut base = Config {
+        dry_run: true,
+        ..Config::default()
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{} needs a value\n\n{}", arg, USAGE));
+        match arg.as_str() {
+            "--max-combined" => max_combined = parse_list(&value()?)?,
+            "--min-profit" => min_profit = parse_list(&value()?)?,
+            "--order-size" => order_size = parse_list(&value()?)?,
+            "--latency-ms" => settings.latency = Duration::from_millis(value()?.parse().context("Invalid --latency-ms")?),
+            "--cooldown-ms" => settings.cooldown = Duration::from_millis(value()?.parse().context("Invalid --cooldown-ms")?),
+            "--fixed-cost" => base.fixed_cost_per_trade = Decimal::from_str(&value()?).context("Invalid --fixed-cost")?,
+            "--sell-side" => base.enable_sell_side = true,
+            "--multi-outcome" => base.enable_multi_outcome = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => anyhow::bail!("Unexpected argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    let path = path.with_context(|| format!("Missing recording file\n\n{}", USAGE))?;
+
+    let mut params = Vec::new();
+    for &max_combined_price in &max_combined {
+        for &min_profit_threshold in &min_profit {
+            for &order_size in &order_size {
+                params.push(ParamSet { max_combined_price, min_profit_threshold, order_size });
+            }
+        }
+    }
+
+    println!(
+        "Backtesting {} parameter sets over {} (latency {:?}, cooldown {:?})",
+        params.len(),
+        path,
+        settings.latency,
+        settings.cooldown
+    );
+
+    let reports = backtest::run(&path, &base, &params, &settings).await?;
+
+    println!();
+    println!(
+        "{:>8} {:>8} {:>6} | {:>6} {:>6} {:>6} {:>7} {:>6} | {:>6} {:>8} | {:>10} {:>10}",
+        "max comb", "min prof", "size", "opps", "trades", "filled", "partial", "missed",
+        "fill %", "partial %", "expected $", "P&L $"
+    );
+    for r in &reports {
+        println!(
+            "{:>8} {:>8} {:>6} | {:>6} {:>6} {:>6} {:>7} {:>6} | {:>6} {:>8} | {:>10} {:>10}",
+            r.params.max_combined_price,
+            r.params.min_profit_threshold,
+            r.params.order_size,
+            r.opportunities,
+            r.trades,
+            r.filled,
+            r.partial,
+            r.missed,
+            (r.fill_rate() * Decimal::ONE_HUNDRED).round_dp(1),
+            (r.partial_rate() * Decimal::ONE_HUNDRED).round_dp(1),
+            r.expected_profit.round_dp(4),
+            r.realized_pnl.round_dp(4)
+        );
+    }
+
+    if let Some(best) = reports.iter().max_by_key(|r| r.realized_pnl) {
+        println!();
+        println!("Best P&L: ${} with {}", best.realized_pnl.round_dp(4), best.params);
+    }
+
+    Ok(())
+}
+
+/// Parse a comma-separated list of decimals
+fn parse_list(raw: &str) -> Result<Vec<Decimal>> {
+    raw.split(',')
+        .map(|v| Decimal::from_str(v.trim()).with_context(|| format!("Invalid number {}", v)))
+        .collect()
+}
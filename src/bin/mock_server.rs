@@ -0,0 +1,73 @@
+//! Stand-in Polymarket server for running the bot offline
+//!
+//! Serve a fixture, then point the bot at it:
+//!
+//! ```bash
+//! cargo run --features mock-server --bin mock_server -- fixtures/pair_arb.json --port 8080
+//! CLOB_HOST=http://127.0.0.1:8080 DATA_API_HOST=http://127.0.0.1:8080 \
+//!     WS_URL=ws://127.0.0.1:8080/ws/market CRYPTO_ONLY=false cargo run
+//! ```
+
+use anyhow::{Context, Result};
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+use polymarket_arb::mock_server::{Fixture, MockServer};
+
+const USAGE: &str = "Usage: mock_server <fixture.json> [options]
+
+Options:
+  --port <port>  Port to listen on (default 8080, 0 picks a free one)";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(Level::INFO.into()))
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut port = 8080;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{} needs a value\n\n{}", arg, USAGE));
+        match arg.as_str() {
+            "--port" => port = value()?.parse().context("Invalid --port")?,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => anyhow::bail!("Unexpected argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    let path = path.with_context(|| format!("Missing fixture file\n\n{}", USAGE))?;
+
+    let fixture = Fixture::load(&path)?;
+    let server = MockServer::start(fixture, port).await?;
+
+    println!("Serving {} (Ctrl+C to stop)", path);
+    println!("  CLOB_HOST={}", server.clob_host());
+    println!("  DATA_API_HOST={}", server.data_api_host());
+    println!("  WS_URL={}", server.ws_url());
+    println!("  GAMMA_HOST={}", server.gamma_host());
+
+    tokio::signal::ctrl_c().await.context("Failed to listen for Ctrl+C")?;
+
+    let orders = server.exchange().orders();
+    println!();
+    println!("{} orders received", orders.len());
+    for (request, response) in &orders {
+        println!(
+            "  {} {} {} @ {}: {}",
+            request.side,
+            request.size,
+            request.label,
+            request.price,
+            if response.success { "matched".to_string() } else { response.error_msg.clone().unwrap_or_default() }
+        );
+    }
+    println!("USDC balance: ${}", server.exchange().balance());
+
+    Ok(())
+}
@@ -5,6 +5,15 @@ use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
+use crate::api::client::{CLOB_HOST, DATA_API_HOST, GAMMA_HOST};
+use crate::api::ctf::POLYGON_RPC_URL;
+use crate::api::{CtfConfig, OrderPolicy};
+use crate::discovery::{UpDownSeries, UPDOWN_ASSETS};
+use crate::market_filter::{pattern, MarketFilter, CRYPTO_QUESTION_PATTERN};
+use crate::paper::PaperSettings;
+use crate::risk::RiskLimits;
+use crate::websocket::WS_URL;
+
 /// Bot configuration
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -17,9 +26,111 @@ pub struct Config {
     /// Minimum profit per share to execute (e.g., 0.01 = 1¢)
     pub min_profit_threshold: Decimal,
 
+    /// Minimum combined bid to trigger sell-side arbitrage (e.g., 1.01 = $1.01)
+    pub min_combined_sell_price: Decimal,
+
+    /// Also detect and execute sell-side arbitrage (YES bid + NO bid > $1)
+    /// Requires the wallet to hold complete sets to sell
+    pub enable_sell_side: bool,
+
+    /// Also scan multi-outcome (neg-risk) groups for basket arbitrage
+    pub enable_multi_outcome: bool,
+
     /// Order size in USDC
     pub order_size: Decimal,
 
+    /// Fixed USDC cost per arbitrage (gas for merge/redeem), netted from profit
+    pub fixed_cost_per_trade: Decimal,
+
+    /// Price tolerance per share beyond the detected level when capping limit orders
+    pub order_slippage: Decimal,
+
+    /// Max price move per share accepted when retrying a failed leg
+    pub recovery_max_slippage: Decimal,
+
+    /// Re-priced retries of a failed leg before unwinding the filled one
+    pub recovery_max_retries: u32,
+
+    /// Delay between recovery retries in milliseconds
+    pub recovery_retry_delay_ms: u64,
+
+    /// Max total cost basis of held tokens, in USDC
+    pub max_capital_deployed: Decimal,
+
+    /// Max cost basis held in the markets of a single trade, in USDC
+    pub max_market_exposure: Decimal,
+
+    /// Max cost basis of unhedged shares before new buys are refused, in USDC
+    pub max_unhedged_exposure: Decimal,
+
+    /// Max executions per rolling minute
+    pub max_trades_per_minute: u32,
+
+    /// Realized loss since UTC midnight that halts trading, in USDC
+    pub max_daily_loss: Decimal,
+
+    /// Merge bought YES+NO sets back into USDC right after a successful buy
+    pub enable_merge: bool,
+
+    /// Redeem winning tokens held to resolution in the background
+    pub enable_redeem: bool,
+
+    /// How often to check held positions for resolution, in seconds
+    pub redeem_interval_secs: u64,
+
+    /// How often to reconcile the position ledger with the account, in seconds
+    pub reconcile_interval_secs: u64,
+
+    /// CLOB API base URL (point at the mock server for offline runs)
+    pub clob_host: String,
+
+    /// Data API base URL, used for account positions
+    pub data_api_host: String,
+
+    /// Market WebSocket channel URL
+    pub ws_url: String,
+
+    /// Gamma API base URL, for market liquidity and volume
+    pub gamma_host: String,
+
+    /// JSON-RPC endpoint for on-chain merges and redemptions (point at a local chain for testing)
+    pub rpc_url: String,
+
+    /// Overrides for the CTF, collateral and neg-risk adapter addresses
+    pub ctf_address: Option<String>,
+    pub collateral_address: Option<String>,
+    pub neg_risk_adapter_address: Option<String>,
+
+    /// Creating this file stops trading and shuts the bot down
+    pub kill_switch_file: String,
+
+    /// SQLite file recording opportunities, orders and realized P&L
+    pub journal_path: String,
+
+    /// Directory to record raw market data sessions into (disabled if unset)
+    pub record_dir: Option<String>,
+
+    /// Recording to replay through the scanner instead of trading live
+    pub replay_file: Option<String>,
+
+    /// Replay rate: 1.0 is real time, higher is faster, 0 is as fast as possible
+    pub replay_speed: f64,
+
+    /// Simulate fills against live books in dry run mode instead of skipping trades
+    pub paper_trading: bool,
+
+    /// Virtual USDC the paper account starts with
+    pub paper_balance: Decimal,
+
+    /// Assumed delay before paper orders reach the book, in milliseconds
+    pub paper_latency_ms: u64,
+
+    /// Assumed price move per share against paper orders while in flight
+    pub paper_price_move: Decimal,
+
+    /// Share of displayed depth assumed still available to paper orders (0-1)
+    pub paper_depth_fraction: Decimal,
+
     /// Polling interval in milliseconds
     pub poll_interval_ms: u64,
 
@@ -29,9 +140,37 @@ pub struct Config {
     /// Maximum concurrent markets to scan
     pub max_markets: usize,
 
-    /// Only trade crypto markets (15-min expiry)
+    /// Pages of the markets listing read per refresh (the rest is picked up on later refreshes)
+    pub max_market_pages: usize,
+
+    /// Interval between full re-listings of markets, in seconds; refreshes in between only read new pages
+    pub market_full_refresh_secs: u64,
+
+    /// Markets seen open this many seconds ago or less aren't looked up again when the listing doesn't confirm them
+    pub market_recheck_secs: u64,
+
+    /// Only trade crypto markets (15-min expiry); the default question filter
     pub crypto_only: bool,
 
+    /// Which listed markets are scanned
+    pub market_filter: MarketFilter,
+
+    /// Assets whose recurring up/down markets are discovered by schedule
+    /// (every known asset by default with `crypto_only`, none otherwise; empty disables)
+    pub updown_assets: Vec<String>,
+
+    /// Window lengths of the discovered up/down series, in minutes
+    pub updown_intervals: Vec<u32>,
+
+    /// How far past the running window to look for upcoming markets, in seconds
+    pub discovery_lookahead_secs: u64,
+
+    /// Interval between discovery lookups, in seconds
+    pub discovery_interval_secs: u64,
+
+    /// Stop opening trades in a market this many seconds before it ends (or its game starts)
+    pub expiry_cutoff_secs: u64,
+
     /// Dry run mode - detect but don't execute
     pub dry_run: bool,
 }
@@ -54,11 +193,146 @@ impl Config {
         let min_profit_threshold = Decimal::from_str(&min_profit_threshold)
             .context("Invalid MIN_PROFIT_THRESHOLD")?;
 
+        let min_combined_sell_price = std::env::var("MIN_COMBINED_SELL_PRICE")
+            .unwrap_or_else(|_| "1.01".to_string());
+        let min_combined_sell_price = Decimal::from_str(&min_combined_sell_price)
+            .context("Invalid MIN_COMBINED_SELL_PRICE")?;
+
+        let enable_sell_side = std::env::var("ENABLE_SELL_SIDE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let enable_multi_outcome = std::env::var("ENABLE_MULTI_OUTCOME")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
         let order_size = std::env::var("ORDER_SIZE")
             .unwrap_or_else(|_| "10.0".to_string()); // $10 default
         let order_size = Decimal::from_str(&order_size)
             .context("Invalid ORDER_SIZE")?;
 
+        let fixed_cost_per_trade = std::env::var("FIXED_COST_PER_TRADE")
+            .unwrap_or_else(|_| "0.01".to_string()); // ~1¢ of gas default
+        let fixed_cost_per_trade = Decimal::from_str(&fixed_cost_per_trade)
+            .context("Invalid FIXED_COST_PER_TRADE")?;
+
+        let order_slippage = std::env::var("ORDER_SLIPPAGE")
+            .unwrap_or_else(|_| "0".to_string()); // Cap at the observed level by default
+        let order_slippage = Decimal::from_str(&order_slippage)
+            .context("Invalid ORDER_SLIPPAGE")?;
+
+        let recovery_max_slippage = std::env::var("RECOVERY_MAX_SLIPPAGE")
+            .unwrap_or_else(|_| "0.02".to_string()); // 2¢ default
+        let recovery_max_slippage = Decimal::from_str(&recovery_max_slippage)
+            .context("Invalid RECOVERY_MAX_SLIPPAGE")?;
+
+        let recovery_max_retries = std::env::var("RECOVERY_MAX_RETRIES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .context("Invalid RECOVERY_MAX_RETRIES")?;
+
+        let recovery_retry_delay_ms = std::env::var("RECOVERY_RETRY_DELAY_MS")
+            .unwrap_or_else(|_| "250".to_string())
+            .parse()
+            .context("Invalid RECOVERY_RETRY_DELAY_MS")?;
+
+        let max_capital_deployed = std::env::var("MAX_CAPITAL_DEPLOYED")
+            .unwrap_or_else(|_| "500".to_string());
+        let max_capital_deployed = Decimal::from_str(&max_capital_deployed)
+            .context("Invalid MAX_CAPITAL_DEPLOYED")?;
+
+        let max_market_exposure = std::env::var("MAX_MARKET_EXPOSURE")
+            .unwrap_or_else(|_| "100".to_string());
+        let max_market_exposure = Decimal::from_str(&max_market_exposure)
+            .context("Invalid MAX_MARKET_EXPOSURE")?;
+
+        let max_unhedged_exposure = std::env::var("MAX_UNHEDGED_EXPOSURE")
+            .unwrap_or_else(|_| "25".to_string());
+        let max_unhedged_exposure = Decimal::from_str(&max_unhedged_exposure)
+            .context("Invalid MAX_UNHEDGED_EXPOSURE")?;
+
+        let max_trades_per_minute = std::env::var("MAX_TRADES_PER_MINUTE")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .context("Invalid MAX_TRADES_PER_MINUTE")?;
+
+        let max_daily_loss = std::env::var("MAX_DAILY_LOSS")
+            .unwrap_or_else(|_| "50".to_string());
+        let max_daily_loss = Decimal::from_str(&max_daily_loss)
+            .context("Invalid MAX_DAILY_LOSS")?;
+
+        let enable_merge = std::env::var("ENABLE_MERGE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let enable_redeem = std::env::var("ENABLE_REDEEM")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let redeem_interval_secs = std::env::var("REDEEM_INTERVAL_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .context("Invalid REDEEM_INTERVAL_SECS")?;
+
+        let reconcile_interval_secs = std::env::var("RECONCILE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .context("Invalid RECONCILE_INTERVAL_SECS")?;
+
+        let clob_host = std::env::var("CLOB_HOST")
+            .unwrap_or_else(|_| CLOB_HOST.to_string());
+        let data_api_host = std::env::var("DATA_API_HOST")
+            .unwrap_or_else(|_| DATA_API_HOST.to_string());
+        let ws_url = std::env::var("WS_URL")
+            .unwrap_or_else(|_| WS_URL.to_string());
+        let gamma_host = std::env::var("GAMMA_HOST")
+            .unwrap_or_else(|_| GAMMA_HOST.to_string());
+
+        let rpc_url = std::env::var("RPC_URL")
+            .unwrap_or_else(|_| POLYGON_RPC_URL.to_string());
+
+        let ctf_address = std::env::var("CTF_ADDRESS").ok();
+        let collateral_address = std::env::var("COLLATERAL_ADDRESS").ok();
+        let neg_risk_adapter_address = std::env::var("NEG_RISK_ADAPTER_ADDRESS").ok();
+
+        let kill_switch_file = std::env::var("KILL_SWITCH_FILE")
+            .unwrap_or_else(|_| "STOP_TRADING".to_string());
+
+        let journal_path = std::env::var("JOURNAL_PATH")
+            .unwrap_or_else(|_| "journal.db".to_string());
+
+        let record_dir = std::env::var("RECORD_DIR").ok();
+        let replay_file = std::env::var("REPLAY_FILE").ok();
+
+        let replay_speed = std::env::var("REPLAY_SPEED")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse()
+            .context("Invalid REPLAY_SPEED")?;
+
+        let paper_trading = std::env::var("PAPER_TRADING")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let paper_balance = std::env::var("PAPER_BALANCE")
+            .unwrap_or_else(|_| "1000".to_string());
+        let paper_balance = Decimal::from_str(&paper_balance)
+            .context("Invalid PAPER_BALANCE")?;
+
+        let paper_latency_ms = std::env::var("PAPER_LATENCY_MS")
+            .unwrap_or_else(|_| "250".to_string())
+            .parse()
+            .context("Invalid PAPER_LATENCY_MS")?;
+
+        let paper_price_move = std::env::var("PAPER_PRICE_MOVE")
+            .unwrap_or_else(|_| "0".to_string());
+        let paper_price_move = Decimal::from_str(&paper_price_move)
+            .context("Invalid PAPER_PRICE_MOVE")?;
+
+        let paper_depth_fraction = std::env::var("PAPER_DEPTH_FRACTION")
+            .unwrap_or_else(|_| "1".to_string());
+        let paper_depth_fraction = Decimal::from_str(&paper_depth_fraction)
+            .context("Invalid PAPER_DEPTH_FRACTION")?;
+
         let poll_interval_ms = std::env::var("POLL_INTERVAL_MS")
             .unwrap_or_else(|_| "2000".to_string()) // 2 seconds default
             .parse()
@@ -73,10 +347,78 @@ impl Config {
             .parse()
             .context("Invalid MAX_MARKETS")?;
 
+        let max_market_pages = std::env::var("MAX_MARKET_PAGES")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .context("Invalid MAX_MARKET_PAGES")?;
+
+        let market_full_refresh_secs = std::env::var("MARKET_FULL_REFRESH_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse()
+            .context("Invalid MARKET_FULL_REFRESH_SECS")?;
+
+        let market_recheck_secs = std::env::var("MARKET_RECHECK_SECS")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse()
+            .context("Invalid MARKET_RECHECK_SECS")?;
+
         let crypto_only = std::env::var("CRYPTO_ONLY")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(true); // Default to crypto markets for 15-min expiry
 
+        // An explicit question pattern replaces the crypto one
+        let question_include = match env_pattern("MARKET_QUESTION_INCLUDE")? {
+            Some(re) => Some(re),
+            None if crypto_only => Some(pattern(CRYPTO_QUESTION_PATTERN)?),
+            None => None,
+        };
+        let market_filter = MarketFilter {
+            question_include,
+            question_exclude: env_pattern("MARKET_QUESTION_EXCLUDE")?,
+            slug_include: env_pattern("MARKET_SLUG_INCLUDE")?,
+            slug_exclude: env_pattern("MARKET_SLUG_EXCLUDE")?,
+            tags: env_list("MARKET_TAGS"),
+            exclude_tags: env_list("MARKET_EXCLUDE_TAGS"),
+            min_liquidity: env_decimal("MIN_LIQUIDITY")?,
+            min_volume: env_decimal("MIN_VOLUME")?,
+            min_time_to_end: env_minutes("MARKET_MIN_MINUTES_TO_END")?,
+            max_time_to_end: env_minutes("MARKET_MAX_MINUTES_TO_END")?,
+            allow: env_list("MARKET_ALLOW").into_iter().collect(),
+            deny: env_list("MARKET_DENY").into_iter().collect(),
+        };
+
+        // Discovery only finds crypto markets, so it is off by default outside crypto-only runs
+        let updown_assets = std::env::var("UPDOWN_ASSETS")
+            .unwrap_or_else(|_| if crypto_only { UPDOWN_ASSETS.join(",") } else { String::new() })
+            .split(',')
+            .map(|a| a.trim().to_lowercase())
+            .filter(|a| !a.is_empty())
+            .collect();
+
+        let updown_intervals = std::env::var("UPDOWN_INTERVAL_MINUTES")
+            .unwrap_or_else(|_| "15".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.parse())
+            .collect::<Result<Vec<u32>, _>>()
+            .context("Invalid UPDOWN_INTERVAL_MINUTES")?;
+
+        let discovery_lookahead_secs = std::env::var("DISCOVERY_LOOKAHEAD_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .context("Invalid DISCOVERY_LOOKAHEAD_SECS")?;
+
+        let discovery_interval_secs = std::env::var("DISCOVERY_INTERVAL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .context("Invalid DISCOVERY_INTERVAL_SECS")?;
+
+        let expiry_cutoff_secs = std::env::var("EXPIRY_CUTOFF_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .context("Invalid EXPIRY_CUTOFF_SECS")?;
+
         let dry_run = std::env::var("DRY_RUN")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(true); // Default to dry run for safety
@@ -85,14 +427,108 @@ impl Config {
             private_key,
             max_combined_price,
             min_profit_threshold,
+            min_combined_sell_price,
+            enable_sell_side,
+            enable_multi_outcome,
             order_size,
+            fixed_cost_per_trade,
+            order_slippage,
+            recovery_max_slippage,
+            recovery_max_retries,
+            recovery_retry_delay_ms,
+            max_capital_deployed,
+            max_market_exposure,
+            max_unhedged_exposure,
+            max_trades_per_minute,
+            max_daily_loss,
+            enable_merge,
+            enable_redeem,
+            redeem_interval_secs,
+            reconcile_interval_secs,
+            clob_host,
+            data_api_host,
+            ws_url,
+            gamma_host,
+            rpc_url,
+            ctf_address,
+            collateral_address,
+            neg_risk_adapter_address,
+            kill_switch_file,
+            journal_path,
+            record_dir,
+            replay_file,
+            replay_speed,
+            paper_trading,
+            paper_balance,
+            paper_latency_ms,
+            paper_price_move,
+            paper_depth_fraction,
             poll_interval_ms,
             use_websocket,
             max_markets,
+            max_market_pages,
+            market_full_refresh_secs,
+            market_recheck_secs,
             crypto_only,
+            market_filter,
+            updown_assets,
+            updown_intervals,
+            discovery_lookahead_secs,
+            discovery_interval_secs,
+            expiry_cutoff_secs,
             dry_run,
         })
     }
+
+    /// CTF settings for on-chain merges and redemptions, with address overrides applied
+    pub fn ctf_config(&self) -> Result<CtfConfig> {
+        let mut ctf = CtfConfig {
+            rpc_url: self.rpc_url.clone(),
+            ..CtfConfig::default()
+        };
+        if let Some(addr) = &self.ctf_address {
+            ctf.ctf = addr.parse().context("Invalid CTF_ADDRESS")?;
+        }
+        if let Some(addr) = &self.collateral_address {
+            ctf.collateral = addr.parse().context("Invalid COLLATERAL_ADDRESS")?;
+        }
+        if let Some(addr) = &self.neg_risk_adapter_address {
+            ctf.neg_risk_adapter = addr.parse().context("Invalid NEG_RISK_ADAPTER_ADDRESS")?;
+        }
+        Ok(ctf)
+    }
+
+    /// Risk limits for the risk manager
+    pub fn risk_limits(&self) -> RiskLimits {
+        RiskLimits {
+            max_capital_deployed: self.max_capital_deployed,
+            max_market_exposure: self.max_market_exposure,
+            max_unhedged_exposure: self.max_unhedged_exposure,
+            max_trades_per_minute: self.max_trades_per_minute,
+            max_daily_loss: self.max_daily_loss,
+        }
+    }
+
+    /// Every up/down series to discover: each asset at each interval
+    pub fn updown_series(&self) -> Vec<UpDownSeries> {
+        self.updown_assets
+            .iter()
+            .flat_map(|asset| self.updown_intervals.iter().map(move |&minutes| UpDownSeries::new(asset, minutes)))
+            .collect()
+    }
+
+    /// Fill assumptions for paper trading, with live order pricing
+    pub fn paper_settings(&self) -> PaperSettings {
+        PaperSettings {
+            starting_balance: self.paper_balance,
+            latency: std::time::Duration::from_millis(self.paper_latency_ms),
+            price_move: self.paper_price_move,
+            depth_fraction: self.paper_depth_fraction,
+            orders: OrderPolicy {
+                slippage: self.order_slippage,
+            },
+        }
+    }
 }
 
 impl Default for Config {
@@ -101,12 +537,96 @@ impl Default for Config {
             private_key: String::new(),
             max_combined_price: Decimal::from_str("0.99").unwrap(),
             min_profit_threshold: Decimal::from_str("0.005").unwrap(),
+            min_combined_sell_price: Decimal::from_str("1.01").unwrap(),
+            enable_sell_side: false,
+            enable_multi_outcome: false,
             order_size: Decimal::from_str("10.0").unwrap(),
+            fixed_cost_per_trade: Decimal::from_str("0.01").unwrap(),
+            order_slippage: Decimal::ZERO,
+            recovery_max_slippage: Decimal::from_str("0.02").unwrap(),
+            recovery_max_retries: 3,
+            recovery_retry_delay_ms: 250,
+            max_capital_deployed: Decimal::from(500),
+            max_market_exposure: Decimal::from(100),
+            max_unhedged_exposure: Decimal::from(25),
+            max_trades_per_minute: 10,
+            max_daily_loss: Decimal::from(50),
+            enable_merge: false,
+            enable_redeem: false,
+            redeem_interval_secs: 60,
+            reconcile_interval_secs: 300,
+            clob_host: CLOB_HOST.to_string(),
+            data_api_host: DATA_API_HOST.to_string(),
+            ws_url: WS_URL.to_string(),
+            gamma_host: GAMMA_HOST.to_string(),
+            rpc_url: POLYGON_RPC_URL.to_string(),
+            ctf_address: None,
+            collateral_address: None,
+            neg_risk_adapter_address: None,
+            kill_switch_file: "STOP_TRADING".to_string(),
+            journal_path: "journal.db".to_string(),
+            record_dir: None,
+            replay_file: None,
+            replay_speed: 1.0,
+            paper_trading: false,
+            paper_balance: Decimal::from(1000),
+            paper_latency_ms: 250,
+            paper_price_move: Decimal::ZERO,
+            paper_depth_fraction: Decimal::ONE,
             poll_interval_ms: 2000,
             use_websocket: true,
             max_markets: 50,
+            max_market_pages: 20,
+            market_full_refresh_secs: 3600,
+            market_recheck_secs: 600,
             crypto_only: true,
+            market_filter: MarketFilter::crypto(),
+            updown_assets: UPDOWN_ASSETS.iter().map(|a| a.to_string()).collect(),
+            updown_intervals: vec![15],
+            discovery_lookahead_secs: 300,
+            discovery_interval_secs: 30,
+            expiry_cutoff_secs: 60,
             dry_run: true,
         }
     }
 }
+
+/// Unset or empty variables read as `None`
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+fn env_pattern(name: &str) -> Result<Option<regex::Regex>> {
+    env_value(name)
+        .map(|raw| pattern(&raw).with_context(|| format!("Invalid {}", name)))
+        .transpose()
+}
+
+fn env_decimal(name: &str) -> Result<Option<Decimal>> {
+    env_value(name)
+        .map(|raw| Decimal::from_str(raw.trim()).with_context(|| format!("Invalid {}", name)))
+        .transpose()
+}
+
+fn env_minutes(name: &str) -> Result<Option<chrono::Duration>> {
+    env_value(name)
+        .map(|raw| {
+            raw.trim()
+                .parse()
+                .map(chrono::Duration::minutes)
+                .with_context(|| format!("Invalid {}", name))
+        })
+        .transpose()
+}
+
+/// Comma-separated, lowercased
+fn env_list(name: &str) -> Vec<String> {
+    env_value(name)
+        .map(|raw| {
+            raw.split(',')
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,158 @@
+//! Scheduled market discovery
+//! Polymarket opens a crypto up/down market for every asset and interval
+//! window. Their slugs follow from the asset, the interval and the window
+//! start, so upcoming windows can be looked up before they begin trading.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::api::{Executor, Market};
+
+/// Assets with recurring up/down series
+pub const UPDOWN_ASSETS: [&str; 4] = ["btc", "eth", "sol", "xrp"];
+
+/// A recurring up/down series: one market per window of `interval_minutes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpDownSeries {
+    /// Slug prefix of the asset, e.g. "btc"
+    pub asset: String,
+    pub interval_minutes: u32,
+}
+
+/// One window of a series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeriesWindow {
+    pub slug: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl UpDownSeries {
+    pub fn new(asset: &str, interval_minutes: u32) -> Self {
+        Self {
+            asset: asset.trim().to_lowercase(),
+            interval_minutes: interval_minutes.max(1),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::minutes(self.interval_minutes.into())
+    }
+
+    /// Start of the window running at `at`; windows are aligned to the Unix epoch
+    pub fn window_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = i64::from(self.interval_minutes) * 60;
+        let start = at.timestamp().div_euclid(secs) * secs;
+        DateTime::from_timestamp(start, 0).unwrap_or(at)
+    }
+
+    /// Slug of the window starting at `start`, e.g. `btc-updown-15m-1767225600`
+    pub fn slug(&self, start: DateTime<Utc>) -> String {
+        format!("{}-updown-{}-{}", self.asset, self.interval_label(), start.timestamp())
+    }
+
+    /// The window running at `now` and every window starting within `lookahead`
+    pub fn windows(&self, now: DateTime<Utc>, lookahead: Duration) -> Vec<SeriesWindow> {
+        let mut windows = Vec::new();
+        let mut start = self.window_start(now);
+        while start <= now + lookahead {
+            let end = start + self.interval();
+            windows.push(SeriesWindow { slug: self.slug(start), start, end });
+            start = end;
+        }
+        windows
+    }
+
+    /// "15m", "4h"
+    fn interval_label(&self) -> String {
+        if self.interval_minutes.is_multiple_of(60) {
+            format!("{}h", self.interval_minutes / 60)
+        } else {
+            format!("{}m", self.interval_minutes)
+        }
+    }
+}
+
+impl std::fmt::Display for UpDownSeries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.asset.to_uppercase(), self.interval_label())
+    }
+}
+
+/// Looks up the markets of recurring series as their windows come up
+///
+/// Each window is looked up until its market is found open for orders, then
+/// remembered until it ends so it's only handed out once.
+pub struct MarketDiscovery {
+    series: Vec<UpDownSeries>,
+    lookahead: Duration,
+    /// End of every window already found, by slug
+    found: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MarketDiscovery {
+    /// Discover `series`, looking `lookahead` past the running window
+    pub fn new(series: Vec<UpDownSeries>, lookahead: Duration) -> Self {
+        Self {
+            series,
+            lookahead,
+            found: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.series.is_empty()
+    }
+
+    pub fn series(&self) -> &[UpDownSeries] {
+        &self.series
+    }
+
+    /// Current and upcoming windows whose markets haven't been found yet
+    pub fn pending(&self, now: DateTime<Utc>) -> Vec<SeriesWindow> {
+        let mut found = self.found.lock();
+        found.retain(|_, end| *end > now);
+
+        self.series
+            .iter()
+            .flat_map(|s| s.windows(now, self.lookahead))
+            .filter(|w| !found.contains_key(&w.slug))
+            .collect()
+    }
+
+    /// Look up the pending windows, returning the markets found for the first time
+    ///
+    /// Windows whose market isn't created or open for orders yet stay pending
+    /// and are looked up again on the next call.
+    pub async fn discover(&self, client: &Executor, now: DateTime<Utc>) -> Result<Vec<Market>> {
+        let pending = self.pending(now);
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let slugs: Vec<String> = pending.iter().map(|w| w.slug.clone()).collect();
+        let markets: Vec<Market> = client
+            .get_markets_by_slug(&slugs)
+            .await?
+            .into_iter()
+            .filter(|m| m.active && !m.closed && m.accepting_orders)
+            .collect();
+
+        let mut found = self.found.lock();
+        let mut discovered = Vec::with_capacity(markets.len());
+        for market in markets {
+            let Some(window) = pending.iter().find(|w| market.market_slug.as_deref() == Some(w.slug.as_str())) else {
+                continue;
+            };
+            if found.insert(window.slug.clone(), window.end).is_none() {
+                discovered.push(market);
+            }
+        }
+
+        debug!("Found {} of {} pending up/down windows", discovered.len(), pending.len());
+        Ok(discovered)
+    }
+}
@@ -0,0 +1,115 @@
+//! Trading cost model
+//! Nets taker fees, tick rounding and fixed per-trade costs out of gross profit
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::api::ArbDirection;
+
+/// Per-market trading costs applied to an arbitrage
+#[derive(Debug, Clone, Copy)]
+pub struct CostModel {
+    /// Taker fee rate for the market, in basis points
+    pub fee_rate_bps: Decimal,
+    /// Minimum price increment for the market
+    pub tick_size: Decimal,
+    /// Fixed USDC cost per arbitrage (gas for merge/redeem)
+    pub fixed_cost: Decimal,
+}
+
+impl CostModel {
+    /// Price rounded to the market's tick using `strategy`
+    fn round_to_tick(&self, price: Decimal, strategy: RoundingStrategy) -> Decimal {
+        if self.tick_size <= Decimal::ZERO {
+            return price;
+        }
+        (price / self.tick_size).round_dp_with_strategy(0, strategy) * self.tick_size
+    }
+
+    /// Price rounded up to the market's tick (conservative for buys)
+    pub fn round_up_to_tick(&self, price: Decimal) -> Decimal {
+        self.round_to_tick(price, RoundingStrategy::AwayFromZero)
+    }
+
+    /// Price rounded down to the market's tick (conservative for sells)
+    pub fn round_down_to_tick(&self, price: Decimal) -> Decimal {
+        self.round_to_tick(price, RoundingStrategy::ToZero)
+    }
+
+    /// Taker fee per share at a given price
+    ///
+    /// Polymarket charges `rate * min(price, 1 - price)` per share, so fees are
+    /// highest around 50¢ and vanish at the extremes.
+    pub fn fee_per_share(&self, price: Decimal) -> Decimal {
+        let rate = self.fee_rate_bps / Decimal::from(10_000);
+        rate * price.min(Decimal::ONE - price)
+    }
+
+    /// All-in cost of buying one share at a given price
+    pub fn cost_per_share(&self, price: Decimal) -> Decimal {
+        let price = self.round_up_to_tick(price);
+        price + self.fee_per_share(price)
+    }
+
+    /// Net proceeds of selling one share at a given price
+    pub fn proceeds_per_share(&self, price: Decimal) -> Decimal {
+        let price = self.round_down_to_tick(price);
+        price - self.fee_per_share(price)
+    }
+
+    /// Net profit per complete set traded at the given YES and NO prices
+    pub fn edge_per_share(&self, direction: ArbDirection, yes_price: Decimal, no_price: Decimal) -> Decimal {
+        match direction {
+            ArbDirection::BuyBoth => {
+                Decimal::ONE - self.cost_per_share(yes_price) - self.cost_per_share(no_price)
+            }
+            ArbDirection::SellBoth => {
+                self.proceeds_per_share(yes_price) + self.proceeds_per_share(no_price) - Decimal::ONE
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn costs(fee_rate_bps: i64) -> CostModel {
+        CostModel {
+            fee_rate_bps: Decimal::from(fee_rate_bps),
+            tick_size: Decimal::new(1, 2),
+            fixed_cost: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn fee_is_charged_on_the_cheaper_side() {
+        let costs = costs(200);
+        assert_eq!(costs.fee_per_share(Decimal::new(30, 2)), Decimal::new(6, 3));
+        assert_eq!(costs.fee_per_share(Decimal::new(70, 2)), Decimal::new(6, 3));
+        assert_eq!(costs.fee_per_share(Decimal::new(50, 2)), Decimal::new(1, 2));
+        assert!(costs.fee_per_share(Decimal::ONE).is_zero());
+    }
+
+    #[test]
+    fn prices_round_to_the_tick_against_us() {
+        let costs = costs(0);
+        let price = Decimal::new(423, 3);
+        assert_eq!(costs.round_up_to_tick(price), Decimal::new(43, 2));
+        assert_eq!(costs.round_down_to_tick(price), Decimal::new(42, 2));
+        assert_eq!(costs.round_up_to_tick(Decimal::new(42, 2)), Decimal::new(42, 2));
+
+        assert_eq!(costs.cost_per_share(price), Decimal::new(43, 2));
+        assert_eq!(costs.proceeds_per_share(price), Decimal::new(42, 2));
+    }
+
+    #[test]
+    fn edge_nets_fees_on_both_legs() {
+        let costs = costs(100);
+        let (yes, no) = (Decimal::new(40, 2), Decimal::new(55, 2));
+
+        // 1 - (0.40 + 0.004) - (0.55 + 0.0045)
+        assert_eq!(costs.edge_per_share(ArbDirection::BuyBoth, yes, no), Decimal::new(415, 4));
+        // (0.40 - 0.004) + (0.55 - 0.0045) - 1
+        assert_eq!(costs.edge_per_share(ArbDirection::SellBoth, yes, no), Decimal::new(-585, 4));
+    }
+}
@@ -0,0 +1,582 @@
+//! Persistent SQLite journal of opportunities, orders and realized P&L
+//! Every detection, order, recovery, merge and redemption is recorded for audit and restarts
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, Params};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+use crate::api::{
+    ArbitrageOpportunity, BasketOpportunity, CtfReceipt, OrderRequest, OrderResponse,
+    RecoveryAction, RecoveryReport, Side,
+};
+use crate::redeemer::{HeldLeg, HeldPosition, Redemption};
+
+/// Decimals are stored as TEXT so amounts round-trip exactly
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS opportunities (
+    id TEXT PRIMARY KEY,
+    detected_at TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    market_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    combined_price TEXT NOT NULL,
+    profit_per_share TEXT NOT NULL,
+    max_size TEXT NOT NULL,
+    total_expected_profit TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    submitted_at TEXT NOT NULL,
+    token_id TEXT NOT NULL,
+    label TEXT NOT NULL,
+    side TEXT NOT NULL,
+    price TEXT NOT NULL,
+    size TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    order_id TEXT,
+    error_msg TEXT,
+    making_amount TEXT,
+    taking_amount TEXT,
+    tx_hashes TEXT,
+    condition_id TEXT
+);
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    executed_at TEXT NOT NULL,
+    size TEXT NOT NULL,
+    status TEXT NOT NULL,
+    expected_profit TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS recoveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    action TEXT NOT NULL,
+    price TEXT,
+    exposed_token_id TEXT NOT NULL,
+    size TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    realized_pnl TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS merges (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    condition_id TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    gas_used INTEGER NOT NULL,
+    realized_pnl TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS redemptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    position_id TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    description TEXT NOT NULL,
+    opened_at TEXT NOT NULL,
+    payout TEXT NOT NULL,
+    cost TEXT NOT NULL,
+    tx_hashes TEXT NOT NULL,
+    realized_pnl TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS orders_opportunity ON orders (opportunity_id);
+CREATE INDEX IF NOT EXISTS trades_opportunity ON trades (opportunity_id);
+";
+
+/// How an executed trade's legs came back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeStatus {
+    /// Every leg filled
+    Filled,
+    /// Some legs filled, leaving exposure to recover
+    Partial,
+    /// No leg filled
+    Failed,
+}
+
+impl TradeStatus {
+    /// Classify a trade from the success of each leg
+    pub fn from_legs<'a>(legs: impl IntoIterator<Item = &'a OrderResponse>) -> Self {
+        let (mut filled, mut total) = (0, 0);
+        for leg in legs {
+            total += 1;
+            if leg.success {
+                filled += 1;
+            }
+        }
+        match filled {
+            0 => TradeStatus::Failed,
+            n if n == total => TradeStatus::Filled,
+            _ => TradeStatus::Partial,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            TradeStatus::Filled => "filled",
+            TradeStatus::Partial => "partial",
+            TradeStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Lifetime totals read back from the journal
+#[derive(Debug, Clone, Default)]
+pub struct JournalSummary {
+    pub opportunities: u64,
+    pub trades: u64,
+    pub filled: u64,
+    /// Expected profit locked by fully filled trades
+    pub expected_profit: Decimal,
+    /// P&L realized by unwinds, merges and redemptions
+    pub realized_pnl: Decimal,
+}
+
+impl std::fmt::Display for JournalSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} opportunities | {} trades ({} filled) | ${} expected profit | ${} realized",
+            self.opportunities, self.trades, self.filled, self.expected_profit, self.realized_pnl
+        )
+    }
+}
+
+/// Append-only trade journal backed by a local SQLite file
+///
+/// Writes are small and synchronous; a failed write is logged rather than
+/// interrupting trading.
+pub struct Journal {
+    conn: Mutex<Connection>,
+}
+
+impl Journal {
+    /// Open (or create) the journal at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .context("Failed to enable WAL mode")?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create journal tables")?;
+        // Journals written before orders recorded their market lack the column
+        if conn.prepare("SELECT condition_id FROM orders LIMIT 0").is_err() {
+            conn.execute("ALTER TABLE orders ADD COLUMN condition_id TEXT", [])
+                .context("Failed to add condition_id to journal orders")?;
+        }
+
+        info!("Journal opened at {}", path.display());
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Record a detected pair opportunity
+    pub fn record_opportunity(&self, opp: &ArbitrageOpportunity) {
+        self.write(
+            "opportunity",
+            "INSERT OR IGNORE INTO opportunities
+                (id, detected_at, kind, market_id, description, direction,
+                 combined_price, profit_per_share, max_size, total_expected_profit)
+             VALUES (?1, ?2, 'pair', ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                opp.id,
+                timestamp(opp.timestamp),
+                opp.market_id,
+                format!("{} / {}", opp.yes_outcome, opp.no_outcome),
+                opp.direction.to_string(),
+                opp.combined_price.to_string(),
+                opp.profit_per_share.to_string(),
+                opp.max_size.to_string(),
+                opp.total_expected_profit.to_string(),
+            ],
+        );
+    }
+
+    /// Record a detected multi-outcome basket opportunity
+    pub fn record_basket_opportunity(&self, opp: &BasketOpportunity) {
+        self.write(
+            "basket opportunity",
+            "INSERT OR IGNORE INTO opportunities
+                (id, detected_at, kind, market_id, description, direction,
+                 combined_price, profit_per_share, max_size, total_expected_profit)
+             VALUES (?1, ?2, 'basket', ?3, ?4, 'BUY ALL', ?5, ?6, ?7, ?8)",
+            params![
+                opp.id,
+                timestamp(opp.timestamp),
+                opp.group_id,
+                opp.description,
+                opp.combined_price.to_string(),
+                opp.profit_per_share.to_string(),
+                opp.max_size.to_string(),
+                opp.total_expected_profit.to_string(),
+            ],
+        );
+    }
+
+    /// Record an order on market `condition_id` as submitted together with the exchange's response
+    pub fn record_order(
+        &self,
+        opportunity_id: &str,
+        condition_id: &str,
+        request: &OrderRequest,
+        response: &OrderResponse,
+    ) {
+        self.write(
+            "order",
+            "INSERT INTO orders
+                (opportunity_id, submitted_at, token_id, label, side, price, size,
+                 success, order_id, error_msg, making_amount, taking_amount, tx_hashes, condition_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                opportunity_id,
+                timestamp(Utc::now()),
+                request.token_id,
+                request.label,
+                request.side.to_string(),
+                request.price.to_string(),
+                request.size.to_string(),
+                response.success,
+                response.order_id,
+                response.error_msg,
+                response.making_amount.map(|a| a.to_string()),
+                response.taking_amount.map(|a| a.to_string()),
+                response.transaction_hashes.as_ref().map(|h| h.join(",")),
+                condition_id,
+            ],
+        );
+    }
+
+    /// Record the outcome of executing an opportunity at `size`
+    pub fn record_trade(&self, opportunity_id: &str, size: Decimal, status: TradeStatus, expected_profit: Decimal) {
+        self.write(
+            "trade",
+            "INSERT INTO trades (opportunity_id, executed_at, size, status, expected_profit)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                opportunity_id,
+                timestamp(Utc::now()),
+                size.to_string(),
+                status.as_str(),
+                expected_profit.to_string(),
+            ],
+        );
+    }
+
+    /// Record how a one-legged fill was recovered
+    pub fn record_recovery(&self, opportunity_id: &str, report: &RecoveryReport) {
+        let (action, price) = match report.action {
+            RecoveryAction::Hedged { price } => ("hedged", Some(price)),
+            RecoveryAction::Unwound { price } => ("unwound", Some(price)),
+            RecoveryAction::Unhedged => ("unhedged", None),
+        };
+        self.write(
+            "recovery",
+            "INSERT INTO recoveries
+                (opportunity_id, recorded_at, action, price, exposed_token_id, size, attempts, realized_pnl)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                opportunity_id,
+                timestamp(Utc::now()),
+                action,
+                price.map(|p| p.to_string()),
+                report.exposed_token_id,
+                report.size.to_string(),
+                report.attempts,
+                report.realized_pnl.to_string(),
+            ],
+        );
+    }
+
+    /// Record sets merged back into USDC and the P&L the merge locked in
+    pub fn record_merge(&self, opportunity_id: &str, receipt: &CtfReceipt, realized_pnl: Decimal) {
+        self.write(
+            "merge",
+            "INSERT INTO merges
+                (opportunity_id, recorded_at, condition_id, amount, tx_hash, gas_used, realized_pnl)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                opportunity_id,
+                timestamp(Utc::now()),
+                receipt.condition_id,
+                receipt.amount.to_string(),
+                receipt.tx_hash,
+                receipt.gas_used,
+                realized_pnl.to_string(),
+            ],
+        );
+    }
+
+    /// Record a redeemed position
+    pub fn record_redemption(&self, redemption: &Redemption) {
+        self.write(
+            "redemption",
+            "INSERT INTO redemptions
+                (position_id, recorded_at, description, opened_at, payout, cost, tx_hashes, realized_pnl)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                redemption.id,
+                timestamp(Utc::now()),
+                redemption.description,
+                timestamp(redemption.opened_at),
+                redemption.payout.to_string(),
+                redemption.cost.to_string(),
+                redemption.tx_hashes.join(","),
+                redemption.realized_pnl().to_string(),
+            ],
+        );
+    }
+
+    /// P&L realized since `since` (unwinds, merges and redemptions)
+    ///
+    /// Hedged recoveries are excluded; their sets realize on merge or redemption.
+    pub fn realized_pnl_since(&self, since: DateTime<Utc>) -> Result<Decimal> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT realized_pnl FROM recoveries WHERE action = 'unwound' AND recorded_at >= ?1
+             UNION ALL SELECT realized_pnl FROM merges WHERE recorded_at >= ?1
+             UNION ALL SELECT realized_pnl FROM redemptions WHERE recorded_at >= ?1",
+        )?;
+        let amounts = stmt
+            .query_map(params![timestamp(since)], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        sum_decimals(&amounts)
+    }
+
+    /// Lifetime totals across every run that used this journal
+    pub fn summary(&self) -> Result<JournalSummary> {
+        let realized_pnl = self.realized_pnl_since(DateTime::<Utc>::UNIX_EPOCH)?;
+
+        let conn = self.conn.lock();
+        let opportunities: u64 = conn.query_row("SELECT COUNT(*) FROM opportunities", [], |row| row.get(0))?;
+        let trades: u64 = conn.query_row("SELECT COUNT(*) FROM trades", [], |row| row.get(0))?;
+
+        let mut stmt = conn.prepare("SELECT expected_profit FROM trades WHERE status = 'filled'")?;
+        let profits = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(JournalSummary {
+            opportunities,
+            trades,
+            filled: profits.len() as u64,
+            expected_profit: sum_decimals(&profits)?,
+            realized_pnl,
+        })
+    }
+
+    /// Tokens still held from journaled trades, to be tracked for redemption
+    ///
+    /// Replays every filled order (recovery orders included) and merge in the
+    /// order they happened, netting shares per token at average cost under
+    /// the position each opportunity belongs to: its market for pairs, its
+    /// group for baskets. Positions already redeemed are left out.
+    pub fn held_positions(&self) -> Result<Vec<HeldPosition>> {
+        let conn = self.conn.lock();
+
+        // Older orders have no market; a pair's is its opportunity's
+        let mut stmt = conn.prepare(
+            "SELECT o.submitted_at, p.market_id, p.description, p.detected_at,
+                    COALESCE(o.condition_id, CASE WHEN p.kind = 'pair' THEN p.market_id END),
+                    o.token_id, o.label, o.side, o.price, o.size, o.making_amount, o.taking_amount
+             FROM orders o JOIN opportunities p ON p.id = o.opportunity_id
+             WHERE o.success = 1 AND p.market_id NOT IN (SELECT position_id FROM redemptions)
+             ORDER BY o.id",
+        )?;
+        let orders = stmt
+            .query_map([], |row| {
+                Ok(JournaledFill {
+                    at: row.get(0)?,
+                    position_id: row.get(1)?,
+                    description: row.get(2)?,
+                    opened_at: row.get(3)?,
+                    condition_id: row.get(4)?,
+                    order: Some(JournaledOrder {
+                        token_id: row.get(5)?,
+                        label: row.get(6)?,
+                        side: row.get(7)?,
+                        price: row.get(8)?,
+                        size: row.get(9)?,
+                        making_amount: row.get(10)?,
+                        taking_amount: row.get(11)?,
+                    }),
+                    merged: None,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT m.recorded_at, p.market_id, p.description, p.detected_at, m.condition_id, m.amount
+             FROM merges m JOIN opportunities p ON p.id = m.opportunity_id
+             WHERE p.market_id NOT IN (SELECT position_id FROM redemptions)
+             ORDER BY m.id",
+        )?;
+        let merges = stmt
+            .query_map([], |row| {
+                Ok(JournaledFill {
+                    at: row.get(0)?,
+                    position_id: row.get(1)?,
+                    description: row.get(2)?,
+                    opened_at: row.get(3)?,
+                    condition_id: row.get(4)?,
+                    order: None,
+                    merged: Some(row.get(5)?),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut fills: Vec<JournaledFill> = orders.into_iter().chain(merges).collect();
+        // Stable, so an order and a merge journaled in the same millisecond keep that order
+        fills.sort_by(|a, b| a.at.cmp(&b.at));
+
+        // Shares and cost per token of each position, in first-traded order
+        let mut positions: BTreeMap<String, (HeldPosition, Vec<Decimal>)> = BTreeMap::new();
+        for fill in fills {
+            let Some(condition_id) = fill.condition_id else {
+                warn!("Journaled order for {} has no market, skipping it", fill.position_id);
+                continue;
+            };
+            let opened_at = DateTime::parse_from_rfc3339(&fill.opened_at)
+                .with_context(|| format!("Invalid timestamp {} in journal", fill.opened_at))?
+                .with_timezone(&Utc);
+            let (position, costs) = positions.entry(fill.position_id.clone()).or_insert_with(|| {
+                (
+                    HeldPosition {
+                        id: fill.position_id.clone(),
+                        description: fill.description.clone(),
+                        legs: Vec::new(),
+                        cost: Decimal::ZERO,
+                        opened_at,
+                    },
+                    Vec::new(),
+                )
+            });
+            position.opened_at = position.opened_at.min(opened_at);
+
+            if let Some(order) = fill.order {
+                let side = match order.side.as_str() {
+                    "BUY" => Side::Buy,
+                    "SELL" => Side::Sell,
+                    other => anyhow::bail!("Invalid side {} in journal", other),
+                };
+                let (making, taking) = (parse_optional(&order.making_amount)?, parse_optional(&order.taking_amount)?);
+                let (price, size) = (parse_decimal(&order.price)?, parse_decimal(&order.size)?);
+                let (shares, usdc) = match side {
+                    Side::Buy => (taking.unwrap_or(size), making.unwrap_or(size * price)),
+                    Side::Sell => (making.unwrap_or(size), taking.unwrap_or(size * price)),
+                };
+
+                let k = match position.legs.iter().position(|l| l.token_id == order.token_id) {
+                    Some(k) => k,
+                    None => {
+                        position.legs.push(HeldLeg {
+                            condition_id,
+                            token_id: order.token_id,
+                            label: order.label,
+                            shares: Decimal::ZERO,
+                        });
+                        costs.push(Decimal::ZERO);
+                        costs.len() - 1
+                    }
+                };
+                match side {
+                    Side::Buy => {
+                        position.legs[k].shares += shares;
+                        costs[k] += usdc;
+                    }
+                    Side::Sell => release(&mut position.legs[k], &mut costs[k], shares),
+                }
+            } else if let Some(merged) = fill.merged {
+                // A merge burns the same number of every outcome of the market
+                let merged = parse_decimal(&merged)?;
+                for (leg, cost) in position.legs.iter_mut().zip(costs.iter_mut()) {
+                    if leg.condition_id == condition_id {
+                        release(leg, cost, merged);
+                    }
+                }
+            }
+        }
+
+        Ok(positions
+            .into_values()
+            .filter_map(|(mut position, costs)| {
+                position.cost = position
+                    .legs
+                    .iter()
+                    .zip(&costs)
+                    .filter(|(leg, _)| leg.shares > Decimal::ZERO)
+                    .map(|(_, cost)| *cost)
+                    .sum();
+                position.legs.retain(|leg| leg.shares > Decimal::ZERO);
+                (!position.legs.is_empty()).then_some(position)
+            })
+            .collect())
+    }
+
+    /// Run an insert, logging instead of failing so trading carries on
+    fn write(&self, what: &str, sql: &str, params: impl Params) {
+        if let Err(e) = self.conn.lock().execute(sql, params) {
+            warn!("Failed to journal {}: {}", what, e);
+        }
+    }
+}
+
+/// Fixed-width UTC timestamps so text comparison orders them correctly
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// An order or merge read back from the journal, before replay
+struct JournaledFill {
+    at: String,
+    /// Market id for pairs, group id for baskets
+    position_id: String,
+    description: String,
+    opened_at: String,
+    condition_id: Option<String>,
+    order: Option<JournaledOrder>,
+    /// Sets merged, for merges
+    merged: Option<String>,
+}
+
+struct JournaledOrder {
+    token_id: String,
+    label: String,
+    side: String,
+    price: String,
+    size: String,
+    making_amount: Option<String>,
+    taking_amount: Option<String>,
+}
+
+/// Take `shares` out of a held leg at its average cost
+fn release(leg: &mut HeldLeg, cost: &mut Decimal, shares: Decimal) {
+    let released = shares.min(leg.shares);
+    if !leg.shares.is_zero() {
+        *cost -= *cost * released / leg.shares;
+    }
+    leg.shares -= released;
+}
+
+fn parse_decimal(value: &str) -> Result<Decimal> {
+    Decimal::from_str(value).with_context(|| format!("Invalid amount {} in journal", value))
+}
+
+fn parse_optional(value: &Option<String>) -> Result<Option<Decimal>> {
+    value.as_deref().map(parse_decimal).transpose()
+}
+
+fn sum_decimals(values: &[String]) -> Result<Decimal> {
+    values
+        .iter()
+        .map(|v| parse_decimal(v))
+        .sum()
+}
@@ -0,0 +1,24 @@
+//! Polymarket arbitrage library
+//!
+//! Market scanning, execution and bookkeeping shared by the trading bot
+//! and the backtester
+
+pub mod api;
+pub mod backtest;
+pub mod config;
+pub mod discovery;
+pub mod fees;
+pub mod journal;
+pub mod market_filter;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+pub mod orderbook;
+pub mod paper;
+pub mod positions;
+pub mod recording;
+pub mod redeemer;
+pub mod risk;
+pub mod scanner;
+pub mod shutdown;
+pub mod simulation;
+pub mod websocket;
@@ -3,23 +3,32 @@
 //! Detects and executes arbitrage opportunities when YES + NO < $1
 //! Targets 15-minute crypto markets for fast resolution
 
-mod api;
-mod config;
-mod scanner;
-mod websocket;
-
 use anyhow::Result;
+use chrono::Utc;
 use rust_decimal::Decimal;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn, Level};
 use tracing_subscriber::EnvFilter;
 
-use api::{ClobClient, ArbitrageOpportunity};
-use config::Config;
-use scanner::ArbitrageScanner;
-use websocket::{WsClient, PriceUpdate};
+use polymarket_arb::api::{
+    ClobClient, Executor, MockExchange, ArbDirection, ArbExecution, ArbitrageOpportunity, BasketExecution, BasketOpportunity,
+    MarketListing, OrderPolicy, RecoveryAction, RecoveryPolicy, RecoveryReport,
+};
+use polymarket_arb::config::Config;
+use polymarket_arb::journal::{Journal, TradeStatus};
+use polymarket_arb::orderbook::OrderBookStore;
+use polymarket_arb::paper::{PaperTrade, PaperTrader};
+use polymarket_arb::positions::PositionLedger;
+use polymarket_arb::recording::{RecordedData, Recorder, Replay};
+use polymarket_arb::redeemer::{HeldLeg, HeldPosition, Redeemer};
+use polymarket_arb::risk::{RiskManager, TradeCheck};
+use polymarket_arb::scanner::ArbitrageScanner;
+use polymarket_arb::shutdown::KillSwitch;
+use polymarket_arb::websocket::WsClient;
 
 /// Stats tracking for the bot
 #[derive(Debug, Default)]
@@ -29,6 +38,48 @@ struct BotStats {
     trades_successful: u64,
     total_profit: Decimal,
     scans_completed: u64,
+    partial_fills: u64,
+    recoveries_hedged: u64,
+    recoveries_unwound: u64,
+    sets_merged: Decimal,
+    paper_trades: u64,
+    paper_pnl: Decimal,
+}
+
+impl std::fmt::Display for BotStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} scans | {} opportunities | {} trades ({} successful) | {} partial ({} hedged, {} unwound) | {} sets merged | ${} profit",
+            self.scans_completed,
+            self.opportunities_found,
+            self.trades_executed,
+            self.trades_successful,
+            self.partial_fills,
+            self.recoveries_hedged,
+            self.recoveries_unwound,
+            self.sets_merged,
+            self.total_profit
+        )?;
+        if self.paper_trades > 0 {
+            write!(f, " | {} paper trades, ${} paper P&L", self.paper_trades, self.paper_pnl)?;
+        }
+        Ok(())
+    }
+}
+
+/// Services every trade goes through, shared by the run modes
+#[derive(Clone)]
+struct TradeServices {
+    redeemer: Arc<Redeemer>,
+    risk: Arc<RiskManager>,
+    journal: Arc<Journal>,
+    /// Simulated account used instead of the exchange in paper trading
+    paper: Option<Arc<PaperTrader>>,
+    /// Shared stats, locked only while counters are updated so slow trades don't block readers
+    stats: Arc<RwLock<BotStats>>,
+    /// Merges still in flight on chain, awaited before shutting down
+    merges: Arc<parking_lot::Mutex<JoinSet<()>>>,
 }
 
 #[tokio::main]
@@ -51,24 +102,144 @@ async fn main() -> Result<()> {
     info!("Configuration:");
     info!("  Max combined price: {}", config.max_combined_price);
     info!("  Min profit threshold: {}", config.min_profit_threshold);
+    info!("  Sell side: {} (min combined bid {})", config.enable_sell_side, config.min_combined_sell_price);
+    info!("  Multi-outcome: {}", config.enable_multi_outcome);
     info!("  Order size: ${}", config.order_size);
+    info!("  Fixed cost per trade: ${}", config.fixed_cost_per_trade);
+    info!(
+        "  Risk: ${} capital, ${} per market, ${} unhedged, {} trades/min, ${} daily loss",
+        config.max_capital_deployed,
+        config.max_market_exposure,
+        config.max_unhedged_exposure,
+        config.max_trades_per_minute,
+        config.max_daily_loss
+    );
+    info!("  Orders: {} limit, slippage {}", OrderPolicy::ORDER_TYPE, config.order_slippage);
+    info!("  Merge sets: {} (RPC {})", config.enable_merge, config.rpc_url);
+    info!("  Redeem resolved: {} (every {}s)", config.enable_redeem, config.redeem_interval_secs);
+    info!("  CLOB: {} | WebSocket: {}", config.clob_host, config.ws_url);
+    info!("  Journal: {}", config.journal_path);
+    if let Some(dir) = &config.record_dir {
+        info!("  Recording market data to: {}", dir);
+    }
+    info!(
+        "  Market listing: up to {} pages, full refresh every {}s, recheck after {}s",
+        config.max_market_pages, config.market_full_refresh_secs, config.market_recheck_secs
+    );
     info!("  Poll interval: {}ms", config.poll_interval_ms);
+    info!("  Expiry cutoff: {}s before end", config.expiry_cutoff_secs);
     info!("  Crypto only: {}", config.crypto_only);
+    info!("  Market filter: {}", config.market_filter);
+    let series = config.updown_series();
+    if series.is_empty() {
+        info!("  Up/down discovery: off");
+    } else {
+        info!(
+            "  Up/down discovery: {} (every {}s, {}s ahead)",
+            series.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "),
+            config.discovery_interval_secs,
+            config.discovery_lookahead_secs
+        );
+    }
     info!("  Dry run: {}", config.dry_run);
+    if config.paper_trading {
+        info!(
+            "  Paper trading: ${} balance, {}ms latency, {} price move, {} of depth",
+            config.paper_balance,
+            config.paper_latency_ms,
+            config.paper_price_move,
+            config.paper_depth_fraction
+        );
+    }
 
-    if config.dry_run {
+    if config.paper_trading && !config.dry_run {
+        anyhow::bail!("PAPER_TRADING only simulates trades; set DRY_RUN=true to use it");
+    }
+
+    if config.paper_trading {
+        warn!("*** PAPER TRADING MODE - Fills are simulated against live order books ***");
+    } else if config.dry_run {
         warn!("*** DRY RUN MODE - No trades will be executed ***");
     }
 
+    // Simulated account standing in for the exchange in dry runs
+    let paper = config
+        .paper_trading
+        .then(|| Arc::new(PaperTrader::new(config.paper_settings())));
+
+    // Every opportunity, order and realized P&L is kept across restarts
+    let journal = Arc::new(Journal::open(&config.journal_path)?);
+    match journal.summary() {
+        Ok(summary) => info!("Journal history: {}", summary),
+        Err(e) => warn!("Could not read journal history: {:#}", e),
+    }
+
+    // Replays only exercise parsing and detection, so an empty mock stands in for the exchange
+    if let Some(path) = &config.replay_file {
+        let client = Executor::new(Arc::new(MockExchange::new(Decimal::ZERO)));
+        let scanner = ArbitrageScanner::new(client, config.clone());
+        return run_replay_mode(&scanner, path, config.replay_speed).await;
+    }
+
     // Create API client with authentication (using official Polymarket SDK)
-    let client = ClobClient::new(&config.private_key).await?;
+    let exchange = ClobClient::new(&config.private_key, &config.clob_host, &config.data_api_host, &config.gamma_host).await?;
+    let ctf = if config.enable_merge || config.enable_redeem {
+        Some(exchange.ctf_client(config.ctf_config()?)?)
+    } else {
+        None
+    };
+    let client = Executor::new(Arc::new(exchange))
+        .with_journal(journal.clone())
+        .with_market_listing(MarketListing {
+            max_pages: config.max_market_pages,
+            full_refresh: Duration::from_secs(config.market_full_refresh_secs),
+        })
+        .with_recovery_policy(RecoveryPolicy {
+            max_slippage: config.recovery_max_slippage,
+            max_retries: config.recovery_max_retries,
+            retry_delay: Duration::from_millis(config.recovery_retry_delay_ms),
+        })
+        .with_order_policy(OrderPolicy {
+            slippage: config.order_slippage,
+        });
+    let client = match ctf {
+        Some(ctf) => client.with_ctf(ctf),
+        None => client,
+    };
     info!("Wallet address: {}", client.address());
 
+    let client = match &config.record_dir {
+        Some(dir) => client.with_recorder(Arc::new(Recorder::create(dir)?)),
+        None => client,
+    };
+
+    // Orders without an allowance fail with opaque errors, so check up front
+    match client.refresh_funding().await {
+        Ok(funding) => {
+            info!(
+                "USDC balance: ${} (allowance ${}, neg-risk ${})",
+                funding.balance, funding.allowance, funding.neg_risk_allowance
+            );
+            if funding.allowance_missing() {
+                error!("USDC allowance is not set for the exchange contracts.");
+                error!("Approve USDC for the CTF Exchange and neg-risk exchange before trading.");
+                if !config.dry_run {
+                    anyhow::bail!("Missing USDC allowance for {}", client.address());
+                }
+            }
+        }
+        Err(e) => warn!("Could not check USDC balance and allowance: {:#}", e),
+    }
+
     // Create scanner
     let scanner = Arc::new(ArbitrageScanner::new(client, config.clone()));
 
-    // Initial market refresh
-    let market_count = scanner.refresh_markets().await?;
+    // Initial market refresh, plus the up/down windows open now or soon
+    scanner.refresh_markets().await?;
+    if let Err(e) = scanner.discover_markets().await {
+        warn!("Up/down market discovery failed: {:#}", e);
+    }
+    let market_count = scanner.detector().pairs().len();
     info!("Loaded {} markets to monitor", market_count);
 
     if market_count == 0 {
@@ -79,11 +250,194 @@ async fn main() -> Result<()> {
     // Stats tracking
     let stats = Arc::new(RwLock::new(BotStats::default()));
 
+    // Start from the account's real holdings, then keep the ledger honest
+    if let Err(e) = scanner.client().reconcile_positions().await {
+        warn!("Initial position reconciliation failed: {:#}", e);
+    }
+    log_exposures(scanner.client().positions());
+    {
+        let scanner = scanner.clone();
+        let interval = Duration::from_secs(config.reconcile_interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // Skip the immediate tick; we just reconciled
+            loop {
+                ticker.tick().await;
+                match scanner.client().reconcile_positions().await {
+                    Ok(_) => log_exposures(scanner.client().positions()),
+                    Err(e) => warn!("Position reconciliation failed: {:#}", e),
+                }
+            }
+        });
+    }
+
+    // Positions held to resolution, redeemed in the background
+    let redeemer = Arc::new(Redeemer::new());
+
+    // Risk limits consulted before every execution
+    let risk = Arc::new(RiskManager::new(config.risk_limits()));
+
+    // Carry today's realized P&L over so a restart doesn't reset the daily loss limit
+    let start_of_day = Utc::now().date_naive().and_time(chrono::NaiveTime::MIN).and_utc();
+    match journal.realized_pnl_since(start_of_day) {
+        Ok(pnl) if !pnl.is_zero() => {
+            info!("Realized P&L earlier today: ${}", pnl);
+            risk.record_pnl(pnl);
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Could not restore today's P&L from the journal: {:#}", e),
+    }
+
+    if config.enable_redeem {
+        // Positions bought before a restart are still waiting to resolve
+        match journal.held_positions() {
+            Ok(positions) => {
+                if !positions.is_empty() {
+                    info!("Tracking {} positions from the journal for redemption", positions.len());
+                }
+                for position in positions {
+                    redeemer.track(position);
+                }
+            }
+            Err(e) => warn!("Could not restore held positions from the journal: {:#}", e),
+        }
+
+        let redeemer = redeemer.clone();
+        let scanner = scanner.clone();
+        let risk = risk.clone();
+        let journal = journal.clone();
+        let interval = Duration::from_secs(config.redeem_interval_secs);
+        tokio::spawn(async move {
+            redeemer
+                .run(scanner.client(), interval, |r| {
+                    risk.record_pnl(r.realized_pnl());
+                    journal.record_redemption(r);
+                })
+                .await;
+        });
+    }
+
+    // A new up/down window opens every few minutes; pick each one up before it
+    // starts and drop it once it's too close to the end
+    let markets_changed = Arc::new(Notify::new());
+    if scanner.discovery().is_enabled() {
+        let scanner = scanner.clone();
+        let markets_changed = markets_changed.clone();
+        let interval = Duration::from_secs(config.discovery_interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // Skip the immediate tick; we just discovered
+            loop {
+                ticker.tick().await;
+                let watched = watched_tokens(&scanner);
+                scanner.detector().evict_expired();
+                if let Err(e) = scanner.discover_markets().await {
+                    warn!("Up/down market discovery failed: {:#}", e);
+                }
+                if watched_tokens(&scanner) != watched {
+                    markets_changed.notify_one();
+                }
+            }
+        });
+    }
+
+    // Stop trading on Ctrl+C, SIGTERM or the kill file
+    let kill = Arc::new(KillSwitch::new());
+    kill.listen_for_signals();
+    kill.watch_file(&config.kill_switch_file);
+    info!("Kill switch: Ctrl+C, SIGTERM or `touch {}`", config.kill_switch_file);
+
     // Decide on strategy: WebSocket or Polling
-    if config.use_websocket {
-        run_websocket_mode(scanner, stats, config).await
+    let dry_run = config.dry_run;
+    let merges = Arc::new(parking_lot::Mutex::new(JoinSet::new()));
+    let services = TradeServices {
+        redeemer,
+        risk,
+        journal,
+        paper: paper.clone(),
+        stats: stats.clone(),
+        merges: merges.clone(),
+    };
+    let result = if config.use_websocket {
+        run_websocket_mode(scanner.clone(), stats.clone(), services, kill.clone(), markets_changed, config).await
     } else {
-        run_polling_mode(scanner, stats, config).await
+        run_polling_mode(scanner.clone(), stats.clone(), services, kill.clone(), config).await
+    };
+
+    shutdown(&scanner, &stats, &merges, paper.as_deref(), &kill, dry_run).await;
+    result
+}
+
+/// Clean up after the trading loop stops: cancel resting orders and flush stats
+///
+/// Runs after the loop has finished its current execution, so any
+/// in-flight recovery has already completed; merges still on chain are
+/// waited for.
+async fn shutdown(
+    scanner: &ArbitrageScanner,
+    stats: &RwLock<BotStats>,
+    merges: &parking_lot::Mutex<JoinSet<()>>,
+    paper: Option<&PaperTrader>,
+    kill: &KillSwitch,
+    dry_run: bool,
+) {
+    info!("===========================================");
+    info!(
+        "  SHUTTING DOWN ({})",
+        kill.reason().unwrap_or_else(|| "trading loop exited".to_string())
+    );
+    info!("===========================================");
+
+    // Dry runs never place orders, so leave any manual ones alone
+    if !dry_run {
+        match scanner.client().cancel_all_orders().await {
+            Ok(cancelled) => info!("Cancelled {} resting orders", cancelled),
+            Err(e) => error!("Failed to cancel resting orders: {:#}", e),
+        }
+    }
+
+    let mut merges = std::mem::take(&mut *merges.lock());
+    if !merges.is_empty() {
+        info!("Waiting for {} merges to confirm", merges.len());
+        while merges.join_next().await.is_some() {}
+    }
+
+    info!("Final stats: {}", stats.read().await);
+    log_exposures(scanner.client().positions());
+
+    if let Some(paper) = paper {
+        info!("Paper account: {}", paper.summary());
+        log_exposures(paper.positions());
+    }
+}
+
+/// Log matched and unhedged holdings per market or group
+fn log_exposures(ledger: &PositionLedger) {
+    let exposures = ledger.exposures();
+    if exposures.is_empty() {
+        info!("Positions: none held");
+        return;
+    }
+
+    info!("Positions: {} markets", exposures.len());
+    for exposure in &exposures {
+        info!(
+            "  {}: {} matched sets, cost ${}",
+            exposure.id, exposure.matched, exposure.cost
+        );
+        if exposure.is_unhedged() {
+            for leg in &exposure.unmatched {
+                warn!(
+                    "    UNHEDGED {} {} @ avg ${}",
+                    leg.shares, leg.label, leg.avg_price()
+                );
+            }
+        }
+    }
+
+    let unhedged = ledger.unhedged().len();
+    if unhedged > 0 {
+        warn!("{} markets hold unhedged shares", unhedged);
     }
 }
 
@@ -91,6 +445,8 @@ async fn main() -> Result<()> {
 async fn run_polling_mode(
     scanner: Arc<ArbitrageScanner>,
     stats: Arc<RwLock<BotStats>>,
+    services: TradeServices,
+    kill: Arc<KillSwitch>,
     config: Config,
 ) -> Result<()> {
     info!("Starting in POLLING mode ({}ms interval)", config.poll_interval_ms);
@@ -98,7 +454,7 @@ async fn run_polling_mode(
     let poll_interval = Duration::from_millis(config.poll_interval_ms);
     let mut refresh_counter = 0u64;
 
-    loop {
+    while !kill.is_triggered() {
         let scan_start = std::time::Instant::now();
 
         // Refresh markets every 100 scans (roughly every 3-5 minutes)
@@ -112,12 +468,14 @@ async fn run_polling_mode(
         // Scan for opportunities
         match scanner.scan_opportunities().await {
             Ok(opportunities) => {
-                let mut stats_guard = stats.write().await;
-                stats_guard.scans_completed += 1;
+                stats.write().await.scans_completed += 1;
 
                 for opp in opportunities {
-                    stats_guard.opportunities_found += 1;
-                    handle_opportunity(&opp, scanner.clone(), &mut stats_guard, &config).await;
+                    if kill.is_triggered() {
+                        break;
+                    }
+                    stats.write().await.opportunities_found += 1;
+                    handle_opportunity(&opp, scanner.clone(), &services, &config).await;
                 }
             }
             Err(e) => {
@@ -125,40 +483,59 @@ async fn run_polling_mode(
             }
         }
 
+        if config.enable_multi_outcome && !kill.is_triggered() {
+            match scanner.scan_baskets().await {
+                Ok(baskets) => {
+                    for opp in baskets {
+                        if kill.is_triggered() {
+                            break;
+                        }
+                        stats.write().await.opportunities_found += 1;
+                        handle_basket_opportunity(&opp, scanner.clone(), &services, &config).await;
+                    }
+                }
+                Err(e) => {
+                    warn!("Basket scan failed: {}", e);
+                }
+            }
+        }
+
         let scan_duration = scan_start.elapsed();
         debug!("Scan completed in {:?}", scan_duration);
 
         // Print stats every 50 scans
         if refresh_counter % 50 == 0 {
-            let stats_guard = stats.read().await;
-            info!(
-                "Stats: {} scans | {} opportunities | {} trades ({} successful) | ${} profit",
-                stats_guard.scans_completed,
-                stats_guard.opportunities_found,
-                stats_guard.trades_executed,
-                stats_guard.trades_successful,
-                stats_guard.total_profit
-            );
+            info!("Stats: {}", stats.read().await);
         }
 
-        // Sleep for remaining interval
+        // Sleep for remaining interval, waking early to stop
         if scan_duration < poll_interval {
-            tokio::time::sleep(poll_interval - scan_duration).await;
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval - scan_duration) => {}
+                _ = kill.triggered() => {}
+            }
         }
     }
+
+    Ok(())
 }
 
 /// Run the bot in WebSocket mode (real-time updates)
+///
+/// Resubscribes whenever `markets_changed` fires, so added markets are watched
+/// and evicted ones dropped.
 async fn run_websocket_mode(
     scanner: Arc<ArbitrageScanner>,
     stats: Arc<RwLock<BotStats>>,
+    services: TradeServices,
+    kill: Arc<KillSwitch>,
+    markets_changed: Arc<Notify>,
     config: Config,
 ) -> Result<()> {
     info!("Starting in WEBSOCKET mode (real-time updates)");
 
-    // Track prices in memory for fast arb checking
-    let prices: Arc<RwLock<std::collections::HashMap<String, PriceUpdate>>> =
-        Arc::new(RwLock::new(std::collections::HashMap::new()));
+    // Local L2 books maintained from the feed for fast arb checking
+    let books = Arc::new(OrderBookStore::new());
 
     // Spawn periodic stats printer
     let stats_clone = stats.clone();
@@ -166,26 +543,24 @@ async fn run_websocket_mode(
         let mut interval = tokio::time::interval(Duration::from_secs(30));
         loop {
             interval.tick().await;
-            let s = stats_clone.read().await;
-            info!(
-                "Stats: {} opportunities | {} trades ({} successful) | ${} profit",
-                s.opportunities_found,
-                s.trades_executed,
-                s.trades_successful,
-                s.total_profit
-            );
+            info!("Stats: {}", stats_clone.read().await);
         }
     });
 
-    // Spawn market refresh task
+    // Spawn market refresh task, resubscribing when it adds or evicts markets
     let scanner_clone = scanner.clone();
+    let refreshed = markets_changed.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(300)); // Every 5 minutes
         loop {
             interval.tick().await;
+            let watched = watched_tokens(&scanner_clone);
             if let Err(e) = scanner_clone.refresh_markets().await {
                 warn!("Failed to refresh markets: {}", e);
             }
+            if watched_tokens(&scanner_clone) != watched {
+                refreshed.notify_one();
+            }
         }
     });
 
@@ -193,31 +568,64 @@ async fn run_websocket_mode(
     let mut reconnect_delay = Duration::from_secs(1);
     let max_reconnect_delay = Duration::from_secs(60);
 
-    loop {
-        let token_ids = scanner.get_watched_token_ids();
+    while !kill.is_triggered() {
+        let token_ids = scanner.detector().get_watched_token_ids();
         info!("Subscribing to {} token feeds", token_ids.len());
 
-        match WsClient::connect(token_ids).await {
+        match WsClient::connect(&config.ws_url, token_ids, books.clone(), scanner.client().recorder().cloned()).await {
             Ok(mut ws_client) => {
                 // Reset reconnect delay on successful connection
                 reconnect_delay = Duration::from_secs(1);
+                let mut resubscribe = false;
+
+                // Process WebSocket updates until the feed drops, the watched markets change or the kill switch fires
+                loop {
+                    // Polled in order, so a queued update never beats the kill switch
+                    let update = tokio::select! {
+                        biased;
+                        _ = kill.triggered() => break,
+                        _ = markets_changed.notified() => {
+                            info!("Watched markets changed, resubscribing...");
+                            resubscribe = true;
+                            break;
+                        }
+                        update = ws_client.rx.recv() => match update {
+                            Some(update) => update,
+                            None => break,
+                        },
+                    };
+
+                    debug!(
+                        "Book update {}: bid {:?} / ask {:?}",
+                        update.asset_id, update.best_bid, update.best_ask
+                    );
 
-                // Process WebSocket updates
-                while let Some(update) = ws_client.rx.recv().await {
-                    // Update price cache
-                    {
-                        let mut price_map = prices.write().await;
-                        price_map.insert(update.asset_id.clone(), update.clone());
+                    // Quick check for arbitrage using the local order books
+                    if let Some(opp) = scanner.detector().check_cached_pair(&update.asset_id, &books) {
+                        if kill.is_triggered() {
+                            break;
+                        }
+                        stats.write().await.opportunities_found += 1;
+                        handle_opportunity(&opp, scanner.clone(), &services, &config).await;
                     }
 
-                    // Quick check for arbitrage using cached prices
-                    if let Some(opp) = check_arb_from_cache(&prices, &scanner, &config).await {
-                        let mut stats_guard = stats.write().await;
-                        stats_guard.opportunities_found += 1;
-                        handle_opportunity(&opp, scanner.clone(), &mut stats_guard, &config).await;
+                    if let Some(opp) = scanner.detector().check_cached_group(&update.asset_id, &books) {
+                        if kill.is_triggered() {
+                            break;
+                        }
+                        stats.write().await.opportunities_found += 1;
+                        handle_basket_opportunity(&opp, scanner.clone(), &services, &config).await;
                     }
                 }
 
+                ws_client.shutdown().await;
+                if kill.is_triggered() {
+                    info!("Closed WebSocket connection");
+                    break;
+                }
+                if resubscribe {
+                    continue;
+                }
                 warn!("WebSocket connection closed, reconnecting in {:?}...", reconnect_delay);
             }
             Err(e) => {
@@ -226,54 +634,191 @@ async fn run_websocket_mode(
         }
 
         // Wait before reconnecting
-        tokio::time::sleep(reconnect_delay).await;
+        tokio::select! {
+            _ = tokio::time::sleep(reconnect_delay) => {}
+            _ = kill.triggered() => break,
+        }
 
         // Exponential backoff (double the delay, up to max)
         reconnect_delay = (reconnect_delay * 2).min(max_reconnect_delay);
     }
+
+    Ok(())
 }
 
-/// Check for arbitrage using cached WebSocket prices
-async fn check_arb_from_cache(
-    prices: &Arc<RwLock<std::collections::HashMap<String, PriceUpdate>>>,
-    scanner: &ArbitrageScanner,
-    config: &Config,
-) -> Option<ArbitrageOpportunity> {
-    // This is a simplified check - the full implementation would
-    // map token pairs and check combined prices from the cache
-    // For now, trigger a full scan when we see price updates
-
-    // To avoid excessive scanning, we rely on the scanner's batch approach
-    // A production system would maintain a proper price index
-    None
+/// Tokens the WebSocket feed should be subscribed to, to spot when they change
+fn watched_tokens(scanner: &ArbitrageScanner) -> HashSet<String> {
+    scanner.detector().get_watched_token_ids().into_iter().collect()
+}
+
+/// Feed a recorded session back through the same parsing and scanning code
+///
+/// REST books are scanned as a whole, WebSocket frames are applied to local
+/// books exactly as live. Opportunities are logged and counted, never executed.
+async fn run_replay_mode(scanner: &ArbitrageScanner, path: &str, speed: f64) -> Result<()> {
+    info!("Starting REPLAY of {} (speed {}x, 0 = unthrottled)", path, speed);
+
+    let detector = scanner.detector();
+    let mut replay = Replay::open(path, speed).await?;
+    let books = OrderBookStore::new();
+    let mut stats = BotStats::default();
+
+    while let Some(event) = replay.next().await? {
+        detector.set_time(event.at);
+        let at = event.at.format("%Y-%m-%d %H:%M:%S%.3f");
+        match event.data {
+            RecordedData::Markets { markets } => {
+                let count = detector.load_markets(&markets);
+                info!("[{}] Loaded {} recorded markets", at, count);
+            }
+            RecordedData::Groups { members } => detector.set_group_members(members),
+            RecordedData::Books { books: fetched } => {
+                stats.scans_completed += 1;
+                for opp in detector.scan_books(&fetched) {
+                    stats.opportunities_found += 1;
+                    log_replayed_opportunity(&at, &opp);
+                }
+                for opp in detector.scan_basket_books(&fetched) {
+                    stats.opportunities_found += 1;
+                    log_replayed_basket(&at, &opp);
+                }
+            }
+            RecordedData::Ws { frame } => {
+                let updates = WsClient::handle_message(&frame, &books).unwrap_or_else(|e| {
+                    debug!("Failed to handle recorded frame: {}", e);
+                    Vec::new()
+                });
+                for update in updates {
+                    if let Some(opp) = detector.check_cached_pair(&update.asset_id, &books) {
+                        stats.opportunities_found += 1;
+                        log_replayed_opportunity(&at, &opp);
+                    }
+                    if let Some(opp) = detector.check_cached_group(&update.asset_id, &books) {
+                        stats.opportunities_found += 1;
+                        log_replayed_basket(&at, &opp);
+                    }
+                }
+            }
+        }
+    }
+
+    info!("Replay finished: {}", stats);
+    Ok(())
+}
+
+fn log_replayed_opportunity(at: &impl std::fmt::Display, opp: &ArbitrageOpportunity) {
+    info!(
+        "[{}] {} {} / {} on {}: combined ${}, profit/share ${}, max size {}, expected ${}",
+        at,
+        opp.direction,
+        opp.yes_outcome,
+        opp.no_outcome,
+        opp.market_id,
+        opp.combined_price,
+        opp.profit_per_share,
+        opp.max_size,
+        opp.total_expected_profit
+    );
+}
+
+fn log_replayed_basket(at: &impl std::fmt::Display, opp: &BasketOpportunity) {
+    info!(
+        "[{}] BASKET {} ({} legs): combined ${}, profit/share ${}, max size {}, expected ${}",
+        at,
+        opp.description,
+        opp.legs.len(),
+        opp.combined_price,
+        opp.profit_per_share,
+        opp.max_size,
+        opp.total_expected_profit
+    );
 }
 
 /// Handle a detected arbitrage opportunity
 async fn handle_opportunity(
     opp: &ArbitrageOpportunity,
     scanner: Arc<ArbitrageScanner>,
-    stats: &mut BotStats,
+    services: &TradeServices,
     config: &Config,
 ) {
+    let TradeServices { redeemer, risk, journal, paper, stats, .. } = services;
+
     info!("===========================================");
     info!("  ARBITRAGE OPPORTUNITY DETECTED!");
     info!("===========================================");
     info!("  Market: {}", opp.market_id);
-    info!("  YES ask: ${}", opp.yes_ask_price);
-    info!("  NO ask:  ${}", opp.no_ask_price);
+    info!("  Direction: {}", opp.direction);
+    info!("  {} best: ${}", opp.yes_outcome, opp.yes_price);
+    info!("  {} best: ${}", opp.no_outcome, opp.no_price);
     info!("  Combined: ${}", opp.combined_price);
     info!("  Profit/share: ${}", opp.profit_per_share);
-    info!("  Max size: {}", opp.max_size);
-    info!("  Expected profit: ${}", opp.expected_profit(config.order_size));
+    info!(
+        "  VWAP: {} ${} + {} ${} = ${}",
+        opp.yes_outcome, opp.yes_vwap, opp.no_outcome, opp.no_vwap, opp.combined_vwap
+    );
+    info!(
+        "  Marginal: {} ${} | {} ${}",
+        opp.yes_outcome, opp.yes_marginal_price, opp.no_outcome, opp.no_marginal_price
+    );
+    info!("  Max size: {} ({} levels)", opp.max_size, opp.levels.len());
+    info!("  Total expected profit: ${}", opp.total_expected_profit);
+    info!(
+        "  Expected profit: ${} (gross ${}, fee rate {} bps)",
+        opp.expected_profit(config.order_size),
+        opp.gross_profit(config.order_size),
+        opp.costs.fee_rate_bps
+    );
     info!("===========================================");
 
+    journal.record_opportunity(opp);
+
+    // Determine order size (minimum of config size and available liquidity)
+    let size = config.order_size.min(opp.max_size);
+
     if config.dry_run {
-        info!("DRY RUN - Skipping trade execution");
+        match paper {
+            Some(paper) if size >= Decimal::ONE => {
+                let result = paper.execute_pair(scanner.client(), opp, size).await;
+                record_paper_trade(&mut *stats.write().await, paper, result);
+            }
+            _ => info!("DRY RUN - Skipping trade execution"),
+        }
         return;
     }
 
-    // Determine order size (minimum of config size and available liquidity)
-    let size = config.order_size.min(opp.max_size);
+    // Size down to what the wallet can fund (USDC for buys, held sets for sells)
+    let size = match scanner.client().fundable_size(opp, size).await {
+        Ok(fundable) => {
+            if fundable < size {
+                warn!("Insufficient funds for {} shares, sizing down to {}", size, fundable);
+            }
+            fundable
+        }
+        Err(e) => {
+            warn!("Could not check funds, skipping: {:#}", e);
+            return;
+        }
+    };
+
+    // Risk limits may size the trade down or refuse it outright
+    let check = TradeCheck {
+        condition_ids: vec![opp.market_id.as_str()],
+        size,
+        cost: opp.fill_cost(size),
+        adds_exposure: opp.direction == ArbDirection::BuyBoth,
+    };
+    let size = match risk.approve(scanner.client().positions(), &check) {
+        Ok(approved) => {
+            if approved < size {
+                warn!("Risk limits size trade down from {} to {}", size, approved);
+            }
+            approved
+        }
+        Err(rejection) => {
+            warn!("Risk check rejected trade: {}", rejection);
+            return;
+        }
+    };
 
     if size < Decimal::ONE {
         warn!("Order size too small, skipping");
@@ -281,29 +826,353 @@ async fn handle_opportunity(
     }
 
     // Execute the arbitrage
-    stats.trades_executed += 1;
+    stats.write().await.trades_executed += 1;
+
+    let result = match opp.direction {
+        ArbDirection::BuyBoth => scanner.client().execute_arbitrage(opp, size).await,
+        ArbDirection::SellBoth => scanner.client().execute_sell_arbitrage(opp, size).await,
+    };
+
+    let execution = match result {
+        Ok(execution) => {
+            risk.record_trade();
+            execution
+        }
+        Err(e) => {
+            error!("Trade execution failed: {}", e);
+            journal.record_trade(&opp.id, size, TradeStatus::Failed, opp.expected_profit(size));
+            return;
+        }
+    };
+
+    let ArbExecution { yes: yes_resp, no: no_resp, recovery } = &execution;
+    journal.record_trade(
+        &opp.id,
+        size,
+        TradeStatus::from_legs([yes_resp, no_resp]),
+        opp.expected_profit(size),
+    );
+    if let Some(report) = recovery {
+        journal.record_recovery(&opp.id, report);
+    }
+    let mut stats = stats.write().await;
+    if yes_resp.success && no_resp.success {
+        stats.trades_successful += 1;
+        stats.total_profit += opp.expected_profit(size);
+        info!("Trade successful! Locked profit: ${}", opp.expected_profit(size));
+    } else {
+        warn!(
+            "Trade partially failed - {}: {}, {}: {}",
+            opp.yes_outcome, yes_resp.success, opp.no_outcome, no_resp.success
+        );
+        if let Some(err) = &yes_resp.error_msg {
+            warn!("{} error: {}", opp.yes_outcome, err);
+        }
+        if let Some(err) = &no_resp.error_msg {
+            warn!("{} error: {}", opp.no_outcome, err);
+        }
+
+        if let Some(report) = recovery {
+            stats.partial_fills += 1;
+            stats.total_profit += report.realized_pnl;
+            // Hedged sets realize their P&L later, on merge or redemption
+            if matches!(report.action, RecoveryAction::Unwound { .. }) {
+                risk.record_pnl(report.realized_pnl);
+            }
+            match report.action {
+                RecoveryAction::Hedged { .. } => stats.recoveries_hedged += 1,
+                RecoveryAction::Unwound { .. } => stats.recoveries_unwound += 1,
+                RecoveryAction::Unhedged => {}
+            }
+            warn!(
+                "Recovery of {} x {}: {:?} after {} attempt(s) and {} order(s), realized P&L ${}",
+                report.size,
+                report.exposed_token_id,
+                report.action,
+                report.attempts,
+                report.orders.len(),
+                report.realized_pnl
+            );
+        }
+    }
+
+    // Complete sets we now hold (sells leave nothing new behind)
+    let held_sets = match opp.direction {
+        ArbDirection::BuyBoth => execution.matched_sets(size),
+        ArbDirection::SellBoth => Decimal::ZERO,
+    };
+    let track = config.enable_redeem && opp.direction == ArbDirection::BuyBoth;
+
+    // Turn matched sets back into USDC instead of holding them to resolution.
+    // Merges wait on settlement and confirmation, so they run off the trading path.
+    if config.enable_merge && held_sets > Decimal::ZERO {
+        let opp = opp.clone();
+        let recovery = recovery.clone();
+        let services = services.clone();
+        let merges = services.merges.clone();
+        let mut merges = merges.lock();
+        while merges.try_join_next().is_some() {}
+        merges.spawn(async move {
+            let held_sets = merge_held_sets(&scanner, &opp, held_sets, &services).await;
+            if track {
+                services.redeemer.track(held_pair_position(&opp, held_sets, recovery.as_ref()));
+            }
+        });
+    } else if track {
+        redeemer.track(held_pair_position(opp, held_sets, recovery.as_ref()));
+    }
+}
+
+/// Merge held sets of a pair into USDC, returning how many are still held
+async fn merge_held_sets(
+    scanner: &ArbitrageScanner,
+    opp: &ArbitrageOpportunity,
+    sets: Decimal,
+    services: &TradeServices,
+) -> Decimal {
+    match scanner.client().merge_complete_sets(opp, sets).await {
+        Ok(receipt) => {
+            // Merging locks in the set's payout, realizing its profit or loss net
+            // of fees and the merged share of the trade's fixed cost
+            let fixed_cost = opp.costs.fixed_cost * receipt.amount / sets;
+            let pnl = receipt.amount - opp.fill_cost(receipt.amount) - fixed_cost;
+            services.risk.record_pnl(pnl);
+            services.journal.record_merge(&opp.id, &receipt, pnl);
+            services.stats.write().await.sets_merged += receipt.amount;
+            info!(
+                "Merged {} sets of {} into USDC (tx {}, gas {})",
+                receipt.amount, receipt.condition_id, receipt.tx_hash, receipt.gas_used
+            );
+            sets - receipt.amount
+        }
+        Err(e) => {
+            error!("Merge failed, holding sets until resolution: {:#}", e);
+            sets
+        }
+    }
+}
+
+/// Count a simulated trade in the stats and log the paper account
+fn record_paper_trade(stats: &mut BotStats, paper: &PaperTrader, result: Result<PaperTrade>) {
+    match result {
+        Ok(trade) => {
+            stats.paper_trades += 1;
+            stats.paper_pnl += trade.pnl;
+            info!(
+                "PAPER trade {:?}: {} of {} sets, P&L ${} (expected ${})",
+                trade.status(),
+                trade.sets,
+                trade.size,
+                trade.pnl,
+                trade.expected_profit
+            );
+            info!("Paper account: {}", paper.summary());
+        }
+        Err(e) => warn!("Paper trade skipped: {:#}", e),
+    }
+}
+
+/// Tokens of a basket left to redeem: complete sets, or any leg recovery
+/// couldn't unwind
+fn held_basket_position(opp: &BasketOpportunity, execution: &BasketExecution) -> HeldPosition {
+    HeldPosition {
+        id: opp.group_id.clone(),
+        description: opp.description.clone(),
+        legs: opp
+            .legs
+            .iter()
+            .zip(&execution.held)
+            .filter(|(_, shares)| **shares > Decimal::ZERO)
+            .map(|(leg, shares)| HeldLeg {
+                condition_id: leg.condition_id.clone(),
+                token_id: leg.token_id.clone(),
+                label: leg.label.clone(),
+                shares: *shares,
+            })
+            .collect(),
+        cost: execution.cost.iter().sum(),
+        opened_at: opp.timestamp,
+    }
+}
+
+/// Tokens of a pair arbitrage left to redeem: unmerged sets plus any
+/// leg left exposed by an unsuccessful recovery
+fn held_pair_position(
+    opp: &ArbitrageOpportunity,
+    sets: Decimal,
+    recovery: Option<&RecoveryReport>,
+) -> HeldPosition {
+    let mut yes_shares = sets;
+    let mut no_shares = sets;
+    let mut cost = opp.fill_cost(sets);
+
+    if let Some(report) = recovery.filter(|r| matches!(r.action, RecoveryAction::Unhedged)) {
+        let (yes_cost, no_cost) = opp.leg_costs(report.size);
+        if report.exposed_token_id == opp.yes_token_id {
+            yes_shares += report.size;
+            cost += yes_cost;
+        } else {
+            no_shares += report.size;
+            cost += no_cost;
+        }
+    }
+
+    HeldPosition {
+        id: opp.market_id.clone(),
+        description: format!("{} / {}", opp.yes_outcome, opp.no_outcome),
+        legs: vec![
+            HeldLeg {
+                condition_id: opp.market_id.clone(),
+                token_id: opp.yes_token_id.clone(),
+                label: opp.yes_outcome.clone(),
+                shares: yes_shares,
+            },
+            HeldLeg {
+                condition_id: opp.market_id.clone(),
+                token_id: opp.no_token_id.clone(),
+                label: opp.no_outcome.clone(),
+                shares: no_shares,
+            },
+        ],
+        cost,
+        opened_at: opp.timestamp,
+    }
+}
+
+/// Handle a detected multi-outcome basket opportunity
+async fn handle_basket_opportunity(
+    opp: &BasketOpportunity,
+    scanner: Arc<ArbitrageScanner>,
+    services: &TradeServices,
+    config: &Config,
+) {
+    let TradeServices { redeemer, risk, journal, paper, stats, .. } = services;
+
+    info!("===========================================");
+    info!("  BASKET OPPORTUNITY DETECTED!");
+    info!("===========================================");
+    info!("  Group: {} ({})", opp.description, opp.group_id);
+    for leg in &opp.legs {
+        info!("  {}: ${} (VWAP ${})", leg.label, leg.best_ask, leg.vwap);
+    }
+    info!("  Combined: ${} ({} legs)", opp.combined_price, opp.legs.len());
+    info!("  Profit/share: ${}", opp.profit_per_share);
+    info!("  Combined VWAP: ${}", opp.combined_vwap);
+    info!("  Max size: {} ({} levels)", opp.max_size, opp.levels.len());
+    info!("  Total expected profit: ${}", opp.total_expected_profit);
+    info!("  Expected profit: ${}", opp.expected_profit(config.order_size));
+    info!("===========================================");
+
+    journal.record_basket_opportunity(opp);
+
+    let size = config.order_size.min(opp.max_size);
+
+    if config.dry_run {
+        match paper {
+            Some(paper) if size >= Decimal::ONE => {
+                let result = paper.execute_basket(scanner.client(), opp, size).await;
+                record_paper_trade(&mut *stats.write().await, paper, result);
+            }
+            _ => info!("DRY RUN - Skipping trade execution"),
+        }
+        return;
+    }
 
-    match scanner.client().execute_arbitrage(opp, size).await {
-        Ok((yes_resp, no_resp)) => {
-            if yes_resp.success && no_resp.success {
+    let size = match scanner.client().fundable_basket_size(opp, size).await {
+        Ok(fundable) => {
+            if fundable < size {
+                warn!("Insufficient USDC for {} baskets, sizing down to {}", size, fundable);
+            }
+            fundable
+        }
+        Err(e) => {
+            warn!("Could not check USDC balance, skipping: {:#}", e);
+            return;
+        }
+    };
+
+    let check = TradeCheck {
+        condition_ids: opp.legs.iter().map(|leg| leg.condition_id.as_str()).collect(),
+        size,
+        cost: opp.fill_cost(size),
+        adds_exposure: true,
+    };
+    let size = match risk.approve(scanner.client().positions(), &check) {
+        Ok(approved) => {
+            if approved < size {
+                warn!("Risk limits size basket down from {} to {}", size, approved);
+            }
+            approved
+        }
+        Err(rejection) => {
+            warn!("Risk check rejected basket: {}", rejection);
+            return;
+        }
+    };
+
+    if size < Decimal::ONE {
+        warn!("Order size too small, skipping");
+        return;
+    }
+
+    stats.write().await.trades_executed += 1;
+
+    match scanner.client().execute_basket(opp, size).await {
+        Ok(execution) => {
+            risk.record_trade();
+            journal.record_trade(
+                &opp.id,
+                size,
+                TradeStatus::from_legs(&execution.legs),
+                opp.expected_profit(size),
+            );
+            for report in &execution.recovery {
+                journal.record_recovery(&opp.id, report);
+            }
+            let mut stats = stats.write().await;
+            if execution.legs.iter().all(|r| r.success) {
                 stats.trades_successful += 1;
                 stats.total_profit += opp.expected_profit(size);
-                info!("Trade successful! Locked profit: ${}", opp.expected_profit(size));
+                info!("Basket successful! Locked profit: ${}", opp.expected_profit(size));
             } else {
-                warn!(
-                    "Trade partially failed - YES: {}, NO: {}",
-                    yes_resp.success, no_resp.success
-                );
-                if let Some(err) = yes_resp.error_msg {
-                    warn!("YES error: {}", err);
+                for (leg, resp) in opp.legs.iter().zip(&execution.legs) {
+                    if let Some(err) = &resp.error_msg {
+                        warn!("{} ({}) error: {}", leg.label, leg.condition_id, err);
+                    }
                 }
-                if let Some(err) = no_resp.error_msg {
-                    warn!("NO error: {}", err);
+
+                if !execution.recovery.is_empty() {
+                    stats.partial_fills += 1;
+                    let unwound_pnl: Decimal = execution.recovery.iter().map(|r| r.realized_pnl).sum();
+                    if execution.matched_sets() > Decimal::ZERO {
+                        stats.recoveries_hedged += 1;
+                        let cost: Decimal = execution.cost.iter().sum();
+                        stats.total_profit += execution.matched_sets() - cost - opp.costs.fixed_cost;
+                    } else {
+                        if execution.is_flat() {
+                            stats.recoveries_unwound += 1;
+                        }
+                        stats.total_profit += unwound_pnl;
+                        risk.record_pnl(unwound_pnl);
+                    }
+                    warn!(
+                        "Basket recovery on group {}: {} leg report(s), {} sets held, realized P&L ${}",
+                        opp.group_id,
+                        execution.recovery.len(),
+                        execution.matched_sets(),
+                        unwound_pnl
+                    );
                 }
             }
+
+            // Baskets pay out on resolution, so hold every leg still held until then
+            if config.enable_redeem {
+                redeemer.track(held_basket_position(opp, &execution));
+            }
         }
         Err(e) => {
-            error!("Trade execution failed: {}", e);
+            error!("Basket execution failed: {}", e);
+            journal.record_trade(&opp.id, size, TradeStatus::Failed, opp.expected_profit(size));
         }
     }
 }
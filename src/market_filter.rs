@@ -0,0 +1,221 @@
+//! Market selection
+//! Narrows the listed markets down to the ones worth scanning, by text,
+//! tags, liquidity, volume, time to resolution and explicit id lists
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::{Regex, RegexBuilder};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+
+use crate::api::{Market, MarketStats};
+
+/// Question pattern used by `CRYPTO_ONLY` when no question filter is set;
+/// whole words only, so "eth" doesn't match "whether"
+pub const CRYPTO_QUESTION_PATTERN: &str =
+    r"\b(bitcoin|btc|ethereum|eth|ether|solana|sol|xrp|ripple|crypto|cryptocurrency)\b";
+
+/// Which listed markets are scanned
+///
+/// Every criterion narrows the selection and unset ones let everything
+/// through. Text patterns are case-insensitive; tags and condition ids are
+/// compared case-insensitively too.
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    /// Question must match
+    pub question_include: Option<Regex>,
+    /// Question must not match
+    pub question_exclude: Option<Regex>,
+    /// Slug must match
+    pub slug_include: Option<Regex>,
+    /// Slug must not match
+    pub slug_exclude: Option<Regex>,
+    /// Market must carry at least one of these tags (its category counts as a tag)
+    pub tags: Vec<String>,
+    /// Market must carry none of these tags
+    pub exclude_tags: Vec<String>,
+    /// Minimum liquidity in USDC, from the market stats
+    pub min_liquidity: Option<Decimal>,
+    /// Minimum traded volume in USDC, from the market stats
+    pub min_volume: Option<Decimal>,
+    /// Market must end at least this long from now
+    pub min_time_to_end: Option<chrono::Duration>,
+    /// Market must end at most this long from now
+    pub max_time_to_end: Option<chrono::Duration>,
+    /// Only these condition ids, if any are given
+    pub allow: HashSet<String>,
+    /// Never these condition ids
+    pub deny: HashSet<String>,
+}
+
+impl MarketFilter {
+    /// Crypto markets only, as selected by `CRYPTO_ONLY`
+    pub fn crypto() -> Self {
+        Self {
+            question_include: Some(pattern(CRYPTO_QUESTION_PATTERN).expect("crypto pattern is valid")),
+            ..Self::default()
+        }
+    }
+
+    /// Whether liquidity or volume limits need market stats fetched
+    pub fn needs_stats(&self) -> bool {
+        self.min_liquidity.is_some() || self.min_volume.is_some()
+    }
+
+    /// Markets passing every criterion that doesn't need stats
+    pub fn apply(&self, markets: Vec<Market>, now: DateTime<Utc>) -> Vec<Market> {
+        markets.into_iter().filter(|m| self.matches(m, now)).collect()
+    }
+
+    /// Markets meeting the liquidity and volume minimums; markets without
+    /// stats are dropped when a minimum is set
+    pub fn apply_stats(&self, markets: Vec<Market>, stats: &[MarketStats]) -> Vec<Market> {
+        if !self.needs_stats() {
+            return markets;
+        }
+        let stats: HashMap<String, &MarketStats> = stats
+            .iter()
+            .map(|s| (s.condition_id.to_lowercase(), s))
+            .collect();
+
+        markets
+            .into_iter()
+            .filter(|m| match stats.get(&m.condition_id.to_lowercase()) {
+                Some(s) => {
+                    self.min_liquidity.is_none_or(|min| s.liquidity >= min)
+                        && self.min_volume.is_none_or(|min| s.volume >= min)
+                }
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Whether the allow and deny lists let a market through
+    pub fn permits(&self, market: &Market) -> bool {
+        let id = market.condition_id.to_lowercase();
+        !self.deny.contains(&id) && (self.allow.is_empty() || self.allow.contains(&id))
+    }
+
+    /// Whether a market passes every criterion that doesn't need stats
+    pub fn matches(&self, market: &Market, now: DateTime<Utc>) -> bool {
+        if !self.permits(market) {
+            return false;
+        }
+
+        let question = market.question.as_deref().unwrap_or_default();
+        let slug = market.market_slug.as_deref().unwrap_or_default();
+        if !included(&self.question_include, question)
+            || excluded(&self.question_exclude, question)
+            || !included(&self.slug_include, slug)
+            || excluded(&self.slug_exclude, slug)
+        {
+            return false;
+        }
+
+        if !self.tags.is_empty() || !self.exclude_tags.is_empty() {
+            let tags: Vec<String> = market
+                .tags
+                .iter()
+                .chain(market.category.iter())
+                .map(|t| t.to_lowercase())
+                .collect();
+            if !self.tags.is_empty() && !self.tags.iter().any(|t| tags.contains(t)) {
+                return false;
+            }
+            if self.exclude_tags.iter().any(|t| tags.contains(t)) {
+                return false;
+            }
+        }
+
+        if self.min_time_to_end.is_some() || self.max_time_to_end.is_some() {
+            let Some(end) = market.end_date_iso.as_deref().and_then(parse_end_date) else {
+                return false;
+            };
+            let remaining = end - now;
+            if self.min_time_to_end.is_some_and(|min| remaining < min)
+                || self.max_time_to_end.is_some_and(|max| remaining > max)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl std::fmt::Display for MarketFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(re) = &self.question_include {
+            parts.push(format!("question ~ /{}/", re));
+        }
+        if let Some(re) = &self.question_exclude {
+            parts.push(format!("question !~ /{}/", re));
+        }
+        if let Some(re) = &self.slug_include {
+            parts.push(format!("slug ~ /{}/", re));
+        }
+        if let Some(re) = &self.slug_exclude {
+            parts.push(format!("slug !~ /{}/", re));
+        }
+        if !self.tags.is_empty() {
+            parts.push(format!("tags any of [{}]", self.tags.join(", ")));
+        }
+        if !self.exclude_tags.is_empty() {
+            parts.push(format!("tags none of [{}]", self.exclude_tags.join(", ")));
+        }
+        if let Some(min) = self.min_liquidity {
+            parts.push(format!("liquidity >= ${}", min));
+        }
+        if let Some(min) = self.min_volume {
+            parts.push(format!("volume >= ${}", min));
+        }
+        if let Some(min) = self.min_time_to_end {
+            parts.push(format!("ends in >= {}m", min.num_minutes()));
+        }
+        if let Some(max) = self.max_time_to_end {
+            parts.push(format!("ends in <= {}m", max.num_minutes()));
+        }
+        if !self.allow.is_empty() {
+            parts.push(format!("{} allowed ids", self.allow.len()));
+        }
+        if !self.deny.is_empty() {
+            parts.push(format!("{} denied ids", self.deny.len()));
+        }
+
+        if parts.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", parts.join(" | "))
+        }
+    }
+}
+
+/// Compile a case-insensitive filter pattern
+pub fn pattern(raw: &str) -> Result<Regex> {
+    RegexBuilder::new(raw)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("Invalid pattern {}", raw))
+}
+
+fn included(re: &Option<Regex>, text: &str) -> bool {
+    re.as_ref().is_none_or(|re| re.is_match(text))
+}
+
+fn excluded(re: &Option<Regex>, text: &str) -> bool {
+    re.as_ref().is_some_and(|re| re.is_match(text))
+}
+
+/// End dates come as RFC 3339 timestamps or bare dates (taken as midnight UTC)
+pub fn parse_end_date(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|d| d.and_utc())
+        })
+}
@@ -0,0 +1,719 @@
+//! Stand-in Polymarket server
+//! Serves a fixture's markets, order books, order entry and market channel over
+//! HTTP and WebSocket, so the full bot can run without network access
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, info};
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket};
+use warp::{Filter, Reply};
+
+use crate::api::ctf::{CTF_EXCHANGE_ADDRESS, NEG_RISK_ADAPTER_ADDRESS, NEG_RISK_EXCHANGE_ADDRESS};
+use crate::api::{Exchange, Market, MarketStats, MockExchange, OrderBook, OrderRequest, OrderResponse, OrderType, Side};
+use crate::orderbook::OrderBookStore;
+use crate::websocket::WsClient;
+
+/// Exchange contracts reported as approved by `/balance-allowance`
+const EXCHANGE_CONTRACTS: [&str; 3] = [CTF_EXCHANGE_ADDRESS, NEG_RISK_EXCHANGE_ADDRESS, NEG_RISK_ADAPTER_ADDRESS];
+
+/// Unlimited approval (2^256 - 1) in base units
+const MAX_ALLOWANCE: &str =
+    "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+/// Cursor after the last page of a listing (base64 of "-1")
+const END_CURSOR: &str = "LTE=";
+
+/// USDC and outcome tokens both have 6 decimals on the wire
+const UNITS: u64 = 1_000_000;
+
+/// Markets, books and market channel script served by the mock server
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    pub markets: Vec<Market>,
+    #[serde(default)]
+    pub books: Vec<OrderBook>,
+    /// Liquidity and volume served by the Gamma API route
+    #[serde(default)]
+    pub stats: Vec<MarketStats>,
+    /// USDC the account starts with, fully approved
+    #[serde(default = "default_balance")]
+    pub balance: Decimal,
+    /// Markets per page of `/sampling-markets`
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    /// Tokens whose orders are rejected, to script failed legs
+    #[serde(default)]
+    pub reject_tokens: Vec<String>,
+    /// Frames sent on the market channel after the subscription snapshot
+    #[serde(default)]
+    pub ws_script: Vec<ScriptedFrame>,
+}
+
+fn default_balance() -> Decimal {
+    Decimal::from(1000)
+}
+
+fn default_page_size() -> usize {
+    500
+}
+
+impl Fixture {
+    /// Load a fixture from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("Invalid fixture {}", path.display()))
+    }
+}
+
+/// One scripted market channel frame
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedFrame {
+    /// Wait before sending, in milliseconds
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Event or array of events, exactly as the live channel sends them
+    pub frame: Value,
+}
+
+/// A running mock server, shut down when dropped
+///
+/// The CLOB and data API share one address; the Gamma API is served under
+/// `/gamma` and the market channel at `/ws/market`. Orders are matched by a `MockExchange` loaded from the
+/// fixture, which stays available for inspection.
+pub struct MockServer {
+    addr: SocketAddr,
+    exchange: Arc<MockExchange>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Serve `fixture` on 127.0.0.1:`port`; port 0 picks a free one
+    pub async fn start(fixture: Fixture, port: u16) -> Result<Self> {
+        let exchange = Arc::new(MockExchange::new(fixture.balance));
+        exchange.set_markets(fixture.markets.clone());
+        for book in &fixture.books {
+            exchange.set_book(book.clone());
+        }
+        for stats in &fixture.stats {
+            exchange.set_market_stats(stats.clone());
+        }
+        for token_id in &fixture.reject_tokens {
+            exchange.reject_orders(token_id);
+        }
+
+        let state = Arc::new(State {
+            exchange: exchange.clone(),
+            markets: fixture.markets,
+            page_size: fixture.page_size.max(1),
+            script: fixture.ws_script,
+        });
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let (addr, server) = warp::serve(routes(state))
+            .try_bind_with_graceful_shutdown(([127, 0, 0, 1], port), async {
+                signal.await.ok();
+            })
+            .context("Failed to bind mock server")?;
+        tokio::spawn(server);
+
+        info!("Mock Polymarket server listening on {}", addr);
+        Ok(Self { addr, exchange, shutdown: Some(shutdown) })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL for `CLOB_HOST`
+    pub fn clob_host(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Base URL for `DATA_API_HOST`
+    pub fn data_api_host(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Base URL for `GAMMA_HOST`
+    pub fn gamma_host(&self) -> String {
+        format!("http://{}/gamma", self.addr)
+    }
+
+    /// URL for `WS_URL`
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}/ws/market", self.addr)
+    }
+
+    /// The exchange matching posted orders, holding every order and balance
+    pub fn exchange(&self) -> &Arc<MockExchange> {
+        &self.exchange
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// Shared by every request handler
+struct State {
+    exchange: Arc<MockExchange>,
+    markets: Vec<Market>,
+    page_size: usize,
+    script: Vec<ScriptedFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CursorQuery {
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// Subscription sent by clients of the market channel
+#[derive(Debug, Deserialize)]
+struct Subscription {
+    #[serde(default)]
+    assets_ids: Vec<String>,
+}
+
+impl State {
+    /// A page of `/sampling-markets`; cursors are base64 offsets, as on the live API
+    fn markets_page(&self, cursor: Option<&str>) -> Value {
+        let offset = cursor
+            .and_then(|c| base64::engine::general_purpose::STANDARD.decode(c).ok())
+            .and_then(|raw| String::from_utf8(raw).ok())
+            .and_then(|raw| raw.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let data: Vec<Value> = self
+            .markets
+            .iter()
+            .skip(offset)
+            .take(self.page_size)
+            .map(market_json)
+            .collect();
+        let next = offset + self.page_size;
+        let next_cursor = if next < self.markets.len() {
+            base64::engine::general_purpose::STANDARD.encode(next.to_string())
+        } else {
+            END_CURSOR.to_string()
+        };
+
+        json!({
+            "limit": self.page_size,
+            "count": data.len(),
+            "next_cursor": next_cursor,
+            "data": data,
+        })
+    }
+
+    fn market_by_token(&self, token_id: &str) -> Option<&Market> {
+        self.markets
+            .iter()
+            .find(|m| m.tokens.iter().any(|t| t.token_id == token_id))
+    }
+
+    async fn book(&self, token_id: &str) -> Option<OrderBook> {
+        self.exchange
+            .get_order_books(&[token_id.to_string()])
+            .await
+            .ok()
+            .and_then(|mut books| books.pop())
+    }
+
+    /// Decode a signed order and match it against the books
+    async fn post_order(&self, body: &Value) -> Value {
+        let (request, order_type) = match self.decode_order(body) {
+            Ok(decoded) => decoded,
+            Err(e) => return order_json(&OrderResponse::failed(format!("invalid order: {:#}", e))),
+        };
+
+        let response = match self.exchange.post_orders(std::slice::from_ref(&request), order_type).await {
+            Ok(mut responses) => responses.pop().unwrap_or_else(|| OrderResponse::failed("no response")),
+            Err(e) => OrderResponse::failed(e.to_string()),
+        };
+        debug!(
+            "Mock {} {} {} @ {} ({}): {}",
+            order_type,
+            request.side,
+            request.size,
+            request.price,
+            request.label,
+            if response.success { "matched" } else { "rejected" }
+        );
+        order_json(&response)
+    }
+
+    /// Recover the limit price and size from a signed order's amounts
+    ///
+    /// Buys give `makerAmount` USDC for `takerAmount` shares, sells the reverse.
+    fn decode_order(&self, body: &Value) -> Result<(OrderRequest, OrderType)> {
+        let order = body.get("order").context("missing order")?;
+        let token_id = order
+            .get("tokenId")
+            .and_then(|v| v.as_str().map(str::to_string).or_else(|| v.as_u64().map(|n| n.to_string())))
+            .context("missing tokenId")?;
+        let maker = units(order.get("makerAmount")).context("invalid makerAmount")?;
+        let taker = units(order.get("takerAmount")).context("invalid takerAmount")?;
+
+        let side = match order.get("side") {
+            Some(Value::String(s)) if s.eq_ignore_ascii_case("buy") => Side::Buy,
+            Some(Value::String(s)) if s.eq_ignore_ascii_case("sell") => Side::Sell,
+            Some(Value::Number(n)) if n.as_u64() == Some(0) => Side::Buy,
+            Some(Value::Number(n)) if n.as_u64() == Some(1) => Side::Sell,
+            _ => anyhow::bail!("invalid side"),
+        };
+        let (usdc, shares) = match side {
+            Side::Buy => (maker, taker),
+            Side::Sell => (taker, maker),
+        };
+        if shares.is_zero() {
+            anyhow::bail!("zero size");
+        }
+
+        let order_type = match body.get("orderType").and_then(Value::as_str) {
+            Some("FOK") => OrderType::Fok,
+            Some("FAK") | Some("IOC") => OrderType::Ioc,
+            _ => OrderType::Gtc,
+        };
+
+        let label = self
+            .market_by_token(&token_id)
+            .and_then(|m| m.tokens.iter().find(|t| t.token_id == token_id))
+            .map(|t| t.outcome.clone())
+            .unwrap_or_else(|| token_id.clone());
+
+        Ok((
+            OrderRequest {
+                token_id,
+                label,
+                side,
+                price: (usdc / shares).round_dp(4),
+                size: shares,
+            },
+            order_type,
+        ))
+    }
+
+    async fn funding(&self) -> Value {
+        let balance = self.exchange.balance();
+        let allowances: serde_json::Map<String, Value> = EXCHANGE_CONTRACTS
+            .iter()
+            .map(|address| (address.to_string(), json!(MAX_ALLOWANCE)))
+            .collect();
+        json!({
+            "balance": (balance * Decimal::from(UNITS)).trunc().to_string(),
+            "allowances": allowances,
+        })
+    }
+
+    /// Holdings in the data API's shape, paged by offset
+    async fn positions(&self, page: &PageQuery) -> Value {
+        let positions = self.exchange.get_account_positions().await.unwrap_or_default();
+        let rows: Vec<Value> = positions
+            .iter()
+            .skip(page.offset)
+            .take(page.limit.unwrap_or(usize::MAX))
+            .map(|p| {
+                json!({
+                    "asset": p.asset,
+                    "conditionId": p.condition_id,
+                    "size": number(p.size),
+                    "avgPrice": number(p.avg_price),
+                    "outcome": p.outcome,
+                })
+            })
+            .collect();
+        Value::Array(rows)
+    }
+}
+
+fn routes(state: Arc<State>) -> impl Filter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone {
+    let state = warp::any().map(move || state.clone());
+
+    let health = warp::get()
+        .and(warp::path::end())
+        .map(|| ok(json!("OK")));
+
+    let time = warp::get()
+        .and(warp::path!("time"))
+        .map(|| ok(json!(chrono::Utc::now().timestamp())));
+
+    // Any key works; signatures and L2 headers aren't checked
+    let api_key = warp::path!("auth" / "api-key")
+        .or(warp::path!("auth" / "derive-api-key"))
+        .unify()
+        .map(|| {
+            ok(json!({
+                "apiKey": "00000000-0000-0000-0000-000000000000",
+                "secret": "bW9jay1zZXJ2ZXItc2VjcmV0LW1vY2stc2VydmVyLXM=",
+                "passphrase": "mock-passphrase",
+            }))
+        });
+
+    let markets = warp::get()
+        .and(warp::path!("sampling-markets").or(warp::path!("markets")).unify())
+        .and(warp::query::<CursorQuery>())
+        .and(state.clone())
+        .map(|query: CursorQuery, state: Arc<State>| ok(state.markets_page(query.next_cursor.as_deref())));
+
+    let market = warp::get()
+        .and(warp::path!("markets" / String))
+        .and(state.clone())
+        .map(|condition_id: String, state: Arc<State>| {
+            match state.markets.iter().find(|m| m.condition_id == condition_id) {
+                Some(market) => ok(market_json(market)),
+                None => not_found("market not found"),
+            }
+        });
+
+    let book = warp::get()
+        .and(warp::path!("book"))
+        .and(warp::query::<TokenQuery>())
+        .and(state.clone())
+        .and_then(|query: TokenQuery, state: Arc<State>| async move {
+            Ok::<_, Infallible>(match state.book(&query.token_id).await {
+                Some(book) => ok(book_json(&book)),
+                None => not_found("No orderbook exists for the requested token id"),
+            })
+        });
+
+    let books = warp::post()
+        .and(warp::path!("books"))
+        .and(warp::body::json::<Vec<TokenQuery>>())
+        .and(state.clone())
+        .and_then(|queries: Vec<TokenQuery>, state: Arc<State>| async move {
+            let token_ids: Vec<String> = queries.into_iter().map(|q| q.token_id).collect();
+            let books = state.exchange.get_order_books(&token_ids).await.unwrap_or_default();
+            Ok::<_, Infallible>(ok(Value::Array(books.iter().map(book_json).collect())))
+        });
+
+    let tick_size = warp::get()
+        .and(warp::path!("tick-size"))
+        .and(warp::query::<TokenQuery>())
+        .and(state.clone())
+        .map(|query: TokenQuery, state: Arc<State>| match state.market_by_token(&query.token_id) {
+            Some(m) => ok(json!({ "minimum_tick_size": number(m.minimum_tick_size) })),
+            None => not_found("market not found"),
+        });
+
+    let neg_risk = warp::get()
+        .and(warp::path!("neg-risk"))
+        .and(warp::query::<TokenQuery>())
+        .and(state.clone())
+        .map(|query: TokenQuery, state: Arc<State>| match state.market_by_token(&query.token_id) {
+            Some(m) => ok(json!({ "neg_risk": m.neg_risk })),
+            None => not_found("market not found"),
+        });
+
+    let fee_rate = warp::get()
+        .and(warp::path!("fee-rate"))
+        .and(warp::query::<TokenQuery>())
+        .and(state.clone())
+        .map(|query: TokenQuery, state: Arc<State>| match state.market_by_token(&query.token_id) {
+            Some(m) => ok(json!({ "base_fee": number(m.taker_base_fee) })),
+            None => not_found("market not found"),
+        });
+
+    let order = warp::post()
+        .and(warp::path!("order"))
+        .and(warp::body::json::<Value>())
+        .and(state.clone())
+        .and_then(|body: Value, state: Arc<State>| async move {
+            Ok::<_, Infallible>(ok(state.post_order(&body).await))
+        });
+
+    let orders = warp::post()
+        .and(warp::path!("orders"))
+        .and(warp::body::json::<Vec<Value>>())
+        .and(state.clone())
+        .and_then(|bodies: Vec<Value>, state: Arc<State>| async move {
+            let mut responses = Vec::with_capacity(bodies.len());
+            for body in &bodies {
+                responses.push(state.post_order(body).await);
+            }
+            Ok::<_, Infallible>(ok(Value::Array(responses)))
+        });
+
+    // Nothing ever rests on the mock book
+    let cancel_all = warp::delete()
+        .and(warp::path!("cancel-all"))
+        .map(|| ok(json!({ "canceled": [], "not_canceled": {} })));
+
+    let balance = warp::get()
+        .and(warp::path!("balance-allowance"))
+        .and(state.clone())
+        .and_then(|state: Arc<State>| async move { Ok::<_, Infallible>(ok(state.funding().await)) });
+
+    let positions = warp::get()
+        .and(warp::path!("positions"))
+        .and(warp::query::<PageQuery>())
+        .and(state.clone())
+        .and_then(|page: PageQuery, state: Arc<State>| async move {
+            Ok::<_, Infallible>(ok(state.positions(&page).await))
+        });
+
+    // Gamma repeats `condition_ids` and `slug` for each market, which typed queries can't collect
+    let gamma_markets = warp::get()
+        .and(warp::path!("gamma" / "markets"))
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and(state.clone())
+        .and_then(|query: String, state: Arc<State>| async move {
+            let values = |key: &str| -> Vec<String> {
+                let prefix = format!("{}=", key);
+                query
+                    .split('&')
+                    .filter_map(|pair| pair.strip_prefix(prefix.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            };
+
+            let groups = values("neg_risk_market_id");
+            if !groups.is_empty() {
+                let members = state.exchange.get_group_members(&groups).await.unwrap_or_default();
+                let markets: Vec<Value> = members
+                    .iter()
+                    .flat_map(|(group_id, ids)| {
+                        ids.iter().map(move |id| json!({ "conditionId": id, "negRiskMarketID": group_id }))
+                    })
+                    .collect();
+                return Ok::<_, Infallible>(ok(Value::Array(markets)));
+            }
+
+            let slugs = values("slug");
+            if !slugs.is_empty() {
+                let found = state.exchange.get_markets_by_slug(&slugs).await.unwrap_or_default();
+                let markets: Vec<Value> = found
+                    .iter()
+                    .map(|m| json!({ "conditionId": m.condition_id, "slug": m.market_slug }))
+                    .collect();
+                return Ok::<_, Infallible>(ok(Value::Array(markets)));
+            }
+
+            let ids = values("condition_ids");
+            let stats = state.exchange.get_market_stats(&ids).await.unwrap_or_default();
+            let markets: Vec<Value> = stats
+                .iter()
+                .map(|s| {
+                    json!({
+                        "conditionId": s.condition_id,
+                        "liquidity": s.liquidity.to_string(),
+                        "liquidityNum": number(s.liquidity),
+                        "volume": s.volume.to_string(),
+                        "volumeNum": number(s.volume),
+                    })
+                })
+                .collect();
+            Ok::<_, Infallible>(ok(Value::Array(markets)))
+        });
+
+    let market_channel = warp::path!("ws" / "market")
+        .and(warp::ws())
+        .and(state)
+        .map(|ws: warp::ws::Ws, state: Arc<State>| {
+            ws.on_upgrade(move |socket| serve_market_channel(socket, state)).into_response()
+        });
+
+    health
+        .or(time).unify()
+        .or(api_key).unify()
+        .or(markets).unify()
+        .or(market).unify()
+        .or(book).unify()
+        .or(books).unify()
+        .or(tick_size).unify()
+        .or(neg_risk).unify()
+        .or(fee_rate).unify()
+        .or(order).unify()
+        .or(orders).unify()
+        .or(cancel_all).unify()
+        .or(balance).unify()
+        .or(positions).unify()
+        .or(gamma_markets).unify()
+        .or(market_channel).unify()
+}
+
+/// Snapshot the subscribed books, then play the fixture's script
+///
+/// Scripted frames are applied to the exchange's books as well, so orders
+/// placed on what the client sees fill against the same levels.
+async fn serve_market_channel(socket: WebSocket, state: Arc<State>) {
+    let (mut write, mut read) = socket.split();
+
+    let assets = loop {
+        match read.next().await {
+            Some(Ok(msg)) => {
+                if let Some(sub) = msg.to_str().ok().and_then(|t| serde_json::from_str::<Subscription>(t).ok()) {
+                    break sub.assets_ids;
+                }
+            }
+            _ => return,
+        }
+    };
+    debug!("Mock market channel subscribed to {} tokens", assets.len());
+
+    // The live channel opens with a book event per subscribed token
+    let books = state.exchange.get_order_books(&assets).await.unwrap_or_default();
+    let snapshot: Vec<Value> = books.iter().map(book_event).collect();
+    if write.send(Message::text(Value::Array(snapshot).to_string())).await.is_err() {
+        return;
+    }
+
+    let store = OrderBookStore::new();
+    for book in &books {
+        store.apply_snapshot(
+            &book.asset_id,
+            Some(&book.market),
+            &book.bids,
+            &book.asks,
+            Some(&book.hash),
+            Some(&book.timestamp),
+        );
+    }
+    for scripted in &state.script {
+        tokio::time::sleep(Duration::from_millis(scripted.delay_ms)).await;
+
+        let text = scripted.frame.to_string();
+        for update in WsClient::handle_message(&text, &store).unwrap_or_default() {
+            if let Some(book) = store.get(&update.asset_id) {
+                state.exchange.set_book(book);
+            }
+        }
+        if write.send(Message::text(text)).await.is_err() {
+            return;
+        }
+    }
+
+    // Hold the connection open until the client leaves
+    while let Some(Ok(_)) = read.next().await {}
+}
+
+fn ok(body: Value) -> warp::reply::Response {
+    warp::reply::json(&body).into_response()
+}
+
+fn not_found(error: &str) -> warp::reply::Response {
+    warp::reply::with_status(warp::reply::json(&json!({ "error": error })), StatusCode::NOT_FOUND).into_response()
+}
+
+/// Market in the CLOB API's shape
+fn market_json(m: &Market) -> Value {
+    let tokens: Vec<Value> = m
+        .tokens
+        .iter()
+        .map(|t| {
+            json!({
+                "token_id": t.token_id,
+                "outcome": t.outcome,
+                "price": number(t.price.unwrap_or_default()),
+                "winner": t.winner,
+            })
+        })
+        .collect();
+
+    json!({
+        "enable_order_book": true,
+        "active": m.active,
+        "closed": m.closed,
+        "archived": false,
+        "accepting_orders": m.accepting_orders,
+        "minimum_order_size": number(m.minimum_order_size),
+        "minimum_tick_size": number(m.minimum_tick_size),
+        "condition_id": m.condition_id,
+        "question_id": m.question_id,
+        "question": m.question.clone().unwrap_or_default(),
+        "description": m.description.clone().unwrap_or_default(),
+        "market_slug": m.market_slug.clone().unwrap_or_default(),
+        "end_date_iso": m.end_date_iso,
+        "game_start_time": m.game_start_time,
+        "seconds_delay": 0,
+        "fpmm": "",
+        "maker_base_fee": 0,
+        "taker_base_fee": number(m.taker_base_fee),
+        "notifications_enabled": false,
+        "neg_risk": m.neg_risk,
+        "neg_risk_market_id": m.neg_risk_market_id.clone().unwrap_or_default(),
+        "neg_risk_request_id": "",
+        "icon": "",
+        "image": "",
+        "rewards": { "rates": null, "min_size": 0, "max_spread": 0 },
+        "is_50_50_outcome": false,
+        "tokens": tokens,
+        "tags": m.tags,
+    })
+}
+
+/// Book in the shape of `GET /book`
+fn book_json(book: &OrderBook) -> Value {
+    json!({
+        "market": book.market,
+        "asset_id": book.asset_id,
+        "bids": book.bids,
+        "asks": book.asks,
+        "hash": book.hash,
+        "timestamp": chrono::Utc::now().timestamp_millis().to_string(),
+    })
+}
+
+/// Book snapshot event of the market channel
+fn book_event(book: &OrderBook) -> Value {
+    let mut event = book_json(book);
+    event["event_type"] = json!("book");
+    event
+}
+
+/// Order result in the shape of `POST /order`
+fn order_json(response: &OrderResponse) -> Value {
+    json!({
+        "success": response.success,
+        "errorMsg": response.error_msg.clone().unwrap_or_default(),
+        "orderID": response.order_id.clone().unwrap_or_default(),
+        "transactionsHashes": [],
+        "status": if response.success { "matched" } else { "unmatched" },
+        "makingAmount": response.making_amount.unwrap_or_default().to_string(),
+        "takingAmount": response.taking_amount.unwrap_or_default().to_string(),
+    })
+}
+
+/// Amount in 6-decimal base units, sent as a string or a number
+fn units(value: Option<&Value>) -> Option<Decimal> {
+    let raw = match value? {
+        Value::String(s) => Decimal::from_str(s).ok()?,
+        Value::Number(n) => Decimal::from_str(&n.to_string()).ok()?,
+        _ => return None,
+    };
+    Some(raw / Decimal::from(UNITS))
+}
+
+fn number(value: Decimal) -> Value {
+    json!(value.to_f64().unwrap_or_default())
+}
@@ -0,0 +1,219 @@
+//! Local L2 order books maintained from the market WebSocket feed
+//! Applies `book` snapshots and `price_change` level deltas per asset
+
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+use crate::api::{OrderBook, OrderBookEntry, Side};
+
+/// Sorted price ladders for a single asset
+#[derive(Debug, Clone, Default)]
+pub struct LocalBook {
+    market: String,
+    /// price -> size
+    bids: BTreeMap<Decimal, Decimal>,
+    /// price -> size
+    asks: BTreeMap<Decimal, Decimal>,
+    hash: String,
+    timestamp: String,
+}
+
+impl LocalBook {
+    /// Replace both ladders with a full snapshot
+    fn apply_snapshot(&mut self, bids: &[OrderBookEntry], asks: &[OrderBookEntry]) {
+        self.bids = bids
+            .iter()
+            .filter(|e| e.size > Decimal::ZERO)
+            .map(|e| (e.price, e.size))
+            .collect();
+        self.asks = asks
+            .iter()
+            .filter(|e| e.size > Decimal::ZERO)
+            .map(|e| (e.price, e.size))
+            .collect();
+    }
+
+    /// Set the size at a single price level (zero removes the level)
+    fn apply_level(&mut self, side: Side, price: Decimal, size: Decimal) {
+        let ladder = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+
+        if size.is_zero() {
+            ladder.remove(&price);
+        } else {
+            ladder.insert(price, size);
+        }
+    }
+
+    /// Highest bid
+    pub fn best_bid(&self) -> Option<OrderBookEntry> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(price, size)| OrderBookEntry { price: *price, size: *size })
+    }
+
+    /// Lowest ask
+    pub fn best_ask(&self) -> Option<OrderBookEntry> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(price, size)| OrderBookEntry { price: *price, size: *size })
+    }
+
+    /// Convert to an `OrderBook` with best levels first on both sides
+    pub fn to_order_book(&self, asset_id: &str) -> OrderBook {
+        OrderBook {
+            market: self.market.clone(),
+            asset_id: asset_id.to_string(),
+            bids: self
+                .bids
+                .iter()
+                .rev()
+                .map(|(price, size)| OrderBookEntry { price: *price, size: *size })
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .map(|(price, size)| OrderBookEntry { price: *price, size: *size })
+                .collect(),
+            hash: self.hash.clone(),
+            timestamp: self.timestamp.clone(),
+        }
+    }
+}
+
+/// Concurrent store of local order books keyed by asset_id
+#[derive(Debug, Default)]
+pub struct OrderBookStore {
+    books: DashMap<String, LocalBook>,
+}
+
+impl OrderBookStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a full `book` snapshot for an asset
+    pub fn apply_snapshot(
+        &self,
+        asset_id: &str,
+        market: Option<&str>,
+        bids: &[OrderBookEntry],
+        asks: &[OrderBookEntry],
+        hash: Option<&str>,
+        timestamp: Option<&str>,
+    ) {
+        let mut book = self.books.entry(asset_id.to_string()).or_default();
+        book.apply_snapshot(bids, asks);
+        if let Some(market) = market {
+            book.market = market.to_string();
+        }
+        book.hash = hash.unwrap_or_default().to_string();
+        book.timestamp = timestamp.unwrap_or_default().to_string();
+    }
+
+    /// Apply a single `price_change` level update
+    ///
+    /// Returns false if no snapshot has been seen for the asset yet, since a
+    /// delta on an empty book would produce a misleading ladder.
+    pub fn apply_change(
+        &self,
+        asset_id: &str,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+        timestamp: Option<&str>,
+    ) -> bool {
+        match self.books.get_mut(asset_id) {
+            Some(mut book) => {
+                book.apply_level(side, price, size);
+                if let Some(ts) = timestamp {
+                    book.timestamp = ts.to_string();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Best bid and ask for an asset
+    pub fn best_prices(&self, asset_id: &str) -> Option<(Option<OrderBookEntry>, Option<OrderBookEntry>)> {
+        self.books
+            .get(asset_id)
+            .map(|book| (book.best_bid(), book.best_ask()))
+    }
+
+    /// Snapshot the current book for an asset as an `OrderBook`
+    pub fn get(&self, asset_id: &str) -> Option<OrderBook> {
+        self.books.get(asset_id).map(|book| book.to_order_book(asset_id))
+    }
+
+    /// Drop all books (e.g. before resubscribing after a reconnect)
+    pub fn clear(&self) {
+        self.books.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: i64, size: i64) -> OrderBookEntry {
+        OrderBookEntry { price: Decimal::new(price, 2), size: Decimal::from(size) }
+    }
+
+    fn store() -> OrderBookStore {
+        let store = OrderBookStore::new();
+        store.apply_snapshot(
+            "1",
+            Some("0xmarket"),
+            &[level(40, 10), level(39, 20)],
+            &[level(45, 10), level(46, 20)],
+            None,
+            Some("1"),
+        );
+        store
+    }
+
+    fn prices(levels: &[OrderBookEntry]) -> Vec<(Decimal, Decimal)> {
+        levels.iter().map(|l| (l.price, l.size)).collect()
+    }
+
+    #[test]
+    fn change_resizes_a_level() {
+        let store = store();
+        assert!(store.apply_change("1", Side::Sell, Decimal::new(45, 2), Decimal::from(3), Some("2")));
+        assert!(store.apply_change("1", Side::Buy, Decimal::new(41, 2), Decimal::from(5), Some("3")));
+
+        let book = store.get("1").unwrap();
+        assert_eq!(prices(&book.asks), prices(&[level(45, 3), level(46, 20)]));
+        assert_eq!(prices(&book.bids), prices(&[level(41, 5), level(40, 10), level(39, 20)]));
+        assert_eq!(book.timestamp, "3");
+    }
+
+    #[test]
+    fn zero_size_removes_a_level() {
+        let store = store();
+        assert!(store.apply_change("1", Side::Sell, Decimal::new(45, 2), Decimal::ZERO, None));
+        assert!(store.apply_change("1", Side::Buy, Decimal::new(40, 2), Decimal::ZERO, None));
+
+        let (bid, ask) = store.best_prices("1").unwrap();
+        assert_eq!(bid.unwrap().price, Decimal::new(39, 2));
+        assert_eq!(ask.unwrap().price, Decimal::new(46, 2));
+
+        // Removing a level that isn't there leaves the book alone
+        assert!(store.apply_change("1", Side::Sell, Decimal::new(50, 2), Decimal::ZERO, None));
+        assert_eq!(store.get("1").unwrap().asks.len(), 1);
+    }
+
+    #[test]
+    fn change_before_snapshot_is_ignored() {
+        let store = OrderBookStore::new();
+        assert!(!store.apply_change("1", Side::Sell, Decimal::new(45, 2), Decimal::from(3), None));
+        assert!(store.get("1").is_none());
+    }
+}
@@ -0,0 +1,311 @@
+//! Paper trading
+//! Simulates arbitrage legs against live order books and keeps a virtual account
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tracing::info;
+
+use crate::api::{
+    ArbDirection, ArbitrageOpportunity, BasketOpportunity, Executor, OrderBook, OrderBookEntry,
+    OrderPolicy, Side,
+};
+use crate::fees::CostModel;
+use crate::journal::TradeStatus;
+use crate::positions::PositionLedger;
+use crate::simulation::{simulate_trade, SimOrder};
+
+/// Execution assumptions for simulated fills
+#[derive(Debug, Clone)]
+pub struct PaperSettings {
+    /// Virtual USDC the account starts with
+    pub starting_balance: Decimal,
+    /// Delay between detection and the orders reaching the book, before the book is fetched
+    pub latency: Duration,
+    /// Price move per share against us while the orders are in flight (asks up, bids down)
+    pub price_move: Decimal,
+    /// Share of the displayed depth still resting when the orders arrive (0-1)
+    pub depth_fraction: Decimal,
+    /// Time in force and limit pricing, exactly as for live orders
+    pub orders: OrderPolicy,
+}
+
+/// Outcome of one simulated leg
+#[derive(Debug, Clone)]
+pub struct PaperLeg {
+    pub label: String,
+    pub side: Side,
+    pub limit: Decimal,
+    pub filled: Decimal,
+    /// USDC paid (buys) or received (sells) for the fill, after fees
+    pub usdc: Decimal,
+    /// Shares beyond the matched sets traded back into the book
+    pub unwound: Decimal,
+    /// Shares beyond the matched sets the book couldn't absorb
+    pub stranded: Decimal,
+}
+
+/// Outcome of one simulated arbitrage
+#[derive(Debug, Clone)]
+pub struct PaperTrade {
+    pub size: Decimal,
+    /// Complete sets bought or sold
+    pub sets: Decimal,
+    pub legs: Vec<PaperLeg>,
+    /// Realized P&L: set payouts, fills, unwinds and the fixed cost.
+    /// Stranded buys stay in the ledger at cost rather than counting as a loss.
+    pub pnl: Decimal,
+    pub expected_profit: Decimal,
+}
+
+impl PaperTrade {
+    pub fn status(&self) -> TradeStatus {
+        if self.sets >= self.size {
+            TradeStatus::Filled
+        } else if self.legs.iter().any(|leg| leg.filled > Decimal::ZERO) {
+            TradeStatus::Partial
+        } else {
+            TradeStatus::Failed
+        }
+    }
+}
+
+/// Virtual account totals
+#[derive(Debug, Clone)]
+pub struct PaperSummary {
+    pub starting_balance: Decimal,
+    pub balance: Decimal,
+    pub realized_pnl: Decimal,
+    pub trades: u64,
+    pub filled: u64,
+    pub partial: u64,
+    pub missed: u64,
+}
+
+impl std::fmt::Display for PaperSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "${} balance (started ${}) | ${} realized P&L | {} trades ({} filled, {} partial, {} missed)",
+            self.balance,
+            self.starting_balance,
+            self.realized_pnl,
+            self.trades,
+            self.filled,
+            self.partial,
+            self.missed
+        )
+    }
+}
+
+/// One leg to simulate
+struct PaperOrder<'a> {
+    condition_id: &'a str,
+    token_id: &'a str,
+    label: &'a str,
+    side: Side,
+    limit: Decimal,
+}
+
+/// Executes arbitrages against a virtual account instead of the exchange
+///
+/// Fills, unwinds and set settlement follow `simulate_trade`, as backtests
+/// do; buys left stranded by a partial fill stay in the position ledger.
+pub struct PaperTrader {
+    settings: PaperSettings,
+    account: Mutex<PaperSummary>,
+    positions: PositionLedger,
+}
+
+impl PaperTrader {
+    pub fn new(settings: PaperSettings) -> Self {
+        let account = PaperSummary {
+            starting_balance: settings.starting_balance,
+            balance: settings.starting_balance,
+            realized_pnl: Decimal::ZERO,
+            trades: 0,
+            filled: 0,
+            partial: 0,
+            missed: 0,
+        };
+        Self { settings, account: Mutex::new(account), positions: PositionLedger::new() }
+    }
+
+    pub fn summary(&self) -> PaperSummary {
+        self.account.lock().clone()
+    }
+
+    /// Tokens left unhedged by simulated partial fills
+    pub fn positions(&self) -> &PositionLedger {
+        &self.positions
+    }
+
+    /// Simulate both legs of a pair arbitrage
+    pub async fn execute_pair(
+        &self,
+        client: &Executor,
+        opportunity: &ArbitrageOpportunity,
+        size: Decimal,
+    ) -> Result<PaperTrade> {
+        // Sell-side sets are assumed split from USDC at $1 each
+        let per_set = match opportunity.direction {
+            ArbDirection::BuyBoth => opportunity.fill_cost(size) / size,
+            ArbDirection::SellBoth => Decimal::ONE,
+        };
+        let size = self.fundable_size(size, per_set)?;
+
+        let side = match opportunity.direction {
+            ArbDirection::BuyBoth => Side::Buy,
+            ArbDirection::SellBoth => Side::Sell,
+        };
+        let (yes_level, no_level) = opportunity.marginal_prices(size);
+        let orders = vec![
+            PaperOrder {
+                condition_id: &opportunity.market_id,
+                token_id: &opportunity.yes_token_id,
+                label: &opportunity.yes_outcome,
+                side,
+                limit: self.settings.orders.limit_price(side, yes_level, &opportunity.costs),
+            },
+            PaperOrder {
+                condition_id: &opportunity.market_id,
+                token_id: &opportunity.no_token_id,
+                label: &opportunity.no_outcome,
+                side,
+                limit: self.settings.orders.limit_price(side, no_level, &opportunity.costs),
+            },
+        ];
+
+        self.execute(client, &orders, size, &opportunity.costs, opportunity.expected_profit(size))
+            .await
+    }
+
+    /// Simulate every leg of a multi-outcome basket
+    pub async fn execute_basket(
+        &self,
+        client: &Executor,
+        opportunity: &BasketOpportunity,
+        size: Decimal,
+    ) -> Result<PaperTrade> {
+        let per_set = opportunity.fill_cost(size) / size;
+        let size = self.fundable_size(size, per_set)?;
+
+        let orders: Vec<PaperOrder> = opportunity
+            .legs
+            .iter()
+            .zip(opportunity.marginal_prices(size))
+            .map(|(leg, level)| PaperOrder {
+                condition_id: &leg.condition_id,
+                token_id: &leg.token_id,
+                label: &leg.label,
+                side: Side::Buy,
+                limit: self.settings.orders.limit_price(Side::Buy, level, &opportunity.costs),
+            })
+            .collect();
+
+        self.execute(client, &orders, size, &opportunity.costs, opportunity.expected_profit(size))
+            .await
+    }
+
+    /// Size down to what the virtual balance can fund at `per_set` USDC a set, fees included
+    fn fundable_size(&self, size: Decimal, per_set: Decimal) -> Result<Decimal> {
+        let balance = self.account.lock().balance;
+        let fundable = if per_set > Decimal::ZERO {
+            size.min((balance / per_set).floor())
+        } else {
+            size
+        };
+        if fundable < Decimal::ONE {
+            anyhow::bail!("Paper balance ${} can't fund a set at ${}", balance, per_set);
+        }
+        Ok(fundable)
+    }
+
+    /// Wait out the latency, then fill every leg against the books as they are now
+    async fn execute(
+        &self,
+        client: &Executor,
+        orders: &[PaperOrder<'_>],
+        size: Decimal,
+        costs: &CostModel,
+        expected_profit: Decimal,
+    ) -> Result<PaperTrade> {
+        tokio::time::sleep(self.settings.latency).await;
+
+        let token_ids: Vec<String> = orders.iter().map(|o| o.token_id.to_string()).collect();
+        let fetched = client.get_order_books(&token_ids).await?;
+        let books = orders
+            .iter()
+            .map(|order| {
+                fetched
+                    .iter()
+                    .find(|b| b.asset_id == order.token_id)
+                    .map(|b| Some(self.stress(b)))
+                    .with_context(|| format!("No order book for {}", order.label))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let sim_orders: Vec<SimOrder> = orders.iter().map(|o| SimOrder { side: o.side, limit: o.limit }).collect();
+        let sim = simulate_trade(&books, &sim_orders, size, costs);
+
+        // Buys the book couldn't take back stay in the ledger at cost
+        let mut legs = Vec::with_capacity(orders.len());
+        for (order, leg) in orders.iter().zip(&sim.legs) {
+            if order.side == Side::Buy && leg.stranded > Decimal::ZERO {
+                self.positions.record_fill(
+                    order.condition_id, order.token_id, order.label, Side::Buy, leg.stranded, leg.stranded_cost,
+                );
+            }
+            legs.push(PaperLeg {
+                label: order.label.to_string(),
+                side: order.side,
+                limit: order.limit,
+                filled: leg.filled,
+                usdc: leg.usdc,
+                unwound: leg.unwound,
+                stranded: leg.stranded,
+            });
+        }
+
+        let trade = PaperTrade { size, sets: sim.sets, legs, pnl: sim.pnl(), expected_profit };
+
+        let mut account = self.account.lock();
+        account.balance += sim.cash;
+        account.realized_pnl += trade.pnl;
+        account.trades += 1;
+        match trade.status() {
+            TradeStatus::Filled => account.filled += 1,
+            TradeStatus::Partial => account.partial += 1,
+            TradeStatus::Failed => account.missed += 1,
+        }
+
+        for leg in &trade.legs {
+            info!(
+                "  PAPER {} {}: filled {}/{} @ limit {} (${}), unwound {}, stranded {}",
+                leg.side, leg.label, leg.filled, size, leg.limit, leg.usdc, leg.unwound, leg.stranded
+            );
+        }
+
+        Ok(trade)
+    }
+
+    /// The book as assumed to stand when the orders land: prices moved
+    /// against us and only part of the depth left
+    fn stress(&self, book: &OrderBook) -> OrderBook {
+        let shift = |levels: &[OrderBookEntry], by: Decimal| -> Vec<OrderBookEntry> {
+            levels
+                .iter()
+                .map(|level| OrderBookEntry {
+                    price: (level.price + by).max(Decimal::ZERO).min(Decimal::ONE),
+                    size: level.size * self.settings.depth_fraction,
+                })
+                .collect()
+        };
+        OrderBook {
+            bids: shift(&book.bids, -self.settings.price_move),
+            asks: shift(&book.asks, self.settings.price_move),
+            ..book.clone()
+        }
+    }
+}
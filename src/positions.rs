@@ -0,0 +1,332 @@
+//! Position ledger for every outcome token the bot holds
+//! Updated from fills, merges and redemptions, and reconciled against the account
+
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{info, warn};
+
+use crate::api::{AccountPosition, Side};
+
+/// Differences smaller than this are treated as rounding, not drift
+const RECONCILE_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// Holdings of a single outcome token
+#[derive(Debug, Clone)]
+pub struct TokenPosition {
+    pub token_id: String,
+    pub condition_id: String,
+    pub label: String,
+    pub shares: Decimal,
+    /// USDC paid for the shares still held (average cost basis)
+    pub cost: Decimal,
+}
+
+impl TokenPosition {
+    pub fn avg_price(&self) -> Decimal {
+        if self.shares.is_zero() {
+            Decimal::ZERO
+        } else {
+            self.cost / self.shares
+        }
+    }
+}
+
+/// Matched vs. unmatched holdings of one complete set of outcomes
+#[derive(Debug, Clone)]
+pub struct MarketExposure {
+    /// Market id for binary markets, group id for neg-risk groups
+    pub id: String,
+    /// Markets the held tokens belong to
+    pub condition_ids: Vec<String>,
+    /// Complete sets held (min across every outcome of the set), worth $1 each
+    pub matched: Decimal,
+    /// Shares per outcome beyond the matched sets, exposed to the result
+    pub unmatched: Vec<TokenPosition>,
+    pub cost: Decimal,
+}
+
+impl MarketExposure {
+    /// True when holdings on one side aren't offset by the other
+    pub fn is_unhedged(&self) -> bool {
+        self.unmatched.iter().any(|p| p.shares > RECONCILE_TOLERANCE)
+    }
+}
+
+/// A token whose ledger quantity disagreed with the account
+#[derive(Debug, Clone)]
+pub struct Discrepancy {
+    pub token_id: String,
+    pub label: String,
+    pub ledger: Decimal,
+    pub actual: Decimal,
+}
+
+/// Ledger of token positions keyed by token_id
+#[derive(Debug, Default)]
+pub struct PositionLedger {
+    positions: DashMap<String, TokenPosition>,
+    /// Every outcome of each known complete set, by set id
+    outcome_sets: DashMap<String, Vec<String>>,
+    /// Ids of the sets each token belongs to
+    sets_by_token: DashMap<String, HashSet<String>>,
+}
+
+impl PositionLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a fill: buys add shares and cost, sells reduce both at average cost
+    pub fn record_fill(
+        &self,
+        condition_id: &str,
+        token_id: &str,
+        label: &str,
+        side: Side,
+        shares: Decimal,
+        usdc: Decimal,
+    ) {
+        let mut position = self
+            .positions
+            .entry(token_id.to_string())
+            .or_insert_with(|| TokenPosition {
+                token_id: token_id.to_string(),
+                condition_id: condition_id.to_string(),
+                label: label.to_string(),
+                shares: Decimal::ZERO,
+                cost: Decimal::ZERO,
+            });
+
+        match side {
+            Side::Buy => {
+                position.shares += shares;
+                position.cost += usdc;
+            }
+            Side::Sell => {
+                let sold = shares.min(position.shares);
+                let avg_price = position.avg_price();
+                position.cost -= avg_price * sold;
+                position.shares -= sold;
+                if shares > sold {
+                    warn!(
+                        "Sold {} {} but ledger only held {}",
+                        shares, label, sold
+                    );
+                }
+            }
+        }
+    }
+
+    /// Apply a merge: `sets` shares of every held outcome become USDC
+    pub fn record_merge(&self, condition_id: &str, sets: Decimal) {
+        for mut position in self.positions.iter_mut() {
+            if position.condition_id == condition_id {
+                let merged = sets.min(position.shares);
+                let avg_price = position.avg_price();
+                position.cost -= avg_price * merged;
+                position.shares -= merged;
+            }
+        }
+        self.prune();
+    }
+
+    /// Apply a redemption: every token of the condition has been burned
+    pub fn record_redemption(&self, condition_id: &str) {
+        self.positions.retain(|_, p| p.condition_id != condition_id);
+    }
+
+    /// Register the tokens that together pay out $1 whatever the result
+    ///
+    /// A market is one set of all its outcome tokens; a neg-risk group is one
+    /// set of the YES token of every member. Holdings only count as matched
+    /// across every outcome of a registered set.
+    pub fn register_outcomes(&self, id: &str, token_ids: Vec<String>) {
+        if token_ids.len() < 2 {
+            return;
+        }
+        for token_id in &token_ids {
+            self.sets_by_token
+                .entry(token_id.clone())
+                .or_default()
+                .insert(id.to_string());
+        }
+        self.outcome_sets.insert(id.to_string(), token_ids);
+    }
+
+    /// Shares held of a token (zero if none)
+    pub fn shares(&self, token_id: &str) -> Decimal {
+        self.positions.get(token_id).map(|p| p.shares).unwrap_or_default()
+    }
+
+    /// Matched and unmatched quantities per complete set
+    ///
+    /// Sets are matched across every outcome registered for them, so a set
+    /// with any outcome not held is entirely unmatched. Larger sets (groups)
+    /// are matched before the markets inside them. Tokens outside any
+    /// registered set are reported per market, unmatched.
+    pub fn exposures(&self) -> Vec<MarketExposure> {
+        let held: HashMap<String, TokenPosition> = self
+            .positions
+            .iter()
+            .filter(|p| p.shares > Decimal::ZERO)
+            .map(|p| (p.key().clone(), p.value().clone()))
+            .collect();
+
+        let mut sets: Vec<(String, Vec<String>)> = held
+            .keys()
+            .filter_map(|token_id| self.sets_by_token.get(token_id))
+            .flat_map(|ids| ids.value().iter().cloned().collect::<Vec<_>>())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .filter_map(|id| self.outcome_sets.get(&id).map(|tokens| (id, tokens.value().clone())))
+            .collect();
+        sets.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+        // Shares not yet matched into a set, and the set reporting each token's remainder
+        let mut remaining: HashMap<&str, Decimal> = held.iter().map(|(id, p)| (id.as_str(), p.shares)).collect();
+        let mut owner: HashMap<&str, usize> = HashMap::new();
+        let mut matched = vec![Decimal::ZERO; sets.len()];
+        for (k, (_, tokens)) in sets.iter().enumerate() {
+            matched[k] = tokens
+                .iter()
+                .map(|t| remaining.get(t.as_str()).copied().unwrap_or_default())
+                .min()
+                .unwrap_or_default();
+            for token_id in tokens {
+                if let Some(left) = remaining.get_mut(token_id.as_str()) {
+                    *left -= matched[k];
+                    owner.entry(token_id.as_str()).or_insert(k);
+                }
+            }
+        }
+
+        let mut exposures: Vec<MarketExposure> = Vec::new();
+        for (k, (id, tokens)) in sets.iter().enumerate() {
+            let mut exposure = MarketExposure {
+                id: id.clone(),
+                condition_ids: Vec::new(),
+                matched: matched[k],
+                unmatched: Vec::new(),
+                cost: Decimal::ZERO,
+            };
+            for token_id in tokens {
+                let Some(position) = held.get(token_id) else {
+                    continue;
+                };
+                let mut shares = matched[k];
+                if owner.get(token_id.as_str()) == Some(&k) {
+                    let left = remaining[token_id.as_str()];
+                    if left > Decimal::ZERO {
+                        exposure.unmatched.push(TokenPosition {
+                            shares: left,
+                            cost: position.avg_price() * left,
+                            ..position.clone()
+                        });
+                    }
+                    shares += left;
+                }
+                if shares > Decimal::ZERO {
+                    exposure.cost += position.avg_price() * shares;
+                    if !exposure.condition_ids.contains(&position.condition_id) {
+                        exposure.condition_ids.push(position.condition_id.clone());
+                    }
+                }
+            }
+            if !exposure.matched.is_zero() || !exposure.unmatched.is_empty() {
+                exposures.push(exposure);
+            }
+        }
+
+        // Tokens of markets never registered can't be matched
+        let mut unknown: BTreeMap<String, Vec<TokenPosition>> = BTreeMap::new();
+        for (token_id, position) in &held {
+            if !owner.contains_key(token_id.as_str()) {
+                unknown.entry(position.condition_id.clone()).or_default().push(position.clone());
+            }
+        }
+        for (condition_id, unmatched) in unknown {
+            exposures.push(MarketExposure {
+                id: condition_id.clone(),
+                condition_ids: vec![condition_id],
+                matched: Decimal::ZERO,
+                cost: unmatched.iter().map(|p| p.cost).sum(),
+                unmatched,
+            });
+        }
+
+        exposures
+    }
+
+    /// Markets holding shares not offset by a complementary outcome
+    pub fn unhedged(&self) -> Vec<MarketExposure> {
+        self.exposures().into_iter().filter(|e| e.is_unhedged()).collect()
+    }
+
+    /// Replace ledger quantities with the account's real balances
+    ///
+    /// Tokens the account holds but the ledger doesn't are adopted at their
+    /// reported average price; tokens the ledger holds but the account
+    /// doesn't are dropped. Returns every disagreement beyond rounding.
+    pub fn reconcile(&self, actual: &[AccountPosition]) -> Vec<Discrepancy> {
+        let mut discrepancies = Vec::new();
+        let mut seen = HashSet::new();
+
+        for held in actual.iter().filter(|p| p.size > Decimal::ZERO) {
+            seen.insert(held.asset.clone());
+            let mut position = self
+                .positions
+                .entry(held.asset.clone())
+                .or_insert_with(|| TokenPosition {
+                    token_id: held.asset.clone(),
+                    condition_id: held.condition_id.clone(),
+                    label: held.outcome.clone(),
+                    shares: Decimal::ZERO,
+                    cost: Decimal::ZERO,
+                });
+
+            if (position.shares - held.size).abs() > RECONCILE_TOLERANCE {
+                discrepancies.push(Discrepancy {
+                    token_id: held.asset.clone(),
+                    label: position.label.clone(),
+                    ledger: position.shares,
+                    actual: held.size,
+                });
+                // Trust the account's cost basis when our quantity was wrong
+                position.cost = held.avg_price * held.size;
+            }
+            position.shares = held.size;
+        }
+
+        for position in self.positions.iter() {
+            if !seen.contains(position.key()) && position.shares > RECONCILE_TOLERANCE {
+                discrepancies.push(Discrepancy {
+                    token_id: position.token_id.clone(),
+                    label: position.label.clone(),
+                    ledger: position.shares,
+                    actual: Decimal::ZERO,
+                });
+            }
+        }
+        self.positions.retain(|token_id, _| seen.contains(token_id));
+
+        for d in &discrepancies {
+            warn!(
+                "Position drift on {} ({}): ledger {} vs account {}",
+                d.label, d.token_id, d.ledger, d.actual
+            );
+        }
+        info!(
+            "Reconciled {} positions ({} discrepancies)",
+            self.positions.len(),
+            discrepancies.len()
+        );
+
+        discrepancies
+    }
+
+    /// Drop positions that have been fully closed out
+    fn prune(&self) {
+        self.positions.retain(|_, p| p.shares > Decimal::ZERO);
+    }
+}
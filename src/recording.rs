@@ -0,0 +1,152 @@
+//! Market data recording and replay
+//! Captures raw WebSocket frames and REST order books to a file and plays them back
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tracing::{info, warn};
+
+use crate::api::{Market, OrderBook};
+
+/// What was received at one point in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedData {
+    /// Markets loaded into the scanner on a refresh
+    Markets { markets: Vec<Market> },
+    /// Open member markets of neg-risk groups, looked up before their markets load
+    Groups { members: HashMap<String, Vec<String>> },
+    /// Order books returned by one REST fetch
+    Books { books: Vec<OrderBook> },
+    /// A raw WebSocket text frame, exactly as received
+    Ws { frame: String },
+}
+
+/// One line of a recording file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub data: RecordedData,
+}
+
+/// Appends market data to a JSON-lines session file
+///
+/// Every event is flushed as it is written so a crash loses nothing.
+pub struct Recorder {
+    path: PathBuf,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl Recorder {
+    /// Start a new session file in `dir`, named after the current time
+    pub fn create(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create recording directory {}", dir.display()))?;
+
+        let path = dir.join(format!("session-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S")));
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+
+        info!("Recording market data to {}", path.display());
+        Ok(Self { path, writer: Mutex::new(BufWriter::new(file)) })
+    }
+
+    pub fn record_markets(&self, markets: &[Market]) {
+        self.write(RecordedData::Markets { markets: markets.to_vec() });
+    }
+
+    pub fn record_groups(&self, members: &HashMap<String, Vec<String>>) {
+        if !members.is_empty() {
+            self.write(RecordedData::Groups { members: members.clone() });
+        }
+    }
+
+    pub fn record_books(&self, books: &[OrderBook]) {
+        self.write(RecordedData::Books { books: books.to_vec() });
+    }
+
+    pub fn record_frame(&self, frame: &str) {
+        self.write(RecordedData::Ws { frame: frame.to_string() });
+    }
+
+    /// Append one event, logging rather than failing so the bot carries on
+    fn write(&self, data: RecordedData) {
+        let event = RecordedEvent { at: Utc::now(), data };
+        let mut writer = self.writer.lock();
+        let result = serde_json::to_writer(&mut *writer, &event)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush());
+        if let Err(e) = result {
+            warn!("Failed to record market data to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Reads a recording back, pacing events by their original spacing
+pub struct Replay {
+    lines: Lines<BufReader<tokio::fs::File>>,
+    /// Playback rate: 1.0 is real time, 10.0 ten times faster, 0 as fast as possible
+    speed: f64,
+    last_at: Option<DateTime<Utc>>,
+    line: usize,
+}
+
+impl Replay {
+    pub async fn open(path: impl AsRef<Path>, speed: f64) -> Result<Self> {
+        let path = path.as_ref();
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            speed,
+            last_at: None,
+            line: 0,
+        })
+    }
+
+    /// Next recorded event, after waiting out the gap since the previous one
+    ///
+    /// Returns `None` at the end of the file.
+    pub async fn next(&mut self) -> Result<Option<RecordedEvent>> {
+        loop {
+            let Some(line) = self.lines.next_line().await? else {
+                return Ok(None);
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event: RecordedEvent = match serde_json::from_str(&line) {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Skipping unreadable recording line {}: {}", self.line, e);
+                    continue;
+                }
+            };
+
+            if self.speed > 0.0 {
+                if let Some(last_at) = self.last_at {
+                    if let Ok(gap) = (event.at - last_at).to_std() {
+                        tokio::time::sleep(Duration::from_secs_f64(gap.as_secs_f64() / self.speed)).await;
+                    }
+                }
+            }
+            self.last_at = Some(event.at);
+
+            return Ok(Some(event));
+        }
+    }
+}
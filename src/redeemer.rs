@@ -0,0 +1,256 @@
+//! Background redemption of resolved positions
+//! Tracks outcome tokens held to resolution and redeems the winners for USDC
+
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::api::{Executor, Market};
+
+/// Outcome tokens held in one market
+#[derive(Debug, Clone)]
+pub struct HeldLeg {
+    pub condition_id: String,
+    pub token_id: String,
+    pub label: String,
+    pub shares: Decimal,
+}
+
+/// Tokens bought by one arbitrage (pair or basket) and still held
+#[derive(Debug, Clone)]
+pub struct HeldPosition {
+    /// Market id for pairs, group id for baskets
+    pub id: String,
+    pub description: String,
+    pub legs: Vec<HeldLeg>,
+    /// USDC paid for the legs, including fees
+    pub cost: Decimal,
+    pub opened_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Realized result of redeeming a position after resolution
+#[derive(Debug, Clone)]
+pub struct Redemption {
+    pub id: String,
+    pub description: String,
+    pub payout: Decimal,
+    pub cost: Decimal,
+    pub opened_at: chrono::DateTime<chrono::Utc>,
+    pub tx_hashes: Vec<String>,
+}
+
+impl Redemption {
+    pub fn realized_pnl(&self) -> Decimal {
+        self.payout - self.cost
+    }
+}
+
+/// Registry of held positions, redeemed in the background once resolved
+#[derive(Debug, Default)]
+pub struct Redeemer {
+    positions: DashMap<String, HeldPosition>,
+}
+
+impl Redeemer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking tokens held to resolution
+    ///
+    /// Repeat trades on the same market accumulate into one position.
+    pub fn track(&self, position: HeldPosition) {
+        if position.legs.iter().all(|leg| leg.shares <= Decimal::ZERO) {
+            return;
+        }
+
+        debug!("Tracking {} for redemption ({} legs)", position.id, position.legs.len());
+        match self.positions.get_mut(&position.id) {
+            Some(mut held) => {
+                held.cost += position.cost;
+                for leg in position.legs {
+                    match held.legs.iter_mut().find(|l| l.token_id == leg.token_id) {
+                        Some(existing) => existing.shares += leg.shares,
+                        None => held.legs.push(leg),
+                    }
+                }
+            }
+            None => {
+                self.positions.insert(position.id.clone(), position);
+            }
+        }
+    }
+
+    /// Number of positions awaiting resolution
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Poll for resolutions forever, redeeming positions as their markets settle
+    ///
+    /// `on_redeemed` is called with every completed redemption.
+    pub async fn run(
+        self: Arc<Self>,
+        client: &Executor,
+        interval: Duration,
+        on_redeemed: impl Fn(&Redemption),
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if self.is_empty() {
+                continue;
+            }
+
+            debug!("Checking {} held positions for resolution", self.len());
+            for redemption in self.redeem_resolved(client).await {
+                info!(
+                    "Redeemed {} ({}), held since {}: payout ${} vs cost ${}, realized P&L ${} [{}]",
+                    redemption.description,
+                    redemption.id,
+                    redemption.opened_at.format("%Y-%m-%d %H:%M"),
+                    redemption.payout,
+                    redemption.cost,
+                    redemption.realized_pnl(),
+                    redemption.tx_hashes.join(", ")
+                );
+                on_redeemed(&redemption);
+            }
+        }
+    }
+
+    /// Redeem every position whose markets have all resolved
+    ///
+    /// A position is only removed once all of its redemptions succeed, so
+    /// failures are retried on the next pass.
+    pub async fn redeem_resolved(&self, client: &Executor) -> Vec<Redemption> {
+        let held: Vec<HeldPosition> = self.positions.iter().map(|p| p.value().clone()).collect();
+
+        // Fetch each market once per pass
+        let mut markets: HashMap<String, Option<Market>> = HashMap::new();
+        for position in &held {
+            for leg in &position.legs {
+                if markets.contains_key(&leg.condition_id) {
+                    continue;
+                }
+                let market = match client.get_market(&leg.condition_id).await {
+                    Ok(market) => Some(market),
+                    Err(e) => {
+                        warn!("Resolution check failed: {:#}", e);
+                        None
+                    }
+                };
+                markets.insert(leg.condition_id.clone(), market);
+            }
+        }
+
+        let mut redemptions = Vec::new();
+        for position in held {
+            let resolved = position.legs.iter().all(|leg| {
+                markets
+                    .get(&leg.condition_id)
+                    .and_then(|m| m.as_ref())
+                    .map(is_resolved)
+                    .unwrap_or(false)
+            });
+            if !resolved {
+                continue;
+            }
+
+            match self.redeem_position(client, &position, &markets).await {
+                Ok(redemption) => {
+                    self.release(&position);
+                    redemptions.push(redemption);
+                }
+                Err(e) => error!("Redemption of {} failed, will retry: {:#}", position.id, e),
+            }
+        }
+
+        redemptions
+    }
+
+    /// Take the shares and cost of a redeemed snapshot out of its position
+    ///
+    /// Anything tracked while the redemption was in flight stays behind for
+    /// the next pass.
+    fn release(&self, redeemed: &HeldPosition) {
+        if let Some(mut held) = self.positions.get_mut(&redeemed.id) {
+            held.cost -= redeemed.cost;
+            for leg in &redeemed.legs {
+                if let Some(existing) = held.legs.iter_mut().find(|l| l.token_id == leg.token_id) {
+                    existing.shares -= leg.shares;
+                }
+            }
+            held.legs.retain(|leg| leg.shares > Decimal::ZERO);
+        }
+        self.positions.remove_if(&redeemed.id, |_, held| held.legs.is_empty());
+    }
+
+    /// Redeem the winning legs of a resolved position
+    async fn redeem_position(
+        &self,
+        client: &Executor,
+        position: &HeldPosition,
+        markets: &HashMap<String, Option<Market>>,
+    ) -> anyhow::Result<Redemption> {
+        // Group legs by market, since one redemption covers every slot of a condition
+        let mut by_market: HashMap<&str, Vec<&HeldLeg>> = HashMap::new();
+        for leg in &position.legs {
+            by_market.entry(leg.condition_id.as_str()).or_default().push(leg);
+        }
+
+        let mut payout = Decimal::ZERO;
+        let mut tx_hashes = Vec::new();
+
+        for (condition_id, legs) in by_market {
+            let Some(Some(market)) = markets.get(condition_id) else {
+                anyhow::bail!("Market {} missing from resolution check", condition_id);
+            };
+
+            // Shares held per outcome slot, in the market's token order
+            let mut amounts = vec![Decimal::ZERO; market.tokens.len()];
+            let mut winnings = Decimal::ZERO;
+            for leg in legs {
+                let Some(slot) = market.tokens.iter().position(|t| t.token_id == leg.token_id) else {
+                    warn!("{} not found in market {}", leg.label, condition_id);
+                    continue;
+                };
+                amounts[slot] += leg.shares;
+                if market.tokens[slot].winner {
+                    winnings += leg.shares;
+                }
+            }
+
+            // Losing tokens are worth nothing; skip the gas
+            if winnings.is_zero() {
+                debug!("No winning tokens held in {}", condition_id);
+                continue;
+            }
+
+            let receipt = client.redeem_positions(market, &amounts, winnings).await?;
+            payout += receipt.amount;
+            tx_hashes.push(receipt.tx_hash);
+        }
+
+        Ok(Redemption {
+            id: position.id.clone(),
+            description: position.description.clone(),
+            payout,
+            cost: position.cost,
+            opened_at: position.opened_at,
+            tx_hashes,
+        })
+    }
+}
+
+/// A market is redeemable once it is closed and a winner has been reported
+fn is_resolved(market: &Market) -> bool {
+    market.closed && market.tokens.iter().any(|t| t.winner)
+}
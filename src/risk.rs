@@ -0,0 +1,190 @@
+//! Pre-trade risk limits
+//! Caps capital deployed, per-market and unhedged exposure, trade rate and daily losses
+
+use chrono::{NaiveDate, Utc};
+use parking_lot::Mutex;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::positions::PositionLedger;
+
+/// Window for the trade rate limit
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Configured risk limits (USDC unless noted)
+#[derive(Debug, Clone)]
+pub struct RiskLimits {
+    /// Total cost basis of all held tokens
+    pub max_capital_deployed: Decimal,
+    /// Cost basis held in the markets of a single trade
+    pub max_market_exposure: Decimal,
+    /// Cost basis of shares not offset by a complementary outcome
+    pub max_unhedged_exposure: Decimal,
+    /// Executions allowed per rolling minute
+    pub max_trades_per_minute: u32,
+    /// Realized loss since UTC midnight that halts trading
+    pub max_daily_loss: Decimal,
+}
+
+/// Why a trade was refused
+#[derive(Debug, Error)]
+pub enum RiskRejection {
+    #[error("trading halted: daily realized loss ${0} reached the limit")]
+    Halted(Decimal),
+    #[error("rate limit: {0} trades in the last minute")]
+    RateLimited(u32),
+    #[error("unhedged exposure ${0} exceeds the limit")]
+    Unhedged(Decimal),
+    #[error("no room under the capital limit (${0} deployed)")]
+    CapitalLimit(Decimal),
+    #[error("no room under the per-market limit (${0} in market)")]
+    MarketLimit(Decimal),
+}
+
+/// A trade awaiting risk approval
+#[derive(Debug, Clone)]
+pub struct TradeCheck<'a> {
+    /// Markets the trade touches (one for pairs, every leg for baskets)
+    pub condition_ids: Vec<&'a str>,
+    pub size: Decimal,
+    /// USDC the trade would deploy at `size`, fees included
+    pub cost: Decimal,
+    /// Buys add exposure; sells of held sets only reduce it
+    pub adds_exposure: bool,
+}
+
+#[derive(Debug)]
+struct RiskState {
+    trades: VecDeque<Instant>,
+    day: NaiveDate,
+    realized_today: Decimal,
+    halted: bool,
+}
+
+/// Enforces `RiskLimits` before every execution
+#[derive(Debug)]
+pub struct RiskManager {
+    limits: RiskLimits,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(RiskState {
+                trades: VecDeque::new(),
+                day: Utc::now().date_naive(),
+                realized_today: Decimal::ZERO,
+                halted: false,
+            }),
+        }
+    }
+
+    /// Approve a trade, returning the size allowed under the limits
+    ///
+    /// Capital and per-market limits size the trade down rather than reject
+    /// it outright. Approval doesn't take a rate-limit slot; call
+    /// `record_trade` once orders are actually submitted.
+    pub fn approve(&self, ledger: &PositionLedger, trade: &TradeCheck) -> Result<Decimal, RiskRejection> {
+        let mut state = self.state.lock();
+        self.roll_day(&mut state);
+
+        if state.halted {
+            return Err(RiskRejection::Halted(-state.realized_today));
+        }
+
+        let now = Instant::now();
+        while state.trades.front().is_some_and(|t| now.duration_since(*t) > RATE_WINDOW) {
+            state.trades.pop_front();
+        }
+        let recent = state.trades.len() as u32;
+        if recent >= self.limits.max_trades_per_minute {
+            return Err(RiskRejection::RateLimited(recent));
+        }
+
+        let mut size = trade.size;
+        if trade.adds_exposure {
+            let exposures = ledger.exposures();
+
+            let unhedged: Decimal = exposures
+                .iter()
+                .flat_map(|e| &e.unmatched)
+                .map(|p| p.cost)
+                .sum();
+            if unhedged > self.limits.max_unhedged_exposure {
+                return Err(RiskRejection::Unhedged(unhedged));
+            }
+
+            let deployed: Decimal = exposures.iter().map(|e| e.cost).sum();
+            let in_market: Decimal = exposures
+                .iter()
+                .filter(|e| e.condition_ids.iter().any(|id| trade.condition_ids.contains(&id.as_str())))
+                .map(|e| e.cost)
+                .sum();
+
+            let capital_room = self.limits.max_capital_deployed - deployed;
+            if capital_room <= Decimal::ZERO {
+                return Err(RiskRejection::CapitalLimit(deployed));
+            }
+            let market_room = self.limits.max_market_exposure - in_market;
+            if market_room <= Decimal::ZERO {
+                return Err(RiskRejection::MarketLimit(in_market));
+            }
+
+            size = scale_to_budget(size, trade.cost, capital_room.min(market_room));
+        }
+
+        Ok(size)
+    }
+
+    /// Count a submitted trade toward the rate limit
+    pub fn record_trade(&self) {
+        self.state.lock().trades.push_back(Instant::now());
+    }
+
+    /// Add realized P&L (recoveries, merges, redemptions), halting on the daily limit
+    pub fn record_pnl(&self, pnl: Decimal) {
+        let mut state = self.state.lock();
+        self.roll_day(&mut state);
+
+        state.realized_today += pnl;
+        if !state.halted && state.realized_today <= -self.limits.max_daily_loss {
+            state.halted = true;
+            error!(
+                "DAILY LOSS LIMIT HIT: realized ${} today - trading halted until UTC midnight",
+                state.realized_today
+            );
+        }
+    }
+
+    /// Reset the daily counters when the UTC date changes
+    fn roll_day(&self, state: &mut RiskState) {
+        let today = Utc::now().date_naive();
+        if state.day != today {
+            if state.halted {
+                info!("New trading day, lifting daily loss halt");
+            }
+            state.day = today;
+            state.realized_today = Decimal::ZERO;
+            state.halted = false;
+        }
+    }
+}
+
+/// Scale `size` down so its cost fits within `available` USDC, to 0.01 shares
+///
+/// Average fill price only rises with size, so scaling by available/needed
+/// always fits.
+pub fn scale_to_budget(size: Decimal, needed: Decimal, available: Decimal) -> Decimal {
+    if needed <= available {
+        return size;
+    }
+    if needed.is_zero() || available <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (size * available / needed).round_dp_with_strategy(2, RoundingStrategy::ToZero)
+}
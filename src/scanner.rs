@@ -1,104 +1,348 @@
 //! Arbitrage opportunity scanner
-//! Detects when YES + NO prices sum to less than $1
+//! Detects when YES + NO asks sum to less than $1 (or bids to more than $1),
+//! and when every outcome of a multi-outcome group can be bought for under $1
 
 use anyhow::Result;
-use dashmap::DashMap;
-use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use futures_util::{stream, StreamExt};
+use parking_lot::RwLock;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
-use crate::api::{ClobClient, Market, OrderBook, ArbitrageOpportunity};
+use crate::api::{
+    Executor, Market, OrderBook, OrderBookEntry, ArbDirection, ArbitrageOpportunity,
+    BasketLeg, BasketLevel, BasketOpportunity, DepthLevel,
+};
 use crate::config::Config;
+use crate::discovery::MarketDiscovery;
+use crate::market_filter::parse_end_date;
+use crate::fees::CostModel;
+use crate::orderbook::OrderBookStore;
+
+/// Market lookups in flight at once while rechecking the cache
+const MARKET_LOOKUP_CONCURRENCY: usize = 8;
 
 /// Scanner for detecting arbitrage opportunities
+///
+/// Fetches markets and order books through the client and hands them to the
+/// `ArbitrageDetector`.
 pub struct ArbitrageScanner {
-    client: ClobClient,
+    client: Executor,
+    detector: ArbitrageDetector,
+    /// Scheduled up/down markets, found outside the listing
+    discovery: MarketDiscovery,
+    /// When each market was last seen open, by the listing or a lookup
+    confirmed_at: DashMap<String, Instant>,
+}
+
+/// Arbitrage detection over a cached market universe
+///
+/// Needs no client: every check runs against supplied order books, so the
+/// same logic serves live scanning, replays and backtests.
+pub struct ArbitrageDetector {
     config: Config,
     /// Cache of market data: condition_id -> (yes_token_id, no_token_id)
     market_cache: Arc<DashMap<String, MarketPair>>,
+    /// Reverse index: token_id -> condition_id (for WebSocket price lookups)
+    token_index: Arc<DashMap<String, String>>,
+    /// Cache of multi-outcome groups: group_id -> outcomes
+    group_cache: Arc<DashMap<String, MarketGroup>>,
+    /// Reverse index: token_id -> group_id
+    group_index: Arc<DashMap<String, String>>,
+    /// Condition ids of closed or ended markets (and their groups), evicted
+    /// for good so stale listings can't bring them back
+    evicted: Arc<DashSet<String>>,
+    /// Every open member market of each neg-risk group, by `neg_risk_market_id`
+    group_members: Arc<DashMap<String, Vec<String>>>,
+    /// Time cutoffs are checked against; the wall clock unless replaying
+    clock: Arc<RwLock<Option<DateTime<Utc>>>>,
 }
 
 /// Cached market pair info
+///
+/// Any two complementary outcomes form a pair. For YES/NO markets the `yes_*`
+/// side is YES; for other labels (e.g. "Up"/"Down") it is the first outcome.
 #[derive(Debug, Clone)]
 pub struct MarketPair {
     pub condition_id: String,
     pub yes_token_id: String,
     pub no_token_id: String,
+    /// Merges and redemptions go through the neg-risk adapter
+    pub neg_risk: bool,
+    /// Original outcome label of the `yes_*` side
+    pub yes_outcome: String,
+    /// Original outcome label of the `no_*` side
+    pub no_outcome: String,
+    pub description: String,
+    /// Taker fee rate in basis points
+    pub fee_rate_bps: Decimal,
+    /// Minimum price increment
+    pub tick_size: Decimal,
+    /// When the market ends
+    pub end_time: Option<DateTime<Utc>>,
+    /// When the game starts, for sports markets
+    pub game_start_time: Option<DateTime<Utc>>,
+}
+
+impl MarketPair {
+    /// When the market stops being worth trading: its end, or the game start
+    /// for sports markets, whose books the exchange clears when play begins
+    pub fn closes_at(&self) -> Option<DateTime<Utc>> {
+        earliest(self.end_time, self.game_start_time)
+    }
+}
+
+/// Cached multi-outcome group where exactly one outcome pays out $1
+///
+/// Either a single market with more than two outcome tokens, or the YES
+/// tokens of every neg-risk market sharing a `neg_risk_market_id`. Neg-risk
+/// groups are only cached once every open member market is known to be in
+/// the universe (see `ArbitrageDetector::set_group_members`).
+#[derive(Debug, Clone)]
+pub struct MarketGroup {
+    pub group_id: String,
     pub description: String,
+    pub outcomes: Vec<GroupOutcome>,
+    /// Taker fee rate in basis points
+    pub fee_rate_bps: Decimal,
+    /// Minimum price increment
+    pub tick_size: Decimal,
+    /// Earliest close of any member market (see `MarketPair::closes_at`)
+    pub closes_at: Option<DateTime<Utc>>,
+    /// Neg-risk groups trade through the neg-risk exchange
+    pub neg_risk: bool,
+}
+
+/// One outcome of a multi-outcome group
+#[derive(Debug, Clone)]
+pub struct GroupOutcome {
+    pub condition_id: String,
+    pub token_id: String,
+    pub label: String,
 }
 
 impl ArbitrageScanner {
-    pub fn new(client: ClobClient, config: Config) -> Self {
+    pub fn new(client: Executor, config: Config) -> Self {
+        let discovery = MarketDiscovery::new(
+            config.updown_series(),
+            chrono::Duration::seconds(config.discovery_lookahead_secs as i64),
+        );
         Self {
             client,
-            config,
-            market_cache: Arc::new(DashMap::new()),
+            detector: ArbitrageDetector::new(config),
+            discovery,
+            confirmed_at: DashMap::new(),
         }
     }
 
-    /// Refresh the market cache
+    /// Refresh the market cache, evicting markets that can no longer be traded
     pub async fn refresh_markets(&self) -> Result<usize> {
         info!("Refreshing market cache...");
 
-        let markets = if self.config.crypto_only {
-            self.client.get_crypto_markets().await?
-        } else {
-            self.client.get_markets().await?
-        };
+        let listed = self.client.get_markets().await?;
+        let listed_count = listed.len();
+        let confirmed = self.client.confirmed_market_ids();
 
-        let mut count = 0;
-        for market in markets.into_iter().take(self.config.max_markets) {
-            if let Some(pair) = Self::extract_market_pair(&market) {
-                self.market_cache.insert(pair.condition_id.clone(), pair);
-                count += 1;
-            }
+        let filter = &self.detector.config().market_filter;
+        let mut markets = filter.apply(listed, Utc::now());
+        if filter.needs_stats() && !markets.is_empty() {
+            let ids: Vec<String> = markets.iter().map(|m| m.condition_id.clone()).collect();
+            let stats = self.client.get_market_stats(&ids).await?;
+            markets = filter.apply_stats(markets, &stats);
         }
+        info!("{} of {} listed markets pass the market filter", markets.len(), listed_count);
 
-        info!("Cached {} active markets", count);
+        let max_markets = self.detector.config().max_markets;
+        if markets.len() > max_markets {
+            info!("Scanning the first {} of {} markets (MAX_MARKETS)", max_markets, markets.len());
+        }
+        let markets: Vec<Market> = markets.into_iter().take(max_markets).collect();
+        let (markets, removed) = self.recheck_markets(markets, &confirmed).await;
+        if removed > 0 {
+            info!("Removed {} closed, paused or expiring markets", removed);
+        }
+        self.load_group_members(&markets).await;
+        if let Some(recorder) = self.client.recorder() {
+            recorder.record_markets(&markets);
+        }
+
+        let count = self.detector.load_markets(&markets);
+        self.register_outcome_sets(&markets);
         Ok(count)
     }
 
-    /// Extract YES/NO token IDs from a market
-    fn extract_market_pair(market: &Market) -> Option<MarketPair> {
-        if market.tokens.len() != 2 {
-            return None;
+    /// Look up every cached or about-to-load market the listing didn't just
+    /// confirm, since the listing only carries markets accepting orders and
+    /// incremental refreshes keep old copies of earlier pages
+    ///
+    /// Markets seen open within `MARKET_RECHECK_SECS` are trusted without a
+    /// lookup, and at most `MARKET_LOOKUP_CONCURRENCY` lookups run at once.
+    ///
+    /// Markets past their expiry cutoff, and markets found closed or past
+    /// their end, are evicted for good. Markets that stopped accepting orders
+    /// are suspended until a later refresh finds them accepting again. Returns
+    /// `markets` with stale copies replaced by the live ones, and how many
+    /// pairs and groups were removed.
+    async fn recheck_markets(&self, markets: Vec<Market>, confirmed: &HashSet<String>) -> (Vec<Market>, usize) {
+        let mut removed = self.detector.evict_expired();
+
+        let now = Instant::now();
+        let recheck_after = Duration::from_secs(self.detector.config().market_recheck_secs);
+        self.confirmed_at.retain(|_, at| now.duration_since(*at) < recheck_after);
+        for id in confirmed {
+            self.confirmed_at.insert(id.clone(), now);
         }
 
-        let mut yes_token = None;
-        let mut no_token = None;
+        let unconfirmed: HashSet<String> = self
+            .detector
+            .condition_ids()
+            .into_iter()
+            .chain(markets.iter().map(|m| m.condition_id.clone()))
+            .filter(|id| !self.confirmed_at.contains_key(id))
+            .collect();
+        if unconfirmed.is_empty() {
+            return (markets, removed);
+        }
 
-        for token in &market.tokens {
-            match token.outcome.to_lowercase().as_str() {
-                "yes" => yes_token = Some(token.token_id.clone()),
-                "no" => no_token = Some(token.token_id.clone()),
-                _ => {}
+        debug!("Checking {} markets the listing didn't confirm", unconfirmed.len());
+        let lookups: Vec<(String, Result<Market>)> = stream::iter(unconfirmed)
+            .map(|id| async move {
+                let lookup = self.client.get_market(&id).await;
+                (id, lookup)
+            })
+            .buffer_unordered(MARKET_LOOKUP_CONCURRENCY)
+            .collect()
+            .await;
+        let mut live: HashMap<String, Market> = HashMap::new();
+        for (id, lookup) in lookups {
+            match lookup {
+                Ok(market) => {
+                    live.insert(id, market);
+                }
+                Err(e) => debug!("{:#}", e),
             }
         }
 
-        match (yes_token, no_token) {
-            (Some(yes_id), Some(no_id)) => Some(MarketPair {
-                condition_id: market.condition_id.clone(),
-                yes_token_id: yes_id,
-                no_token_id: no_id,
-                description: market.question.clone().unwrap_or_else(|| "Unknown".to_string()),
-            }),
-            _ => None,
+        let (mut ended, mut paused) = (Vec::new(), Vec::new());
+        for market in live.values() {
+            if market.closed || self.detector.is_past_cutoff(market_closes_at(market)) {
+                ended.push(market.condition_id.clone());
+            } else if !market.accepting_orders {
+                paused.push(market.condition_id.clone());
+            } else {
+                self.confirmed_at.insert(market.condition_id.clone(), now);
+            }
         }
+        removed += self.detector.evict_markets(&ended);
+        removed += self.detector.suspend_markets(&paused);
+
+        let markets = markets
+            .into_iter()
+            .map(|m| live.remove(&m.condition_id).unwrap_or(m))
+            .collect();
+        (markets, removed)
     }
 
-    /// Scan all cached markets for arbitrage opportunities
-    pub async fn scan_opportunities(&self) -> Result<Vec<ArbitrageOpportunity>> {
-        let mut opportunities = Vec::new();
+    /// Add the markets of current and upcoming up/down windows as they open
+    ///
+    /// The series are opted into explicitly, so their markets skip the market
+    /// filter and `MAX_MARKETS`, except for the allow and deny lists. Returns
+    /// how many markets were added.
+    pub async fn discover_markets(&self) -> Result<usize> {
+        if !self.discovery.is_enabled() {
+            return Ok(0);
+        }
 
-        // Collect all token IDs for batch request
-        let pairs: Vec<MarketPair> = self.market_cache
+        let filter = &self.detector.config().market_filter;
+        let (markets, denied): (Vec<Market>, Vec<Market>) = self
+            .discovery
+            .discover(&self.client, Utc::now())
+            .await?
+            .into_iter()
+            .partition(|m| filter.permits(m));
+        for market in &denied {
+            debug!(
+                "Skipping discovered {}: not allowed by MARKET_ALLOW/MARKET_DENY",
+                market.market_slug.as_deref().unwrap_or(&market.condition_id)
+            );
+        }
+        if markets.is_empty() {
+            return Ok(0);
+        }
+        for market in &markets {
+            info!(
+                "Discovered {} ({})",
+                market.question.as_deref().unwrap_or("Unknown"),
+                market.market_slug.as_deref().unwrap_or_default()
+            );
+        }
+        self.load_group_members(&markets).await;
+        if let Some(recorder) = self.client.recorder() {
+            recorder.record_markets(&markets);
+        }
+
+        let count = self.detector.load_markets(&markets);
+        self.register_outcome_sets(&markets);
+        Ok(count)
+    }
+
+    /// Look up every member of the neg-risk groups among `markets`, so groups
+    /// missing a member (filtered out, past `MAX_MARKETS` or unlisted) are
+    /// left out when the markets load
+    async fn load_group_members(&self, markets: &[Market]) {
+        if !self.detector.config().enable_multi_outcome {
+            return;
+        }
+        let group_ids: Vec<String> = markets
             .iter()
-            .map(|entry| entry.value().clone())
+            .filter(|m| m.neg_risk)
+            .filter_map(|m| m.neg_risk_market_id.clone())
+            .filter(|id| !id.is_empty())
+            .collect::<HashSet<String>>()
+            .into_iter()
             .collect();
 
+        match self.client.get_group_members(&group_ids).await {
+            Ok(members) => {
+                if let Some(recorder) = self.client.recorder() {
+                    recorder.record_groups(&members);
+                }
+                self.detector.set_group_members(members);
+            }
+            Err(e) => warn!("Failed to fetch group members, their groups won't load: {:#}", e),
+        }
+    }
+
+    /// Tell the position ledger which tokens make up a complete set: every
+    /// outcome of each market, and the YES side of every member of a group
+    fn register_outcome_sets(&self, markets: &[Market]) {
+        let ledger = self.client.positions();
+        for market in markets {
+            ledger.register_outcomes(
+                &market.condition_id,
+                market.tokens.iter().map(|t| t.token_id.clone()).collect(),
+            );
+        }
+        for group in self.detector.groups() {
+            ledger.register_outcomes(
+                &group.group_id,
+                group.outcomes.iter().map(|o| o.token_id.clone()).collect(),
+            );
+        }
+    }
+
+    /// Scan all cached markets for arbitrage opportunities
+    pub async fn scan_opportunities(&self) -> Result<Vec<ArbitrageOpportunity>> {
+        // Collect all token IDs for batch request
+        let pairs = self.detector.pairs();
+
         if pairs.is_empty() {
             warn!("No markets in cache - call refresh_markets first");
-            return Ok(opportunities);
+            return Ok(Vec::new());
         }
 
         info!("Scanning {} markets...", pairs.len());
@@ -114,12 +358,385 @@ impl ArbitrageScanner {
         // Log some sample token IDs for debugging
         if let Some(first_pair) = pairs.first() {
             info!("Sample market: {}", first_pair.description);
-            info!("  YES token: {}", first_pair.yes_token_id);
-            info!("  NO token: {}", first_pair.no_token_id);
+            info!("  {} token: {}", first_pair.yes_outcome, first_pair.yes_token_id);
+            info!("  {} token: {}", first_pair.no_outcome, first_pair.no_token_id);
         }
         let order_books = self.client.get_order_books(&all_token_ids).await?;
         info!("Got {} order books", order_books.len());
 
+        Ok(self.detector.scan_books(&order_books))
+    }
+
+    /// Scan all cached multi-outcome groups for basket opportunities
+    pub async fn scan_baskets(&self) -> Result<Vec<BasketOpportunity>> {
+        let groups = self.detector.groups();
+
+        if groups.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let token_ids: Vec<String> = groups
+            .iter()
+            .flat_map(|g| g.outcomes.iter().map(|o| o.token_id.clone()))
+            .collect();
+
+        info!("Fetching {} order books for {} groups...", token_ids.len(), groups.len());
+        let order_books = self.client.get_order_books(&token_ids).await?;
+
+        Ok(self.detector.scan_basket_books(&order_books))
+    }
+
+    /// Scan a single market by ID (for WebSocket-triggered checks)
+    pub async fn scan_market(&self, condition_id: &str) -> Result<Option<ArbitrageOpportunity>> {
+        let pair = match self.detector.pair(condition_id) {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        let token_ids = vec![pair.yes_token_id.clone(), pair.no_token_id.clone()];
+        let order_books = self.client.get_order_books(&token_ids).await?;
+
+        let book = |token_id: &str| order_books.iter().find(|ob| ob.asset_id == token_id);
+
+        Ok(match (book(&pair.yes_token_id), book(&pair.no_token_id)) {
+            (Some(yes_book), Some(no_book)) => self.detector.check_books(&pair, yes_book, no_book),
+            _ => None,
+        })
+    }
+
+    /// Get reference to the client
+    pub fn client(&self) -> &Executor {
+        &self.client
+    }
+
+    /// Scheduled discovery of up/down markets
+    pub fn discovery(&self) -> &MarketDiscovery {
+        &self.discovery
+    }
+
+    /// Client-free detection over the cached markets
+    pub fn detector(&self) -> &ArbitrageDetector {
+        &self.detector
+    }
+
+    /// Get reference to config
+    pub fn config(&self) -> &Config {
+        self.detector.config()
+    }
+}
+
+impl ArbitrageDetector {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            market_cache: Arc::new(DashMap::new()),
+            token_index: Arc::new(DashMap::new()),
+            group_cache: Arc::new(DashMap::new()),
+            group_index: Arc::new(DashMap::new()),
+            evicted: Arc::new(DashSet::new()),
+            group_members: Arc::new(DashMap::new()),
+            clock: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Check cutoffs as of `at` rather than the wall clock, for replays and backtests
+    pub fn set_time(&self, at: DateTime<Utc>) {
+        *self.clock.write() = Some(at);
+    }
+
+    /// Time cutoffs are checked against
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.read().unwrap_or_else(Utc::now)
+    }
+
+    /// Whether a market closing at `closes_at` is within `EXPIRY_CUTOFF_SECS`
+    /// of closing, or past it; markets without a close time never are
+    pub fn is_past_cutoff(&self, closes_at: Option<DateTime<Utc>>) -> bool {
+        let cutoff = chrono::Duration::seconds(self.config.expiry_cutoff_secs as i64);
+        closes_at.is_some_and(|close| self.now() >= close - cutoff)
+    }
+
+    /// Record the open member markets of neg-risk groups, by `neg_risk_market_id`
+    ///
+    /// Neg-risk groups whose members are unknown, or with a member missing
+    /// from the markets loaded, aren't complete sets and are never cached.
+    pub fn set_group_members(&self, members: HashMap<String, Vec<String>>) {
+        for (group_id, condition_ids) in members {
+            self.group_members.insert(group_id, condition_ids);
+        }
+    }
+
+    /// Cache pairs and groups from a market list (fetched or replayed)
+    ///
+    /// Evicted markets, markets that are closed or not accepting orders and
+    /// markets past their expiry cutoff are left out, and so is any group
+    /// with such a member, since the rest no longer make a complete set.
+    pub fn load_markets(&self, markets: &[Market]) -> usize {
+        let tradable = |m: &Market| {
+            !self.evicted.contains(&m.condition_id)
+                && !m.closed
+                && m.accepting_orders
+                && !self.is_past_cutoff(market_closes_at(m))
+        };
+        let untradable: HashSet<&str> = markets
+            .iter()
+            .filter(|m| !tradable(m))
+            .map(|m| m.condition_id.as_str())
+            .collect();
+
+        let mut count = 0;
+        for market in markets.iter().filter(|m| !untradable.contains(m.condition_id.as_str())) {
+            if let Some(pair) = Self::extract_market_pair(market) {
+                self.token_index.insert(pair.yes_token_id.clone(), pair.condition_id.clone());
+                self.token_index.insert(pair.no_token_id.clone(), pair.condition_id.clone());
+                self.market_cache.insert(pair.condition_id.clone(), pair);
+                count += 1;
+            }
+        }
+
+        if self.config.enable_multi_outcome {
+            let groups: Vec<MarketGroup> = self.extract_market_groups(markets)
+                .into_iter()
+                .filter(|g| {
+                    !self.evicted.contains(&g.group_id)
+                        && !g.outcomes.iter().any(|o| untradable.contains(o.condition_id.as_str()))
+                })
+                .collect();
+            info!("Cached {} multi-outcome groups", groups.len());
+            for group in groups {
+                for outcome in &group.outcomes {
+                    self.group_index.insert(outcome.token_id.clone(), group.group_id.clone());
+                }
+                self.group_cache.insert(group.group_id.clone(), group);
+            }
+        }
+
+        info!("Cached {} active markets", count);
+        count
+    }
+
+    /// Drop closed or ended markets from the cache for good, along with any
+    /// group they belong to
+    ///
+    /// Returns how many pairs and groups were dropped.
+    pub fn evict_markets(&self, condition_ids: &[String]) -> usize {
+        self.remove_markets(condition_ids, true)
+    }
+
+    /// Drop markets that stopped accepting orders, along with any group they
+    /// belong to, until a later load finds them accepting orders again
+    ///
+    /// Returns how many pairs and groups were dropped.
+    pub fn suspend_markets(&self, condition_ids: &[String]) -> usize {
+        self.remove_markets(condition_ids, false)
+    }
+
+    fn remove_markets(&self, condition_ids: &[String], permanent: bool) -> usize {
+        let action = if permanent { "Evicted" } else { "Suspended" };
+        let mut removed = 0;
+        for condition_id in condition_ids {
+            if permanent {
+                self.evicted.insert(condition_id.clone());
+            }
+            if let Some((_, pair)) = self.market_cache.remove(condition_id) {
+                self.token_index.remove(&pair.yes_token_id);
+                self.token_index.remove(&pair.no_token_id);
+                info!("{} {}", action, pair.description);
+                removed += 1;
+            }
+        }
+
+        let groups: Vec<String> = self
+            .group_cache
+            .iter()
+            .filter(|g| g.outcomes.iter().any(|o| condition_ids.contains(&o.condition_id)))
+            .map(|g| g.group_id.clone())
+            .collect();
+        for group_id in groups {
+            removed += self.remove_group(&group_id, permanent);
+        }
+
+        removed
+    }
+
+    /// Evict every pair and group past its expiry cutoff, returning how many were dropped
+    pub fn evict_expired(&self) -> usize {
+        let expired: Vec<String> = self
+            .market_cache
+            .iter()
+            .filter(|p| self.is_past_cutoff(p.closes_at()))
+            .map(|p| p.condition_id.clone())
+            .collect();
+        let mut evicted = self.evict_markets(&expired);
+
+        let groups: Vec<String> = self
+            .group_cache
+            .iter()
+            .filter(|g| self.is_past_cutoff(g.closes_at))
+            .map(|g| g.group_id.clone())
+            .collect();
+        for group_id in groups {
+            evicted += self.remove_group(&group_id, true);
+        }
+
+        evicted
+    }
+
+    /// Drop a group and its token index entries, for good if `permanent`
+    fn remove_group(&self, group_id: &str, permanent: bool) -> usize {
+        let Some((_, group)) = self.group_cache.remove(group_id) else {
+            return 0;
+        };
+        for outcome in &group.outcomes {
+            self.group_index.remove(&outcome.token_id);
+        }
+        if permanent {
+            self.evicted.insert(group.group_id.clone());
+            info!("Evicted group {}", group.description);
+        } else {
+            info!("Suspended group {}", group.description);
+        }
+        1
+    }
+
+    /// Condition ids of every cached pair and group member
+    pub fn condition_ids(&self) -> HashSet<String> {
+        let mut ids: HashSet<String> = self.market_cache.iter().map(|p| p.key().clone()).collect();
+        for group in self.group_cache.iter() {
+            ids.extend(group.outcomes.iter().map(|o| o.condition_id.clone()));
+        }
+        ids
+    }
+
+    /// Build multi-outcome groups from markets
+    ///
+    /// Markets with more than two tokens form a group on their own; binary
+    /// neg-risk markets are grouped by `neg_risk_market_id` using their YES
+    /// tokens, as long as every known member is among `markets`.
+    fn extract_market_groups(&self, markets: &[Market]) -> Vec<MarketGroup> {
+        let mut groups: Vec<MarketGroup> = Vec::new();
+        let mut neg_risk: HashMap<String, MarketGroup> = HashMap::new();
+
+        for market in markets {
+            let question = market.question.clone().unwrap_or_else(|| "Unknown".to_string());
+
+            if market.tokens.len() > 2 {
+                groups.push(MarketGroup {
+                    group_id: market.condition_id.clone(),
+                    description: question,
+                    outcomes: market.tokens.iter().map(|t| GroupOutcome {
+                        condition_id: market.condition_id.clone(),
+                        token_id: t.token_id.clone(),
+                        label: t.outcome.clone(),
+                    }).collect(),
+                    fee_rate_bps: market.taker_base_fee,
+                    tick_size: market.minimum_tick_size,
+                    closes_at: market_closes_at(market),
+                    neg_risk: market.neg_risk,
+                });
+                continue;
+            }
+
+            let group_id = match (&market.neg_risk_market_id, market.neg_risk) {
+                (Some(id), true) if !id.is_empty() => id,
+                _ => continue,
+            };
+            let yes_token = match market.tokens.iter().find(|t| t.outcome.eq_ignore_ascii_case("yes")) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            let group = neg_risk.entry(group_id.clone()).or_insert_with(|| MarketGroup {
+                group_id: group_id.clone(),
+                description: String::new(),
+                outcomes: Vec::new(),
+                fee_rate_bps: market.taker_base_fee,
+                tick_size: market.minimum_tick_size,
+                closes_at: None,
+                neg_risk: true,
+            });
+            // Members can differ; be conservative with the group-wide cost model and close
+            group.fee_rate_bps = group.fee_rate_bps.max(market.taker_base_fee);
+            group.tick_size = group.tick_size.max(market.minimum_tick_size);
+            group.closes_at = earliest(group.closes_at, market_closes_at(market));
+            group.outcomes.push(GroupOutcome {
+                condition_id: market.condition_id.clone(),
+                token_id: yes_token.token_id.clone(),
+                label: question,
+            });
+        }
+
+        for (_, mut group) in neg_risk {
+            if group.outcomes.len() < 2 {
+                continue;
+            }
+            let missing = match self.group_members.get(&group.group_id) {
+                Some(members) => members
+                    .iter()
+                    .filter(|id| !group.outcomes.iter().any(|o| &o.condition_id == *id))
+                    .count(),
+                None => {
+                    debug!("Skipping group {}: members unknown", group.group_id);
+                    continue;
+                }
+            };
+            if missing > 0 {
+                debug!(
+                    "Skipping group {}: {} of its markets aren't loaded",
+                    group.group_id, missing
+                );
+                continue;
+            }
+            group.description = format!(
+                "{} (+{} more)",
+                group.outcomes[0].label,
+                group.outcomes.len() - 1
+            );
+            groups.push(group);
+        }
+
+        groups
+    }
+
+    /// Extract the two complementary outcome tokens from a binary market
+    fn extract_market_pair(market: &Market) -> Option<MarketPair> {
+        if market.tokens.len() != 2 {
+            return None;
+        }
+
+        // Put YES first for yes/no markets; otherwise keep the API's outcome order
+        let (yes_token, no_token) = match (&market.tokens[0], &market.tokens[1]) {
+            (first, second) if second.outcome.eq_ignore_ascii_case("yes")
+                || first.outcome.eq_ignore_ascii_case("no") => (second, first),
+            (first, second) => (first, second),
+        };
+
+        if yes_token.token_id.is_empty()
+            || no_token.token_id.is_empty()
+            || yes_token.token_id == no_token.token_id
+        {
+            return None;
+        }
+
+        Some(MarketPair {
+            condition_id: market.condition_id.clone(),
+            yes_token_id: yes_token.token_id.clone(),
+            no_token_id: no_token.token_id.clone(),
+            neg_risk: market.neg_risk,
+            yes_outcome: yes_token.outcome.clone(),
+            no_outcome: no_token.outcome.clone(),
+            description: market.question.clone().unwrap_or_else(|| "Unknown".to_string()),
+            fee_rate_bps: market.taker_base_fee,
+            tick_size: market.minimum_tick_size,
+            end_time: market.end_date_iso.as_deref().and_then(parse_end_date),
+            game_start_time: market.game_start_time.as_deref().and_then(parse_end_date),
+        })
+    }
+
+    /// Check every cached pair against a set of order books (fetched or replayed)
+    ///
+    /// Pairs missing either book are skipped.
+    pub fn scan_books(&self, order_books: &[OrderBook]) -> Vec<ArbitrageOpportunity> {
         // Create a map of token_id -> order_book for fast lookup
         let book_map: std::collections::HashMap<String, &OrderBook> = order_books
             .iter()
@@ -127,17 +744,16 @@ impl ArbitrageScanner {
             .collect();
 
         // Check each market pair
-        for pair in &pairs {
-            if let Some(opp) = self.check_arbitrage(&pair, &book_map) {
-                opportunities.push(opp);
-            }
-        }
+        let opportunities: Vec<ArbitrageOpportunity> = self.market_cache
+            .iter()
+            .filter_map(|pair| self.check_arbitrage(pair.value(), &book_map))
+            .collect();
 
         if !opportunities.is_empty() {
             info!("Found {} arbitrage opportunities!", opportunities.len());
         }
 
-        Ok(opportunities)
+        opportunities
     }
 
     /// Check a single market for arbitrage opportunity
@@ -146,116 +762,427 @@ impl ArbitrageScanner {
         pair: &MarketPair,
         book_map: &std::collections::HashMap<String, &OrderBook>,
     ) -> Option<ArbitrageOpportunity> {
+        if self.is_past_cutoff(pair.closes_at()) {
+            debug!("Too close to expiry to trade: {}", pair.description);
+            return None;
+        }
+
         let yes_book = match book_map.get(&pair.yes_token_id) {
             Some(b) => b,
             None => {
-                debug!("No YES order book for: {}", &pair.description[..pair.description.len().min(30)]);
+                debug!("No {} order book for: {}", pair.yes_outcome, &pair.description[..pair.description.len().min(30)]);
                 return None;
             }
         };
         let no_book = match book_map.get(&pair.no_token_id) {
             Some(b) => b,
             None => {
-                debug!("No NO order book for: {}", &pair.description[..pair.description.len().min(30)]);
+                debug!("No {} order book for: {}", pair.no_outcome, &pair.description[..pair.description.len().min(30)]);
                 return None;
             }
         };
 
-        // Get best ask prices (cheapest to buy)
-        let yes_ask = match yes_book.asks.first() {
-            Some(a) => a,
-            None => {
-                info!("No YES asks for: {} (bids: {})", &pair.description[..pair.description.len().min(30)], yes_book.bids.len());
-                return None;
-            }
+        let costs = CostModel {
+            fee_rate_bps: pair.fee_rate_bps,
+            tick_size: pair.tick_size,
+            fixed_cost: self.config.fixed_cost_per_trade,
         };
-        let no_ask = match no_book.asks.first() {
-            Some(a) => a,
-            None => {
-                info!("No NO asks for: {} (bids: {})", &pair.description[..pair.description.len().min(30)], no_book.bids.len());
+
+        // Asks can only sum below $1 when bids do too, so at most one side can fire
+        let buy = self.check_side(pair, ArbDirection::BuyBoth, yes_book, no_book, costs);
+        if buy.is_some() || !self.config.enable_sell_side {
+            return buy;
+        }
+        self.check_side(pair, ArbDirection::SellBoth, yes_book, no_book, costs)
+    }
+
+    /// Check one direction of a market: buying both asks or selling into both bids
+    fn check_side(
+        &self,
+        pair: &MarketPair,
+        direction: ArbDirection,
+        yes_book: &OrderBook,
+        no_book: &OrderBook,
+        costs: CostModel,
+    ) -> Option<ArbitrageOpportunity> {
+        // Sort ladders best first rather than trusting the source ordering
+        let (yes_levels, no_levels) = match direction {
+            ArbDirection::BuyBoth => (sorted_asks(&yes_book.asks), sorted_asks(&no_book.asks)),
+            ArbDirection::SellBoth => (sorted_bids(&yes_book.bids), sorted_bids(&no_book.bids)),
+        };
+
+        // Get best prices (cheapest ask to buy, highest bid to sell into)
+        let (yes_best, no_best) = match (yes_levels.first(), no_levels.first()) {
+            (Some(y), Some(n)) => (y, n),
+            (yes, _) => {
+                if direction == ArbDirection::BuyBoth {
+                    info!(
+                        "No {} asks for: {} (bids: {} / {})",
+                        if yes.is_none() { &pair.yes_outcome } else { &pair.no_outcome },
+                        &pair.description[..pair.description.len().min(30)],
+                        yes_book.bids.len(),
+                        no_book.bids.len()
+                    );
+                }
                 return None;
             }
         };
 
-        let combined_price = yes_ask.price + no_ask.price;
-        let profit_per_share = Decimal::ONE - combined_price;
+        let combined_price = yes_best.price + no_best.price;
+        let profit_per_share = costs.edge_per_share(direction, yes_best.price, no_best.price);
 
-        // Always log prices for debugging (at info level for visibility)
         // Safely truncate to ~35 chars respecting UTF-8 boundaries
         let desc_truncated: String = pair.description.chars().take(35).collect();
-        info!(
-            "Market: {} | YES: ${} | NO: ${} | Combined: ${:.3} | Spread: {:.4}",
+        match direction {
+            // Always log prices for debugging (at info level for visibility)
+            ArbDirection::BuyBoth => info!(
+                "Market: {} | {}: ${} | {}: ${} | Combined: ${:.3} | Spread: {:.4}",
+                desc_truncated,
+                pair.yes_outcome,
+                yes_best.price,
+                pair.no_outcome,
+                no_best.price,
+                combined_price,
+                profit_per_share
+            ),
+            ArbDirection::SellBoth => debug!(
+                "Market: {} | {} bid: ${} | {} bid: ${} | Combined: ${:.3} | Spread: {:.4}",
+                desc_truncated,
+                pair.yes_outcome,
+                yes_best.price,
+                pair.no_outcome,
+                no_best.price,
+                combined_price,
+                profit_per_share
+            ),
+        }
+
+        // Top of book must clear the thresholds (net of fees) before walking depth
+        let (within_price, min_edge) = match direction {
+            ArbDirection::BuyBoth => (
+                Decimal::ONE - profit_per_share < self.config.max_combined_price,
+                Decimal::ONE - self.config.max_combined_price,
+            ),
+            ArbDirection::SellBoth => (
+                Decimal::ONE + profit_per_share > self.config.min_combined_sell_price,
+                self.config.min_combined_sell_price - Decimal::ONE,
+            ),
+        };
+        if !within_price || profit_per_share < self.config.min_profit_threshold {
+            return None;
+        }
+
+        let levels: Vec<DepthLevel> = walk_depth(
+            &[yes_levels.as_slice(), no_levels.as_slice()],
+            |prices| costs.edge_per_share(direction, prices[0], prices[1]),
+            min_edge.max(self.config.min_profit_threshold),
+        )
+        .into_iter()
+        .map(|l| DepthLevel { size: l.size, yes_price: l.prices[0], no_price: l.prices[1] })
+        .collect();
+        let max_size: Decimal = levels.iter().map(|l| l.size).sum();
+        if max_size.is_zero() {
+            return None;
+        }
+
+        let yes_notional: Decimal = levels.iter().map(|l| l.size * l.yes_price).sum();
+        let no_notional: Decimal = levels.iter().map(|l| l.size * l.no_price).sum();
+        let yes_vwap = yes_notional / max_size;
+        let no_vwap = no_notional / max_size;
+        let last = levels.last()?;
+        let yes_marginal_price = last.yes_price;
+        let no_marginal_price = last.no_price;
+
+        let mut opp = ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4().to_string(),
+            market_id: pair.condition_id.clone(),
+            direction,
+            yes_token_id: pair.yes_token_id.clone(),
+            no_token_id: pair.no_token_id.clone(),
+            neg_risk: pair.neg_risk,
+            yes_outcome: pair.yes_outcome.clone(),
+            no_outcome: pair.no_outcome.clone(),
+            yes_price: yes_best.price,
+            no_price: no_best.price,
+            combined_price,
+            profit_per_share,
+            yes_vwap,
+            no_vwap,
+            combined_vwap: yes_vwap + no_vwap,
+            yes_marginal_price,
+            no_marginal_price,
+            max_size,
+            total_expected_profit: Decimal::ZERO,
+            levels,
+            costs,
+            timestamp: chrono::Utc::now(),
+        };
+        opp.total_expected_profit = opp.expected_profit(max_size);
+
+        // Fixed costs only clear at size, so re-check net profit at the size we'd trade
+        let trade_size = self.config.order_size.min(max_size);
+        if trade_size <= Decimal::ZERO {
+            return None;
+        }
+        let net_per_share = opp.expected_profit(trade_size) / trade_size;
+        if net_per_share < self.config.min_profit_threshold {
+            debug!(
+                "Skipping {}: net profit/share {} at size {} below threshold after costs",
+                pair.description, net_per_share, trade_size
+            );
+            return None;
+        }
+
+        debug!(
+            "Arbitrage found ({}): {} - {}@{} + {}@{} = {} (profit: {}) | depth {} @ VWAP {} + {}",
+            direction,
+            pair.description,
+            pair.yes_outcome,
+            yes_best.price,
+            pair.no_outcome,
+            no_best.price,
+            combined_price,
+            profit_per_share,
+            max_size,
+            yes_vwap,
+            no_vwap
+        );
+
+        Some(opp)
+    }
+
+    /// Check a market pair against a YES and NO book directly
+    ///
+    /// Used for WebSocket-maintained local books, where no REST fetch is needed.
+    pub fn check_books(
+        &self,
+        pair: &MarketPair,
+        yes_book: &OrderBook,
+        no_book: &OrderBook,
+    ) -> Option<ArbitrageOpportunity> {
+        let book_map: std::collections::HashMap<String, &OrderBook> = [
+            (pair.yes_token_id.clone(), yes_book),
+            (pair.no_token_id.clone(), no_book),
+        ]
+        .into_iter()
+        .collect();
+
+        self.check_arbitrage(pair, &book_map)
+    }
+
+    /// Check every cached group against a set of order books (fetched or replayed)
+    pub fn scan_basket_books(&self, order_books: &[OrderBook]) -> Vec<BasketOpportunity> {
+        let book_map: std::collections::HashMap<String, &OrderBook> = order_books
+            .iter()
+            .map(|ob| (ob.asset_id.clone(), ob))
+            .collect();
+
+        let opportunities: Vec<BasketOpportunity> = self.group_cache
+            .iter()
+            .filter_map(|group| self.check_basket(group.value(), &book_map))
+            .collect();
+
+        if !opportunities.is_empty() {
+            info!("Found {} basket opportunities!", opportunities.len());
+        }
+
+        opportunities
+    }
+
+    /// Check a multi-outcome group: buy every outcome for less than $1 total
+    pub fn check_basket(
+        &self,
+        group: &MarketGroup,
+        book_map: &std::collections::HashMap<String, &OrderBook>,
+    ) -> Option<BasketOpportunity> {
+        if self.is_past_cutoff(group.closes_at) {
+            debug!("Too close to expiry to trade: {}", group.description);
+            return None;
+        }
+
+        // Every outcome needs asks, otherwise the basket isn't a complete set
+        let mut ladders = Vec::with_capacity(group.outcomes.len());
+        for outcome in &group.outcomes {
+            let book = book_map.get(&outcome.token_id)?;
+            let asks = sorted_asks(&book.asks);
+            if asks.is_empty() {
+                debug!("No asks for outcome '{}' in group {}", outcome.label, group.group_id);
+                return None;
+            }
+            ladders.push(asks);
+        }
+
+        let costs = CostModel {
+            fee_rate_bps: group.fee_rate_bps,
+            tick_size: group.tick_size,
+            fixed_cost: self.config.fixed_cost_per_trade,
+        };
+        let basket_edge = |prices: &[Decimal]| {
+            Decimal::ONE - prices.iter().map(|p| costs.cost_per_share(*p)).sum::<Decimal>()
+        };
+
+        let best: Vec<Decimal> = ladders.iter().map(|l| l[0].price).collect();
+        let combined_price: Decimal = best.iter().sum();
+        let profit_per_share = basket_edge(&best);
+
+        let desc_truncated: String = group.description.chars().take(35).collect();
+        debug!(
+            "Group: {} | {} outcomes | Combined: ${:.3} | Spread: {:.4}",
             desc_truncated,
-            yes_ask.price,
-            no_ask.price,
+            group.outcomes.len(),
             combined_price,
             profit_per_share
         );
 
-        // Check if profitable
-        if combined_price < self.config.max_combined_price
-            && profit_per_share >= self.config.min_profit_threshold
+        if Decimal::ONE - profit_per_share >= self.config.max_combined_price
+            || profit_per_share < self.config.min_profit_threshold
         {
-            // Max size is limited by the smaller order book side
-            let max_size = yes_ask.size.min(no_ask.size);
-
-            debug!(
-                "Arbitrage found: {} - YES@{} + NO@{} = {} (profit: {})",
-                pair.description,
-                yes_ask.price,
-                no_ask.price,
-                combined_price,
-                profit_per_share
-            );
+            return None;
+        }
 
-            return Some(ArbitrageOpportunity {
-                market_id: pair.condition_id.clone(),
-                yes_token_id: pair.yes_token_id.clone(),
-                no_token_id: pair.no_token_id.clone(),
-                yes_ask_price: yes_ask.price,
-                no_ask_price: no_ask.price,
-                combined_price,
-                profit_per_share,
-                max_size,
-                timestamp: chrono::Utc::now(),
-            });
+        let ladder_refs: Vec<&[OrderBookEntry]> = ladders.iter().map(|l| l.as_slice()).collect();
+        let levels = walk_depth(
+            &ladder_refs,
+            basket_edge,
+            (Decimal::ONE - self.config.max_combined_price).max(self.config.min_profit_threshold),
+        );
+        let max_size: Decimal = levels.iter().map(|l| l.size).sum();
+        if max_size.is_zero() {
+            return None;
         }
 
-        None
-    }
+        let last = levels.last()?;
+        let legs: Vec<BasketLeg> = group
+            .outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, outcome)| {
+                let notional: Decimal = levels.iter().map(|l| l.size * l.prices[i]).sum();
+                BasketLeg {
+                    condition_id: outcome.condition_id.clone(),
+                    token_id: outcome.token_id.clone(),
+                    label: outcome.label.clone(),
+                    best_ask: best[i],
+                    vwap: notional / max_size,
+                    marginal_price: last.prices[i],
+                }
+            })
+            .collect();
 
-    /// Scan a single market by ID (for WebSocket-triggered checks)
-    pub async fn scan_market(&self, condition_id: &str) -> Result<Option<ArbitrageOpportunity>> {
-        let pair = match self.market_cache.get(condition_id) {
-            Some(p) => p.clone(),
-            None => return Ok(None),
+        let mut opp = BasketOpportunity {
+            id: uuid::Uuid::new_v4().to_string(),
+            group_id: group.group_id.clone(),
+            description: group.description.clone(),
+            neg_risk: group.neg_risk,
+            combined_vwap: legs.iter().map(|l| l.vwap).sum(),
+            legs,
+            combined_price,
+            profit_per_share,
+            max_size,
+            total_expected_profit: Decimal::ZERO,
+            levels,
+            costs,
+            timestamp: chrono::Utc::now(),
         };
+        opp.total_expected_profit = opp.expected_profit(max_size);
 
-        let token_ids = vec![pair.yes_token_id.clone(), pair.no_token_id.clone()];
-        let order_books = self.client.get_order_books(&token_ids).await?;
+        // Fixed costs only clear at size, so re-check net profit at the size we'd trade
+        let trade_size = self.config.order_size.min(max_size);
+        if trade_size <= Decimal::ZERO
+            || opp.expected_profit(trade_size) / trade_size < self.config.min_profit_threshold
+        {
+            return None;
+        }
 
-        let book_map: std::collections::HashMap<String, &OrderBook> = order_books
+        debug!(
+            "Basket found: {} - {} outcomes = {} (profit: {}) | depth {} @ VWAP {}",
+            group.description,
+            group.outcomes.len(),
+            combined_price,
+            profit_per_share,
+            max_size,
+            opp.combined_vwap
+        );
+
+        Some(opp)
+    }
+
+    /// Check a multi-outcome group against local books directly
+    pub fn check_group_books(&self, group: &MarketGroup, books: &[OrderBook]) -> Option<BasketOpportunity> {
+        let book_map: std::collections::HashMap<String, &OrderBook> = books
             .iter()
             .map(|ob| (ob.asset_id.clone(), ob))
             .collect();
 
-        Ok(self.check_arbitrage(&pair, &book_map))
+        self.check_basket(group, &book_map)
+    }
+
+    /// Check the pair a token belongs to against books kept in `books`
+    ///
+    /// Used for WebSocket-maintained local books, after an update to `token_id`.
+    pub fn check_cached_pair(&self, token_id: &str, books: &OrderBookStore) -> Option<ArbitrageOpportunity> {
+        let pair = self.pair_for_token(token_id)?;
+        let yes_book = books.get(&pair.yes_token_id)?;
+        let no_book = books.get(&pair.no_token_id)?;
+        self.check_books(&pair, &yes_book, &no_book)
+    }
+
+    /// Check the multi-outcome group a token belongs to against books kept in `books`
+    pub fn check_cached_group(&self, token_id: &str, books: &OrderBookStore) -> Option<BasketOpportunity> {
+        let group = self.group_for_token(token_id)?;
+        let group_books = group
+            .outcomes
+            .iter()
+            .map(|o| books.get(&o.token_id))
+            .collect::<Option<Vec<_>>>()?;
+        self.check_group_books(&group, &group_books)
+    }
+
+    /// All cached market pairs
+    pub fn pairs(&self) -> Vec<MarketPair> {
+        self.market_cache.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    /// All cached multi-outcome groups
+    pub fn groups(&self) -> Vec<MarketGroup> {
+        self.group_cache.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    /// Look up a cached market pair by condition id
+    pub fn pair(&self, condition_id: &str) -> Option<MarketPair> {
+        self.market_cache.get(condition_id).map(|p| p.clone())
+    }
+
+    /// Look up the market pair a token belongs to
+    pub fn pair_for_token(&self, token_id: &str) -> Option<MarketPair> {
+        let condition_id = self.token_index.get(token_id)?;
+        self.market_cache.get(condition_id.value()).map(|p| p.clone())
+    }
+
+    /// Look up the multi-outcome group a token belongs to
+    pub fn group_for_token(&self, token_id: &str) -> Option<MarketGroup> {
+        let group_id = self.group_index.get(token_id)?;
+        self.group_cache.get(group_id.value()).map(|g| g.clone())
     }
 
     /// Get all watched token IDs (for WebSocket subscriptions)
     pub fn get_watched_token_ids(&self) -> Vec<String> {
-        self.market_cache
+        let mut token_ids: Vec<String> = self.market_cache
             .iter()
             .flat_map(|entry| {
                 let pair = entry.value();
                 vec![pair.yes_token_id.clone(), pair.no_token_id.clone()]
             })
-            .collect()
-    }
+            .collect();
 
-    /// Get reference to the client
-    pub fn client(&self) -> &ClobClient {
-        &self.client
+        // Neg-risk YES tokens are already watched via their pair
+        for entry in self.group_index.iter() {
+            if !self.token_index.contains_key(entry.key()) {
+                token_ids.push(entry.key().clone());
+            }
+        }
+
+        token_ids
     }
 
     /// Get reference to config
@@ -263,3 +1190,150 @@ impl ArbitrageScanner {
         &self.config
     }
 }
+
+/// Earlier of two times, ignoring missing ones
+fn earliest(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// When trading in a market closes (see `MarketPair::closes_at`)
+fn market_closes_at(market: &Market) -> Option<DateTime<Utc>> {
+    earliest(
+        market.end_date_iso.as_deref().and_then(parse_end_date),
+        market.game_start_time.as_deref().and_then(parse_end_date),
+    )
+}
+
+/// Copy of an ask ladder sorted cheapest first, with empty levels dropped
+fn sorted_asks(asks: &[OrderBookEntry]) -> Vec<OrderBookEntry> {
+    let mut sorted: Vec<OrderBookEntry> = asks
+        .iter()
+        .filter(|e| e.size > Decimal::ZERO)
+        .cloned()
+        .collect();
+    sorted.sort_by_key(|e| e.price);
+    sorted
+}
+
+/// Copy of a bid ladder sorted highest first, with empty levels dropped
+fn sorted_bids(bids: &[OrderBookEntry]) -> Vec<OrderBookEntry> {
+    let mut sorted: Vec<OrderBookEntry> = bids
+        .iter()
+        .filter(|e| e.size > Decimal::ZERO)
+        .cloned()
+        .collect();
+    sorted.sort_by_key(|e| std::cmp::Reverse(e.price));
+    sorted
+}
+
+/// Walk best-first ladders together, trading matched chunks across all of them
+///
+/// `edge` gives the net profit per set for a chunk at the given per-ladder
+/// prices. Chunks are taken best first for as long as the volume-weighted edge
+/// stays at or above `min_edge`. The final chunk is taken partially (rounded
+/// down to 0.01 shares) if only part of it fits.
+fn walk_depth(
+    ladders: &[&[OrderBookEntry]],
+    edge: impl Fn(&[Decimal]) -> Decimal,
+    min_edge: Decimal,
+) -> Vec<BasketLevel> {
+    let mut levels = Vec::new();
+    if ladders.is_empty() || ladders.iter().any(|l| l.is_empty()) {
+        return levels;
+    }
+
+    let mut idx = vec![0usize; ladders.len()];
+    let mut left: Vec<Decimal> = ladders.iter().map(|l| l[0].size).collect();
+    let mut total_size = Decimal::ZERO;
+    let mut total_edge = Decimal::ZERO;
+
+    while idx.iter().zip(ladders).all(|(i, l)| *i < l.len()) {
+        let prices: Vec<Decimal> = idx.iter().zip(ladders).map(|(i, l)| l[*i].price).collect();
+        let marginal = edge(&prices);
+        let size = left.iter().copied().min().unwrap_or_default();
+
+        if total_edge + size * marginal < min_edge * (total_size + size) {
+            // Only part of this chunk keeps the average edge above the floor
+            if marginal < min_edge {
+                let partial = ((total_edge - min_edge * total_size) / (min_edge - marginal))
+                    .round_dp_with_strategy(2, RoundingStrategy::ToZero);
+                if partial > Decimal::ZERO {
+                    levels.push(BasketLevel { size: partial, prices });
+                }
+            }
+            break;
+        }
+
+        total_size += size;
+        total_edge += size * marginal;
+        levels.push(BasketLevel { size, prices });
+
+        for (k, ladder) in ladders.iter().enumerate() {
+            left[k] -= size;
+            if left[k].is_zero() {
+                idx[k] += 1;
+                left[k] = ladder.get(idx[k]).map(|e| e.size).unwrap_or_default();
+            }
+        }
+    }
+
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ladder(levels: &[(i64, i64)]) -> Vec<OrderBookEntry> {
+        levels
+            .iter()
+            .map(|&(price, size)| OrderBookEntry { price: Decimal::new(price, 2), size: Decimal::from(size) })
+            .collect()
+    }
+
+    fn set_edge(prices: &[Decimal]) -> Decimal {
+        Decimal::ONE - prices.iter().copied().sum::<Decimal>()
+    }
+
+    #[test]
+    fn walk_depth_matches_uneven_levels() {
+        let yes = ladder(&[(40, 30), (42, 100)]);
+        let no = ladder(&[(50, 80), (51, 100)]);
+
+        let levels = walk_depth(&[&yes, &no], set_edge, Decimal::ZERO);
+        let sizes: Vec<Decimal> = levels.iter().map(|l| l.size).collect();
+        assert_eq!(sizes, vec![Decimal::from(30), Decimal::from(50), Decimal::from(50)]);
+        assert_eq!(levels[1].prices, vec![Decimal::new(42, 2), Decimal::new(50, 2)]);
+        assert_eq!(levels[2].prices, vec![Decimal::new(42, 2), Decimal::new(51, 2)]);
+    }
+
+    #[test]
+    fn walk_depth_stops_where_the_average_edge_hits_the_floor() {
+        // 100 sets at 10¢ edge, then a level losing 3¢ per set
+        let yes = ladder(&[(40, 100), (48, 100)]);
+        let no = ladder(&[(50, 100), (55, 100)]);
+        let min_edge = Decimal::new(5, 2);
+
+        let levels = walk_depth(&[&yes, &no], set_edge, min_edge);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].size, Decimal::from(100));
+        // (10 - 5) / (0.05 + 0.03) sets of the losing level keep the VWAP edge at 5¢
+        assert_eq!(levels[1].size, Decimal::new(6250, 2));
+
+        let size: Decimal = levels.iter().map(|l| l.size).sum();
+        let edge: Decimal = levels.iter().map(|l| l.size * set_edge(&l.prices)).sum();
+        assert_eq!(edge / size, min_edge);
+    }
+
+    #[test]
+    fn walk_depth_takes_nothing_below_the_floor() {
+        let yes = ladder(&[(50, 100)]);
+        let no = ladder(&[(49, 100)]);
+
+        assert!(walk_depth(&[&yes, &no], set_edge, Decimal::new(2, 2)).is_empty());
+        assert!(walk_depth(&[&yes, &[]], set_edge, Decimal::ZERO).is_empty());
+    }
+}
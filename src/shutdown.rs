@@ -0,0 +1,107 @@
+//! Kill switch and shutdown signals
+//! Stops new executions on SIGINT/SIGTERM or when a kill file appears
+
+use parking_lot::Mutex;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// How often the kill file is checked for
+const KILL_FILE_POLL: Duration = Duration::from_secs(1);
+
+/// Global switch that stops trading once triggered
+#[derive(Debug, Default)]
+pub struct KillSwitch {
+    triggered: AtomicBool,
+    reason: Mutex<Option<String>>,
+    notify: Notify,
+}
+
+impl KillSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop trading; only the first reason is kept
+    pub fn trigger(&self, reason: impl Into<String>) {
+        if self.triggered.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let reason = reason.into();
+        error!("KILL SWITCH: {} - stopping new executions", reason);
+        *self.reason.lock() = Some(reason);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        self.reason.lock().clone()
+    }
+
+    /// Resolve once the switch is triggered
+    pub async fn triggered(&self) {
+        loop {
+            // Register before checking so a trigger in between isn't missed
+            let notified = self.notify.notified();
+            if self.is_triggered() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Trigger on SIGINT (Ctrl+C) or SIGTERM
+    pub fn listen_for_signals(self: &Arc<Self>) {
+        let switch = self.clone();
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            {
+                use tokio::signal::unix::{signal, SignalKind};
+                match signal(SignalKind::terminate()) {
+                    Ok(mut sigterm) => {
+                        tokio::select! {
+                            _ = tokio::signal::ctrl_c() => switch.trigger("received SIGINT"),
+                            _ = sigterm.recv() => switch.trigger("received SIGTERM"),
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to install SIGTERM handler: {}", e);
+                        if tokio::signal::ctrl_c().await.is_ok() {
+                            switch.trigger("received SIGINT");
+                        }
+                    }
+                }
+            }
+            #[cfg(not(unix))]
+            {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    switch.trigger("received Ctrl+C");
+                }
+            }
+        });
+    }
+
+    /// Trigger when `path` exists (e.g. `touch STOP_TRADING`)
+    pub fn watch_file(self: &Arc<Self>, path: impl Into<PathBuf>) {
+        let switch = self.clone();
+        let path = path.into();
+        if path.exists() {
+            warn!("Kill file {} already exists; remove it to trade", path.display());
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(KILL_FILE_POLL);
+            while !switch.is_triggered() {
+                ticker.tick().await;
+                if path.exists() {
+                    switch.trigger(format!("kill file {} found", path.display()));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,252 @@
+//! Fill simulation against order book snapshots
+//! Walks book depth as a taker, shared by paper trading, backtests and the mock exchange
+
+use rust_decimal::Decimal;
+
+use crate::api::{OrderBook, OrderBookEntry, Side};
+use crate::fees::CostModel;
+
+/// Liquidity taken from a book by a simulated order
+#[derive(Debug, Clone, Default)]
+pub struct Fill {
+    /// Price levels consumed, best first
+    pub chunks: Vec<OrderBookEntry>,
+}
+
+impl Fill {
+    pub fn shares(&self) -> Decimal {
+        self.chunks.iter().map(|c| c.size).sum()
+    }
+
+    /// USDC at the book prices, before fees
+    pub fn notional(&self) -> Decimal {
+        self.chunks.iter().map(|c| c.size * c.price).sum()
+    }
+
+    /// USDC paid (buys) or received (sells) after fees and tick rounding
+    pub fn net_usdc(&self, side: Side, costs: &CostModel) -> Decimal {
+        self.chunks
+            .iter()
+            .map(|c| match side {
+                Side::Buy => c.size * costs.cost_per_share(c.price),
+                Side::Sell => c.size * costs.proceeds_per_share(c.price),
+            })
+            .sum()
+    }
+}
+
+/// Walk `book` as a taker, filling up to `size` shares no worse than `limit`
+///
+/// Buys take asks cheapest first and sells hit bids highest first. A FOK
+/// order fills only if the result covers all of `size`.
+pub fn take_liquidity(book: &OrderBook, side: Side, size: Decimal, limit: Option<Decimal>) -> Fill {
+    let mut levels: Vec<&OrderBookEntry> = match side {
+        Side::Buy => book.asks.iter().collect(),
+        Side::Sell => book.bids.iter().collect(),
+    };
+    match side {
+        Side::Buy => levels.sort_by_key(|l| l.price),
+        Side::Sell => levels.sort_by_key(|l| std::cmp::Reverse(l.price)),
+    }
+
+    let mut remaining = size;
+    let mut chunks = Vec::new();
+    for level in levels {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        let acceptable = match (side, limit) {
+            (_, None) => true,
+            (Side::Buy, Some(limit)) => level.price <= limit,
+            (Side::Sell, Some(limit)) => level.price >= limit,
+        };
+        if !acceptable {
+            break;
+        }
+        if level.size <= Decimal::ZERO {
+            continue;
+        }
+        let take = level.size.min(remaining);
+        chunks.push(OrderBookEntry { price: level.price, size: take });
+        remaining -= take;
+    }
+
+    Fill { chunks }
+}
+
+/// One leg of a simulated trade, as it would be sent
+#[derive(Debug, Clone, Copy)]
+pub struct SimOrder {
+    pub side: Side,
+    pub limit: Decimal,
+}
+
+/// Simulated outcome of one leg
+#[derive(Debug, Clone, Default)]
+pub struct SimFill {
+    /// Shares filled within the limit: all of the size or none, like a FOK order
+    pub filled: Decimal,
+    /// USDC paid (buys) or received (sells) for the fill, after fees
+    pub usdc: Decimal,
+    /// Shares beyond the matched sets traded back into the book
+    pub unwound: Decimal,
+    /// Shares beyond the matched sets the book couldn't absorb
+    pub stranded: Decimal,
+    /// Cost basis of stranded buys, which stay held
+    pub stranded_cost: Decimal,
+}
+
+/// Simulated outcome of a whole trade
+#[derive(Debug, Clone)]
+pub struct SimTrade {
+    /// Complete sets bought or sold
+    pub sets: Decimal,
+    pub legs: Vec<SimFill>,
+    /// USDC gained or lost: fills, unwinds, set settlement and the fixed cost
+    pub cash: Decimal,
+}
+
+impl SimTrade {
+    /// Cost basis of the stranded buys left held
+    pub fn held(&self) -> Decimal {
+        self.legs.iter().map(|leg| leg.stranded_cost).sum()
+    }
+
+    /// Realized P&L, with stranded buys valued at their cost
+    pub fn pnl(&self) -> Decimal {
+        self.cash + self.held()
+    }
+}
+
+/// Simulate the FOK legs of an arbitrage of `size` sets against `books`
+///
+/// Complete sets settle at $1 straight away, as a merge would: bought sets
+/// pay out $1 and sold sets cost $1 to split. Shares a leg filled beyond the
+/// matched sets are traded back into its book; buys the book can't absorb
+/// stay held at cost, sells that can't be bought back are charged the $1
+/// set they would have to be delivered from. The fixed cost is charged
+/// whenever any sets settle. A leg without a book doesn't fill.
+pub fn simulate_trade(books: &[Option<OrderBook>], orders: &[SimOrder], size: Decimal, costs: &CostModel) -> SimTrade {
+    let fills: Vec<Fill> = orders
+        .iter()
+        .zip(books)
+        .map(|(order, book)| {
+            book.as_ref()
+                .map(|book| take_liquidity(book, order.side, size, Some(order.limit)))
+                .filter(|fill| fill.shares() >= size)
+                .unwrap_or_default()
+        })
+        .collect();
+    let sets = fills.iter().map(Fill::shares).min().unwrap_or_default();
+
+    let mut cash = Decimal::ZERO;
+    let mut legs = Vec::with_capacity(orders.len());
+    for ((order, fill), book) in orders.iter().zip(&fills).zip(books) {
+        let usdc = fill.net_usdc(order.side, costs);
+        cash += signed(order.side, usdc);
+
+        let mut leg = SimFill { filled: fill.shares(), usdc, ..SimFill::default() };
+        let excess = fill.shares() - sets;
+        if excess > Decimal::ZERO {
+            let back = opposite(order.side);
+            let unwind = book
+                .as_ref()
+                .map(|book| take_liquidity(book, back, excess, None))
+                .unwrap_or_default();
+            cash += signed(back, unwind.net_usdc(back, costs));
+            leg.unwound = unwind.shares();
+            leg.stranded = excess - leg.unwound;
+            match order.side {
+                Side::Buy => leg.stranded_cost = usdc * leg.stranded / fill.shares(),
+                Side::Sell => cash -= leg.stranded,
+            }
+        }
+        legs.push(leg);
+    }
+
+    if sets > Decimal::ZERO {
+        cash += match orders[0].side {
+            Side::Buy => sets,
+            Side::Sell => -sets,
+        };
+        cash -= costs.fixed_cost;
+    }
+
+    SimTrade { sets, legs, cash }
+}
+
+/// Buys pay USDC, sells receive it
+fn signed(side: Side, usdc: Decimal) -> Decimal {
+    match side {
+        Side::Buy => -usdc,
+        Side::Sell => usdc,
+    }
+}
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: i64, size: i64) -> OrderBookEntry {
+        OrderBookEntry { price: Decimal::new(price, 2), size: Decimal::from(size) }
+    }
+
+    fn book(bids: &[OrderBookEntry], asks: &[OrderBookEntry]) -> Option<OrderBook> {
+        Some(OrderBook {
+            market: String::new(),
+            asset_id: String::new(),
+            bids: bids.to_vec(),
+            asks: asks.to_vec(),
+            hash: String::new(),
+            timestamp: String::new(),
+        })
+    }
+
+    fn costs() -> CostModel {
+        CostModel { fee_rate_bps: Decimal::ZERO, tick_size: Decimal::new(1, 2), fixed_cost: Decimal::new(1, 2) }
+    }
+
+    fn buy(limit: i64) -> SimOrder {
+        SimOrder { side: Side::Buy, limit: Decimal::new(limit, 2) }
+    }
+
+    #[test]
+    fn filled_sets_settle_at_a_dollar_less_the_fixed_cost() {
+        let books = [book(&[], &[level(40, 10)]), book(&[], &[level(50, 10)])];
+        let trade = simulate_trade(&books, &[buy(40), buy(50)], Decimal::from(10), &costs());
+
+        assert_eq!(trade.sets, Decimal::from(10));
+        assert_eq!(trade.pnl(), Decimal::new(99, 2));
+    }
+
+    #[test]
+    fn partial_fill_unwinds_what_the_book_absorbs_and_holds_the_rest_at_cost() {
+        // NO is too thin to fill; YES can only be sold back for 4 shares at 38¢
+        let books = [book(&[level(38, 4)], &[level(40, 10)]), book(&[], &[level(50, 5)])];
+        let trade = simulate_trade(&books, &[buy(40), buy(50)], Decimal::from(10), &costs());
+
+        assert!(trade.sets.is_zero());
+        assert_eq!(trade.legs[0].unwound, Decimal::from(4));
+        assert_eq!(trade.legs[0].stranded, Decimal::from(6));
+        assert_eq!(trade.held(), Decimal::new(24, 1));
+        // Only the unwind's 2¢ a share is lost; no fixed cost without a settled set
+        assert_eq!(trade.pnl(), Decimal::new(-8, 2));
+    }
+
+    #[test]
+    fn stranded_sells_are_charged_a_dollar() {
+        let sell = |limit| SimOrder { side: Side::Sell, limit: Decimal::new(limit, 2) };
+        let books = [book(&[level(60, 10)], &[]), book(&[level(45, 5)], &[])];
+        let trade = simulate_trade(&books, &[sell(60), sell(45)], Decimal::from(10), &costs());
+
+        assert_eq!(trade.legs[0].stranded, Decimal::from(10));
+        assert_eq!(trade.pnl(), Decimal::from(6) - Decimal::from(10));
+    }
+}
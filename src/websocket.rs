@@ -9,10 +9,12 @@ use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, info, warn};
 
-use crate::api::{OrderBookEntry, WsEvent};
+use crate::api::{OrderBookEntry, Side, WsEvent};
+use crate::orderbook::OrderBookStore;
+use crate::recording::Recorder;
 
-/// WebSocket endpoint
-const WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+/// Default market channel endpoint
+pub const WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
 
 /// Subscription message
 #[derive(Debug, Serialize)]
@@ -23,6 +25,9 @@ struct SubscribeMessage {
 }
 
 /// Price update event sent through channel
+///
+/// Emitted after the local order book for `asset_id` has changed; the full
+/// ladders are available from the shared `OrderBookStore`.
 #[derive(Debug, Clone)]
 pub struct PriceUpdate {
     pub asset_id: String,
@@ -39,15 +44,23 @@ pub struct WsClient {
 }
 
 impl WsClient {
-    /// Connect and subscribe to token IDs
-    pub async fn connect(token_ids: Vec<String>) -> Result<Self> {
+    /// Connect to the market channel at `url` and subscribe to token IDs
+    ///
+    /// Book snapshots and level deltas are applied to `books` as they arrive.
+    /// With a `recorder`, every text frame is written out before it is handled.
+    pub async fn connect(
+        url: &str,
+        token_ids: Vec<String>,
+        books: Arc<OrderBookStore>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Result<Self> {
         if token_ids.is_empty() {
             anyhow::bail!("No token IDs to subscribe to");
         }
 
         info!("Connecting to WebSocket with {} tokens...", token_ids.len());
 
-        let (ws_stream, _) = connect_async(WS_URL)
+        let (ws_stream, _) = connect_async(url)
             .await
             .context("Failed to connect to WebSocket")?;
 
@@ -63,6 +76,10 @@ impl WsClient {
         write.send(Message::Text(msg_json)).await?;
         info!("Subscribed to {} token feeds", token_ids.len());
 
+        // Stale books from a previous connection would miss deltas, and tokens no
+        // longer watched would keep theirs; start over from fresh snapshots
+        books.clear();
+
         // Create channels
         let (tx, rx) = mpsc::channel::<PriceUpdate>(1000);
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
@@ -78,8 +95,16 @@ impl WsClient {
                     msg = read.next() => {
                         match msg {
                             Some(Ok(Message::Text(text))) => {
-                                if let Err(e) = Self::handle_message(&text, &tx).await {
-                                    debug!("Failed to handle message: {}", e);
+                                if let Some(recorder) = &recorder {
+                                    recorder.record_frame(&text);
+                                }
+                                match Self::handle_message(&text, &books) {
+                                    Ok(updates) => {
+                                        for update in updates {
+                                            tx.send(update).await.ok();
+                                        }
+                                    }
+                                    Err(e) => debug!("Failed to handle message: {}", e),
                                 }
                             }
                             Some(Ok(Message::Ping(data))) => {
@@ -108,45 +133,79 @@ impl WsClient {
         Ok(Self { rx, shutdown_tx })
     }
 
-    /// Handle incoming WebSocket message
-    async fn handle_message(text: &str, tx: &mpsc::Sender<PriceUpdate>) -> Result<()> {
+    /// Handle incoming WebSocket message (live or replayed)
+    ///
+    /// Returns the new top of book of every asset the frame touched, in order.
+    /// Frames can carry any number of events, so nothing here waits on a
+    /// bounded consumer.
+    pub fn handle_message(text: &str, books: &OrderBookStore) -> Result<Vec<PriceUpdate>> {
+        let mut updates = Vec::new();
+
         // Try parsing as array of events (Polymarket sends batches)
         if let Ok(events) = serde_json::from_str::<Vec<WsEventWrapper>>(text) {
             for event in events {
-                Self::process_event(event, tx).await?;
+                Self::process_event(event, books, &mut updates)?;
             }
-            return Ok(());
+            return Ok(updates);
         }
 
         // Try parsing as single event
         if let Ok(event) = serde_json::from_str::<WsEventWrapper>(text) {
-            Self::process_event(event, tx).await?;
+            Self::process_event(event, books, &mut updates)?;
         }
 
-        Ok(())
+        Ok(updates)
     }
 
     /// Process a single WebSocket event
-    async fn process_event(event: WsEventWrapper, tx: &mpsc::Sender<PriceUpdate>) -> Result<()> {
+    fn process_event(
+        event: WsEventWrapper,
+        books: &OrderBookStore,
+        updates: &mut Vec<PriceUpdate>,
+    ) -> Result<()> {
         match event.event_type.as_str() {
             "book" => {
-                let update = PriceUpdate {
-                    asset_id: event.asset_id.clone(),
-                    best_bid: event.bids.as_ref()
-                        .and_then(|b| b.first())
-                        .map(|e| e.price),
-                    best_ask: event.asks.as_ref()
-                        .and_then(|a| a.first())
-                        .map(|e| e.price),
-                };
-
-                if update.best_ask.is_some() || update.best_bid.is_some() {
-                    tx.send(update).await.ok();
+                books.apply_snapshot(
+                    &event.asset_id,
+                    event.market.as_deref(),
+                    event.bids.as_deref().unwrap_or_default(),
+                    event.asks.as_deref().unwrap_or_default(),
+                    event.hash.as_deref(),
+                    event.timestamp.as_deref(),
+                );
+                Self::push_update(&event.asset_id, books, updates);
+            }
+            "price_change" => {
+                // Older feeds nest `changes` under a top-level asset_id, newer
+                // ones send `price_changes` entries carrying their own asset_id
+                let changes = event.changes.iter().flatten()
+                    .chain(event.price_changes.iter().flatten());
+
+                let mut touched: Vec<&str> = Vec::new();
+                for change in changes {
+                    let asset_id = change.asset_id.as_deref().unwrap_or(&event.asset_id);
+                    if books.apply_change(
+                        asset_id,
+                        change.side,
+                        change.price,
+                        change.size,
+                        event.timestamp.as_deref(),
+                    ) {
+                        if !touched.contains(&asset_id) {
+                            touched.push(asset_id);
+                        }
+                    } else {
+                        debug!("Delta for {} before snapshot, ignoring", asset_id);
+                    }
+                }
+
+                for asset_id in touched {
+                    Self::push_update(asset_id, books, updates);
                 }
             }
-            "price_change" | "last_trade_price" => {
+            "last_trade_price" => {
                 if let Some(price) = event.price {
-                    debug!("Price update for {}: {}", event.asset_id, price);
+                    debug!("Last trade for {}: {}", event.asset_id, price);
                 }
             }
             _ => {
@@ -157,6 +216,21 @@ impl WsClient {
         Ok(())
     }
 
+    /// Record the new top of book for an asset
+    fn push_update(asset_id: &str, books: &OrderBookStore, updates: &mut Vec<PriceUpdate>) {
+        if let Some((best_bid, best_ask)) = books.best_prices(asset_id) {
+            let update = PriceUpdate {
+                asset_id: asset_id.to_string(),
+                best_bid: best_bid.map(|e| e.price),
+                best_ask: best_ask.map(|e| e.price),
+            };
+
+            if update.best_ask.is_some() || update.best_bid.is_some() {
+                updates.push(update);
+            }
+        }
+    }
+
     /// Shutdown the WebSocket connection
     pub async fn shutdown(self) {
         let _ = self.shutdown_tx.send(()).await;
@@ -167,13 +241,32 @@ impl WsClient {
 #[derive(Debug, Deserialize)]
 struct WsEventWrapper {
     event_type: String,
-    asset_id: String,
     #[serde(default)]
+    asset_id: String,
+    #[serde(default, alias = "buys")]
     bids: Option<Vec<OrderBookEntry>>,
-    #[serde(default)]
+    #[serde(default, alias = "sells")]
     asks: Option<Vec<OrderBookEntry>>,
     #[serde(default)]
     price: Option<rust_decimal::Decimal>,
     #[serde(default)]
     market: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    changes: Option<Vec<WsLevelChange>>,
+    #[serde(default)]
+    price_changes: Option<Vec<WsLevelChange>>,
+}
+
+/// Single price level delta from a `price_change` event
+#[derive(Debug, Deserialize)]
+struct WsLevelChange {
+    #[serde(default)]
+    asset_id: Option<String>,
+    price: rust_decimal::Decimal,
+    side: Side,
+    size: rust_decimal::Decimal,
 }
@@ -0,0 +1,57 @@
+//! Shared test fixtures
+
+// Each test crate uses only some of them
+#![allow(dead_code)]
+
+use rust_decimal::Decimal;
+
+use polymarket_arb::api::{Market, OrderBook, OrderBookEntry, Token};
+use polymarket_arb::config::Config;
+
+/// Active binary market `n` with Yes/No tokens `n1`/`n2`
+pub fn market(n: usize) -> Market {
+    Market {
+        condition_id: format!("0x{:064x}", n),
+        question_id: format!("0x{:064x}", n),
+        tokens: vec![
+            Token { token_id: format!("{}1", n), outcome: "Yes".to_string(), price: None, winner: false },
+            Token { token_id: format!("{}2", n), outcome: "No".to_string(), price: None, winner: false },
+        ],
+        minimum_order_size: Decimal::from(5),
+        minimum_tick_size: Decimal::new(1, 2),
+        taker_base_fee: Decimal::ZERO,
+        description: None,
+        category: None,
+        tags: Vec::new(),
+        end_date_iso: None,
+        game_start_time: None,
+        question: Some(format!("Market {}?", n)),
+        market_slug: Some(format!("market-{}", n)),
+        active: true,
+        closed: false,
+        accepting_orders: true,
+        neg_risk: false,
+        neg_risk_market_id: None,
+    }
+}
+
+/// Defaults with every listed market eligible, whatever its question
+pub fn config() -> Config {
+    Config {
+        crypto_only: false,
+        market_filter: Default::default(),
+        ..Config::default()
+    }
+}
+
+/// Book of `token_id` with 100 shares asked at `price` cents and no bids
+pub fn asks(token_id: &str, price: i64) -> OrderBook {
+    OrderBook {
+        market: String::new(),
+        asset_id: token_id.to_string(),
+        bids: Vec::new(),
+        asks: vec![OrderBookEntry { price: Decimal::new(price, 2), size: Decimal::from(100) }],
+        hash: String::new(),
+        timestamp: String::new(),
+    }
+}
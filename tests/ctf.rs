@@ -0,0 +1,260 @@
+//! On-chain merges against a minimal local chain
+
+use alloy::consensus::{Transaction, TxEnvelope};
+use alloy::eips::eip2718::Decodable2718;
+use alloy::primitives::{Bytes, B256, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use polymarket_arb::api::ctf::CtfClient;
+use polymarket_arb::api::{ArbitrageOpportunity, CtfConfig, Executor, MockExchange};
+use polymarket_arb::scanner::ArbitrageDetector;
+
+mod common;
+use common::{asks, config, market};
+
+/// Well-known development key; never holds funds
+const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+sol! {
+    interface IConditionalTokens {
+        function mergePositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] calldata partition,
+            uint256 amount
+        ) external;
+
+        function balanceOf(address owner, uint256 id) external view returns (uint256);
+    }
+}
+
+/// Outcome token balances of a single market, in 6-decimal units
+#[derive(Default)]
+struct ChainState {
+    balances: HashMap<U256, U256>,
+    /// Balances credited once the bot has checked its balances this many times
+    settling: Option<(usize, Vec<(U256, U256)>)>,
+    balance_checks: usize,
+    /// Amount of every merge sent, in 6-decimal units
+    merges: Vec<U256>,
+}
+
+/// JSON-RPC stand-in for a chain holding the CTF contract
+///
+/// Answers just the calls a merge makes: fee and nonce lookups, balance
+/// checks, the merge transaction itself and its receipt.
+struct LocalChain {
+    url: String,
+    state: Arc<Mutex<ChainState>>,
+}
+
+impl LocalChain {
+    async fn start(tokens: [&str; 2], held: [i64; 2]) -> Self {
+        let mut state = ChainState::default();
+        for (token, shares) in tokens.iter().zip(held) {
+            state.balances.insert(token.parse().unwrap(), units(shares));
+        }
+        let state = Arc::new(Mutex::new(state));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let tokens: Vec<U256> = tokens.iter().map(|t| t.parse().unwrap()).collect();
+        let shared = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = shared.clone();
+                let tokens = tokens.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    stream.read_exact(&mut body).await.unwrap();
+
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    let response = handle(&request, &tokens, &mut state.lock());
+                    let response = response.to_string();
+                    let reply = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        response.len(),
+                        response
+                    );
+                    stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        Self { url, state }
+    }
+
+    /// Credit `shares` of `token` once the bot has checked balances `after` times
+    fn settle_later(&self, after: usize, token: &str, shares: i64) {
+        self.state.lock().settling = Some((after, vec![(token.parse().unwrap(), units(shares))]));
+    }
+
+    fn merges(&self) -> Vec<U256> {
+        self.state.lock().merges.clone()
+    }
+
+    fn balance(&self, token: &str) -> U256 {
+        self.state.lock().balances.get(&token.parse().unwrap()).copied().unwrap_or_default()
+    }
+}
+
+fn handle(request: &Value, tokens: &[U256], state: &mut ChainState) -> Value {
+    let params = &request["params"];
+    let result = match request["method"].as_str().unwrap() {
+        "eth_chainId" => json!("0x7a69"),
+        "eth_blockNumber" => json!("0x1"),
+        "eth_getTransactionCount" => json!(format!("0x{:x}", state.merges.len())),
+        "eth_estimateGas" => json!("0x30000"),
+        "eth_gasPrice" | "eth_maxPriorityFeePerGas" => json!("0x3b9aca00"),
+        "eth_feeHistory" => json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]],
+        }),
+        "eth_call" => {
+            let input: Bytes = serde_json::from_value(
+                params[0].get("input").or(params[0].get("data")).cloned().unwrap(),
+            )
+            .unwrap();
+            let call = IConditionalTokens::balanceOfCall::abi_decode(&input).expect("only balances are read");
+            state.balance_checks += 1;
+            if let Some((after, credits)) = state.settling.take() {
+                if state.balance_checks >= after {
+                    for (token, amount) in credits {
+                        *state.balances.entry(token).or_default() += amount;
+                    }
+                } else {
+                    state.settling = Some((after, credits));
+                }
+            }
+            let balance = state.balances.get(&call.id).copied().unwrap_or_default();
+            json!(Bytes::from(balance.abi_encode()))
+        }
+        "eth_sendRawTransaction" => {
+            let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+            let tx = TxEnvelope::decode_2718(&mut raw.as_ref()).unwrap();
+            let merge = IConditionalTokens::mergePositionsCall::abi_decode(tx.input()).expect("only merges are sent");
+            for token in tokens {
+                let balance = state.balances.entry(*token).or_default();
+                assert!(*balance >= merge.amount, "merge of tokens not held would revert");
+                *balance -= merge.amount;
+            }
+            state.merges.push(merge.amount);
+            json!(tx.tx_hash())
+        }
+        "eth_getTransactionReceipt" => json!({
+            "transactionHash": params[0],
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(1),
+            "blockNumber": "0x1",
+            "from": PRIVATE_KEY.parse::<PrivateKeySigner>().unwrap().address(),
+            "to": polymarket_arb::api::ctf::CTF_ADDRESS,
+            "contractAddress": null,
+            "gasUsed": "0x20000",
+            "cumulativeGasUsed": "0x20000",
+            "effectiveGasPrice": "0x3b9aca00",
+            "type": "0x2",
+            "status": "0x1",
+            "logs": [],
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+        }),
+        method => panic!("unexpected RPC call {}", method),
+    };
+    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+}
+
+/// `shares` in 6-decimal on-chain units
+fn units(shares: i64) -> U256 {
+    U256::from(shares) * U256::from(1_000_000)
+}
+
+/// Buy opportunity on market 1 (tokens "11"/"12") with YES at 40¢ and NO at 50¢
+fn opportunity() -> ArbitrageOpportunity {
+    let detector = ArbitrageDetector::new(config());
+    detector.load_markets(&[market(1)]);
+    let pair = detector.pair(&market(1).condition_id).unwrap();
+    detector.check_books(&pair, &asks("11", 40), &asks("12", 50)).unwrap()
+}
+
+fn executor(chain: &LocalChain, settle_timeout: Duration) -> Executor {
+    let signer: PrivateKeySigner = PRIVATE_KEY.parse().unwrap();
+    let ctf = CtfClient::new(&signer, CtfConfig {
+        rpc_url: chain.url.clone(),
+        settle_timeout,
+        ..CtfConfig::default()
+    })
+    .unwrap();
+    Executor::new(Arc::new(MockExchange::new(Decimal::from(100)))).with_ctf(ctf)
+}
+
+#[tokio::test]
+async fn merge_is_capped_at_sets_held_on_chain() {
+    // The exchange reported 10 sets, but only 6 NO have reached the wallet
+    let chain = LocalChain::start(["11", "12"], [10, 6]).await;
+
+    let receipt = executor(&chain, Duration::ZERO)
+        .merge_complete_sets(&opportunity(), Decimal::from(10))
+        .await
+        .unwrap();
+
+    assert_eq!(receipt.amount, Decimal::from(6));
+    assert_eq!(chain.merges(), vec![units(6)]);
+    assert_eq!(chain.balance("11"), units(4));
+    assert_eq!(chain.balance("12"), U256::ZERO);
+}
+
+#[tokio::test]
+async fn merge_is_skipped_when_nothing_is_held_on_chain() {
+    let chain = LocalChain::start(["11", "12"], [10, 0]).await;
+
+    let result = executor(&chain, Duration::ZERO)
+        .merge_complete_sets(&opportunity(), Decimal::from(10))
+        .await;
+
+    assert!(result.is_err());
+    assert!(chain.merges().is_empty());
+}
+
+#[tokio::test]
+async fn merge_waits_for_fills_to_settle() {
+    let chain = LocalChain::start(["11", "12"], [10, 0]).await;
+    // NO lands after the first round of balance checks
+    chain.settle_later(3, "12", 10);
+
+    let receipt = executor(&chain, Duration::from_secs(10))
+        .merge_complete_sets(&opportunity(), Decimal::from(10))
+        .await
+        .unwrap();
+
+    assert_eq!(receipt.amount, Decimal::from(10));
+    assert_eq!(chain.merges(), vec![units(10)]);
+}
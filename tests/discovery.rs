@@ -0,0 +1,149 @@
+//! Scheduled discovery of the recurring up/down markets
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+use polymarket_arb::api::{Executor, Market, MockExchange};
+use polymarket_arb::config::Config;
+use polymarket_arb::discovery::{MarketDiscovery, UpDownSeries};
+#[cfg(feature = "mock-server")]
+use polymarket_arb::mock_server::{Fixture, MockServer};
+use polymarket_arb::scanner::ArbitrageScanner;
+
+mod common;
+use common::market;
+
+fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, h, m, s).unwrap()
+}
+
+/// Market `n` for the window of `series` starting at `start`
+fn window_market(n: usize, series: &UpDownSeries, start: DateTime<Utc>) -> Market {
+    Market {
+        market_slug: Some(series.slug(start)),
+        question: Some(format!("{} Up or Down {}", series.asset, start)),
+        ..market(n)
+    }
+}
+
+#[test]
+fn windows_follow_the_schedule() {
+    let btc = UpDownSeries::new("BTC", 15);
+    let now = at(12, 7, 30);
+
+    assert_eq!(btc.window_start(now), at(12, 0, 0));
+    assert_eq!(btc.slug(at(12, 0, 0)), format!("btc-updown-15m-{}", at(12, 0, 0).timestamp()));
+    assert_eq!(btc.to_string(), "BTC 15m");
+    assert_eq!(UpDownSeries::new("eth", 240).slug(at(12, 0, 0)), format!("eth-updown-4h-{}", at(12, 0, 0).timestamp()));
+
+    let windows = btc.windows(now, Duration::minutes(10));
+    let starts: Vec<_> = windows.iter().map(|w| w.start).collect();
+    assert_eq!(starts, vec![at(12, 0, 0), at(12, 15, 0)]);
+    assert_eq!(windows[1].end, at(12, 30, 0));
+
+    assert_eq!(btc.windows(now, Duration::zero()).len(), 1);
+}
+
+#[tokio::test]
+async fn finds_each_window_once_it_opens() {
+    let btc = UpDownSeries::new("btc", 15);
+    let now = at(12, 7, 30);
+    let current = window_market(1, &btc, at(12, 0, 0));
+    let mut next = window_market(2, &btc, at(12, 15, 0));
+    next.accepting_orders = false;
+
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(vec![current.clone(), next.clone()]);
+    let client = Executor::new(exchange.clone());
+    let discovery = MarketDiscovery::new(vec![btc.clone()], Duration::minutes(10));
+
+    let found = discovery.discover(&client, now).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].condition_id, current.condition_id);
+    assert_eq!(discovery.pending(now).len(), 1, "the closed window stays pending");
+
+    next.accepting_orders = true;
+    exchange.set_markets(vec![current, next.clone()]);
+    let found = discovery.discover(&client, now).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].condition_id, next.condition_id);
+    assert!(discovery.discover(&client, now).await.unwrap().is_empty());
+
+    // Once the lookahead reaches it, the window after next comes up
+    let later = at(12, 21, 0);
+    let pending = discovery.pending(later);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].start, at(12, 30, 0));
+}
+
+#[tokio::test]
+async fn scanner_adds_discovered_markets_past_the_filter() {
+    let btc = UpDownSeries::new("btc", 15);
+    let start = btc.window_start(Utc::now());
+    let mut current = window_market(1, &btc, start);
+    // Nothing the crypto filter would pick up
+    current.question = Some("Market 1?".to_string());
+
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(vec![current.clone()]);
+
+    let config = Config {
+        updown_assets: vec!["btc".to_string()],
+        updown_intervals: vec![15],
+        ..Config::default()
+    };
+    let scanner = ArbitrageScanner::new(Executor::new(exchange), config);
+
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 0);
+    assert_eq!(scanner.discover_markets().await.unwrap(), 1);
+    assert_eq!(scanner.detector().pairs()[0].condition_id, current.condition_id);
+    assert_eq!(scanner.discover_markets().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn scanner_skips_denied_discovered_markets() {
+    let btc = UpDownSeries::new("btc", 15);
+    let start = btc.window_start(Utc::now());
+    let (current, next) = (window_market(1, &btc, start), window_market(2, &btc, start + Duration::minutes(15)));
+
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(vec![current.clone(), next.clone()]);
+
+    let mut config = Config {
+        updown_assets: vec!["btc".to_string()],
+        updown_intervals: vec![15],
+        discovery_lookahead_secs: 15 * 60,
+        ..Config::default()
+    };
+    config.market_filter.deny.insert(next.condition_id.clone());
+    let scanner = ArbitrageScanner::new(Executor::new(exchange), config);
+
+    assert_eq!(scanner.discover_markets().await.unwrap(), 1);
+    let pairs = scanner.detector().pairs();
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].condition_id, current.condition_id);
+}
+
+#[cfg(feature = "mock-server")]
+#[tokio::test]
+async fn mock_server_resolves_slugs() {
+    let btc = UpDownSeries::new("btc", 15);
+    let listed = window_market(1, &btc, at(12, 0, 0));
+    let fixture: Fixture = serde_json::from_value(serde_json::json!({ "markets": [listed] })).unwrap();
+    let server = MockServer::start(fixture, 0).await.unwrap();
+
+    let found: serde_json::Value = reqwest::Client::new()
+        .get(format!("{}/markets", server.gamma_host()))
+        .query(&[("slug", btc.slug(at(12, 0, 0))), ("slug", btc.slug(at(12, 15, 0)))])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let found = found.as_array().unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["conditionId"], listed.condition_id);
+}
@@ -0,0 +1,223 @@
+//! Order execution and recovery against the mock exchange
+
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use polymarket_arb::api::{
+    ArbDirection, ArbitrageOpportunity, BasketOpportunity, Executor, Market, MockExchange, OrderBook,
+    OrderBookEntry, RecoveryAction, RecoveryPolicy,
+};
+use polymarket_arb::config::Config;
+use polymarket_arb::scanner::ArbitrageDetector;
+
+mod common;
+use common::market;
+
+fn config() -> Config {
+    Config {
+        enable_multi_outcome: true,
+        enable_sell_side: true,
+        ..common::config()
+    }
+}
+
+fn entry(price: i64) -> Vec<OrderBookEntry> {
+    vec![OrderBookEntry { price: Decimal::new(price, 2), size: Decimal::from(100) }]
+}
+
+/// Book with one ask at `ask` cents and, if given, one bid at `bid` cents
+fn book(token_id: &str, ask: i64, bid: Option<i64>) -> OrderBook {
+    OrderBook {
+        market: String::new(),
+        asset_id: token_id.to_string(),
+        bids: bid.map(entry).unwrap_or_default(),
+        asks: entry(ask),
+        hash: String::new(),
+        timestamp: String::new(),
+    }
+}
+
+/// Opportunity on market 1 ("11"/"12") found in `yes` and `no`, on an exchange holding the same books
+fn pair(yes: OrderBook, no: OrderBook) -> (ArbitrageOpportunity, Arc<MockExchange>) {
+    let market = market(1);
+    let detector = ArbitrageDetector::new(config());
+    detector.load_markets(std::slice::from_ref(&market));
+    let opp = detector.check_books(&detector.pair(&market.condition_id).unwrap(), &yes, &no).unwrap();
+
+    let exchange = Arc::new(MockExchange::new(Decimal::from(100)));
+    exchange.set_markets(vec![market]);
+    exchange.set_book(yes);
+    exchange.set_book(no);
+    (opp, exchange)
+}
+
+/// Buy found with YES at 40¢ and NO at 50¢, each bid two cents under
+fn buy_pair() -> (ArbitrageOpportunity, Arc<MockExchange>) {
+    pair(book("11", 40, Some(38)), book("12", 50, Some(48)))
+}
+
+/// Three-member neg-risk group, one YES token per member ("11", "21", "31")
+fn group_markets() -> Vec<Market> {
+    (1..=3)
+        .map(|n| Market {
+            neg_risk: true,
+            neg_risk_market_id: Some("0xgroup".to_string()),
+            ..market(n)
+        })
+        .collect()
+}
+
+/// Basket found with every YES at 30¢, on an exchange holding the same books
+fn basket() -> (BasketOpportunity, Arc<MockExchange>) {
+    let markets = group_markets();
+    let detector = ArbitrageDetector::new(config());
+    detector.set_group_members(HashMap::from([(
+        "0xgroup".to_string(),
+        markets.iter().map(|m| m.condition_id.clone()).collect(),
+    )]));
+    detector.load_markets(&markets);
+
+    let books: Vec<OrderBook> = ["11", "21", "31"].iter().map(|t| book(t, 30, Some(28))).collect();
+    let group = detector.groups().pop().unwrap();
+    let opp = detector.check_group_books(&group, &books).unwrap();
+
+    let exchange = Arc::new(MockExchange::new(Decimal::from(100)));
+    exchange.set_markets(markets);
+    for book in books {
+        exchange.set_book(book);
+    }
+    (opp, exchange)
+}
+
+fn executor(exchange: Arc<MockExchange>) -> Executor {
+    Executor::new(exchange).with_recovery_policy(RecoveryPolicy {
+        max_retries: 2,
+        retry_delay: Duration::ZERO,
+        ..RecoveryPolicy::default()
+    })
+}
+
+#[tokio::test]
+async fn basket_retries_a_missing_leg_within_the_budget() {
+    let (opp, exchange) = basket();
+    // The third leg moved a cent: past the order's limit, inside the recovery budget
+    exchange.set_book(book("31", 31, Some(28)));
+
+    let execution = executor(exchange.clone()).execute_basket(&opp, Decimal::from(10)).await.unwrap();
+
+    assert!(!execution.legs[2].success);
+    assert_eq!(execution.matched_sets(), Decimal::from(10));
+    assert_eq!(execution.recovery.len(), 1);
+    assert_eq!(execution.recovery[0].exposed_token_id, "31");
+    assert_eq!(execution.recovery[0].action, RecoveryAction::Hedged { price: Decimal::new(31, 2) });
+    assert_eq!(exchange.shares("31"), Decimal::from(10));
+    assert_eq!(execution.cost.iter().sum::<Decimal>(), Decimal::new(91, 1));
+}
+
+#[tokio::test]
+async fn basket_unwinds_filled_legs_when_retries_fail() {
+    let (opp, exchange) = basket();
+    exchange.reject_orders("31");
+
+    let execution = executor(exchange.clone()).execute_basket(&opp, Decimal::from(10)).await.unwrap();
+
+    assert!(execution.is_flat());
+    let actions: Vec<(String, RecoveryAction)> = execution
+        .recovery
+        .iter()
+        .map(|r| (r.exposed_token_id.clone(), r.action.clone()))
+        .collect();
+    let unwound = RecoveryAction::Unwound { price: Decimal::new(28, 2) };
+    assert_eq!(actions, vec![("11".to_string(), unwound.clone()), ("21".to_string(), unwound)]);
+
+    // Bought at 30¢ and sold at 28¢ on both legs
+    let realized: Decimal = execution.recovery.iter().map(|r| r.realized_pnl).sum();
+    assert_eq!(realized, Decimal::new(-4, 1));
+    assert_eq!(exchange.balance(), Decimal::new(996, 1));
+}
+
+#[tokio::test]
+async fn basket_leg_that_cannot_be_unwound_stays_held() {
+    let (opp, exchange) = basket();
+    exchange.reject_orders("31");
+    exchange.set_book(book("21", 30, None));
+
+    let execution = executor(exchange.clone()).execute_basket(&opp, Decimal::from(10)).await.unwrap();
+
+    assert_eq!(execution.held, vec![Decimal::ZERO, Decimal::from(10), Decimal::ZERO]);
+    assert_eq!(execution.cost[1], Decimal::from(3));
+    assert_eq!(execution.recovery[1].action, RecoveryAction::Unhedged);
+    assert_eq!(exchange.shares("21"), Decimal::from(10));
+}
+
+#[tokio::test]
+async fn pair_fills_both_legs() {
+    let (opp, exchange) = buy_pair();
+
+    let execution = executor(exchange.clone()).execute_arbitrage(&opp, Decimal::from(10)).await.unwrap();
+
+    assert!(execution.yes.success && execution.no.success);
+    assert!(execution.recovery.is_none());
+    assert_eq!(execution.matched_sets(Decimal::from(10)), Decimal::from(10));
+    assert_eq!(exchange.shares("11"), Decimal::from(10));
+    assert_eq!(exchange.shares("12"), Decimal::from(10));
+    assert_eq!(exchange.balance(), Decimal::from(91));
+}
+
+#[tokio::test]
+async fn pair_retries_a_rejected_leg_within_the_budget() {
+    let (opp, exchange) = buy_pair();
+    // NO moved a cent: the first order misses, the recovery budget still covers it
+    exchange.set_book(book("12", 51, Some(48)));
+
+    let execution = executor(exchange.clone()).execute_arbitrage(&opp, Decimal::from(10)).await.unwrap();
+
+    assert!(execution.yes.success && !execution.no.success);
+    let recovery = execution.recovery.as_ref().unwrap();
+    assert_eq!(recovery.action, RecoveryAction::Hedged { price: Decimal::new(51, 2) });
+    assert_eq!(recovery.exposed_token_id, "11");
+    assert_eq!(execution.matched_sets(Decimal::from(10)), Decimal::from(10));
+    assert_eq!(exchange.shares("12"), Decimal::from(10));
+    assert_eq!(exchange.balance(), Decimal::new(909, 1));
+}
+
+#[tokio::test]
+async fn pair_unwinds_the_filled_leg_when_the_other_is_rejected() {
+    let (opp, exchange) = buy_pair();
+    exchange.reject_orders("12");
+
+    let execution = executor(exchange.clone()).execute_arbitrage(&opp, Decimal::from(10)).await.unwrap();
+
+    let recovery = execution.recovery.as_ref().unwrap();
+    assert_eq!(recovery.attempts, 2);
+    assert_eq!(recovery.action, RecoveryAction::Unwound { price: Decimal::new(38, 2) });
+    assert_eq!(recovery.realized_pnl, Decimal::new(-2, 1));
+    assert_eq!(execution.matched_sets(Decimal::from(10)), Decimal::ZERO);
+    assert_eq!(exchange.shares("11"), Decimal::ZERO);
+    assert_eq!(exchange.balance(), Decimal::new(998, 1));
+}
+
+#[tokio::test]
+async fn sell_pair_sells_held_sets_into_both_bids() {
+    let (buy, exchange) = buy_pair();
+    let executor = executor(exchange.clone());
+    executor.execute_arbitrage(&buy, Decimal::from(10)).await.unwrap();
+
+    // Bids now sum to $1.05 while the asks stay above $1
+    let (yes, no) = (book("11", 62, Some(60)), book("12", 47, Some(45)));
+    let (sell, _) = pair(yes.clone(), no.clone());
+    assert_eq!(sell.direction, ArbDirection::SellBoth);
+    exchange.set_book(yes);
+    exchange.set_book(no);
+
+    let execution = executor.execute_sell_arbitrage(&sell, Decimal::from(10)).await.unwrap();
+
+    assert!(execution.yes.success && execution.no.success);
+    assert!(execution.recovery.is_none());
+    assert_eq!(exchange.shares("11"), Decimal::ZERO);
+    assert_eq!(exchange.shares("12"), Decimal::ZERO);
+    // Bought for $9, sold for $10.50
+    assert_eq!(exchange.balance(), Decimal::new(1015, 1));
+}
@@ -0,0 +1,193 @@
+//! Trading cutoffs before markets end, and eviction of markets that can't be traded
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use polymarket_arb::api::{Executor, Market, MarketListing, MockExchange};
+use polymarket_arb::config::Config;
+use polymarket_arb::scanner::{ArbitrageDetector, ArbitrageScanner};
+
+mod common;
+use common::{asks, market};
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, 12, 0, 0).unwrap()
+}
+
+fn ending(n: usize, end: DateTime<Utc>) -> Market {
+    Market { end_date_iso: Some(end.to_rfc3339()), ..market(n) }
+}
+
+fn config() -> Config {
+    Config {
+        expiry_cutoff_secs: 60,
+        market_recheck_secs: 0,
+        ..common::config()
+    }
+}
+
+#[test]
+fn pairs_carry_end_and_game_start() {
+    let detector = ArbitrageDetector::new(config());
+    detector.set_time(now());
+    let game = Market {
+        game_start_time: Some((now() + Duration::hours(1)).to_rfc3339()),
+        ..ending(1, now() + Duration::hours(3))
+    };
+    detector.load_markets(&[game]);
+
+    let pair = detector.pair(&market(1).condition_id).unwrap();
+    assert_eq!(pair.end_time, Some(now() + Duration::hours(3)));
+    assert_eq!(pair.game_start_time, Some(now() + Duration::hours(1)));
+    assert_eq!(pair.closes_at(), Some(now() + Duration::hours(1)));
+}
+
+#[test]
+fn stops_trading_within_the_cutoff() {
+    let detector = ArbitrageDetector::new(config());
+    detector.set_time(now());
+    detector.load_markets(&[ending(1, now() + Duration::minutes(5))]);
+    let pair = detector.pair(&market(1).condition_id).unwrap();
+    let (yes, no) = (asks("11", 45), asks("12", 50));
+
+    detector.set_time(now() + Duration::minutes(3));
+    assert!(detector.check_books(&pair, &yes, &no).is_some());
+
+    detector.set_time(now() + Duration::seconds(250));
+    assert!(detector.check_books(&pair, &yes, &no).is_none(), "50s before the end");
+
+    // Markets already inside the cutoff aren't cached in the first place
+    detector.load_markets(&[ending(2, now() + Duration::minutes(4))]);
+    assert!(detector.pair(&market(2).condition_id).is_none());
+}
+
+#[tokio::test]
+async fn refresh_evicts_closed_and_expiring_markets() {
+    let long = ending(1, now() + Duration::hours(1));
+    let short = ending(2, now() + Duration::minutes(10));
+    let mut closed = market(3);
+
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(vec![long.clone(), short.clone(), closed.clone()]);
+    let scanner = ArbitrageScanner::new(Executor::new(exchange.clone()), config());
+    let detector = scanner.detector();
+
+    detector.set_time(now());
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 3);
+
+    // Market 3 closes and drops out of the listing; market 2 nears its end
+    closed.closed = true;
+    exchange.set_markets(vec![long.clone(), short.clone(), closed.clone()]);
+    detector.set_time(now() + Duration::seconds(9 * 60 + 30));
+    scanner.refresh_markets().await.unwrap();
+
+    let cached: Vec<String> = detector.pairs().into_iter().map(|p| p.condition_id).collect();
+    assert_eq!(cached, vec![long.condition_id.clone()]);
+    let mut watched = detector.get_watched_token_ids();
+    watched.sort();
+    assert_eq!(watched, vec!["11", "12"]);
+
+    // A stale listing can't bring an evicted market back
+    closed.closed = false;
+    assert_eq!(detector.load_markets(&[closed]), 0);
+}
+
+#[tokio::test]
+async fn refresh_suspends_paused_markets_until_they_accept_orders() {
+    let mut members: Vec<Market> = (1..=3)
+        .map(|n| Market {
+            neg_risk: true,
+            neg_risk_market_id: Some("0xgroup".to_string()),
+            ..market(n)
+        })
+        .collect();
+
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(members.clone());
+    let config = Config { enable_multi_outcome: true, ..config() };
+    let scanner = ArbitrageScanner::new(Executor::new(exchange.clone()), config);
+    let detector = scanner.detector();
+    scanner.refresh_markets().await.unwrap();
+    assert_eq!(detector.groups().len(), 1);
+
+    // Member 2 pauses: it and its group are dropped...
+    members[1].accepting_orders = false;
+    exchange.set_markets(members.clone());
+    scanner.refresh_markets().await.unwrap();
+    assert_eq!(detector.pairs().len(), 2);
+    assert!(detector.groups().is_empty());
+
+    // ...until it accepts orders again
+    members[1].accepting_orders = true;
+    exchange.set_markets(members);
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 3);
+    assert_eq!(detector.groups().len(), 1);
+}
+
+#[tokio::test]
+async fn refresh_rechecks_markets_an_incremental_listing_kept() {
+    let mut markets: Vec<Market> = (1..=3).map(market).collect();
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(markets.clone());
+    exchange.set_page_size(2);
+    let client = Executor::new(exchange.clone()).with_market_listing(MarketListing {
+        max_pages: 10,
+        full_refresh: std::time::Duration::from_secs(3600),
+    });
+    let scanner = ArbitrageScanner::new(client, config());
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 3);
+
+    // The incremental refresh only reads the last page, so its copy of market 1 is stale
+    markets[0].closed = true;
+    exchange.set_markets(markets);
+    scanner.refresh_markets().await.unwrap();
+
+    let mut cached: Vec<String> = scanner.detector().pairs().into_iter().map(|p| p.condition_id).collect();
+    cached.sort();
+    assert_eq!(cached, vec![market(2).condition_id, market(3).condition_id]);
+}
+
+#[test]
+fn evicting_a_member_drops_its_group() {
+    let detector = ArbitrageDetector::new(Config { enable_multi_outcome: true, ..config() });
+    let members: Vec<Market> = (1..=3)
+        .map(|n| Market {
+            neg_risk: true,
+            neg_risk_market_id: Some("0xgroup".to_string()),
+            ..market(n)
+        })
+        .collect();
+    detector.set_group_members(HashMap::from([(
+        "0xgroup".to_string(),
+        members.iter().map(|m| m.condition_id.clone()).collect(),
+    )]));
+    detector.load_markets(&members);
+    assert_eq!(detector.groups().len(), 1);
+
+    detector.evict_markets(&[market(2).condition_id]);
+    assert!(detector.groups().is_empty());
+    assert!(detector.group_for_token("11").is_none());
+
+    // The remaining members no longer make a complete set
+    detector.load_markets(&members);
+    assert!(detector.groups().is_empty());
+    assert_eq!(detector.pairs().len(), 2);
+}
+
+#[tokio::test]
+async fn refresh_trusts_markets_confirmed_recently() {
+    let mut markets: Vec<Market> = (1..=2).map(market).collect();
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(markets.clone());
+    let config = Config { market_recheck_secs: 600, ..config() };
+    let scanner = ArbitrageScanner::new(Executor::new(exchange.clone()), config);
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 2);
+
+    // Market 1 was listed moments ago, so it isn't looked up again yet
+    markets[0].closed = true;
+    exchange.set_markets(markets);
+    scanner.refresh_markets().await.unwrap();
+    assert_eq!(scanner.detector().pairs().len(), 2);
+}
@@ -0,0 +1,56 @@
+//! Sizing trades to the USDC the wallet can spend on each exchange
+
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+use polymarket_arb::api::{ArbitrageOpportunity, Executor, Market, MockExchange};
+use polymarket_arb::scanner::ArbitrageDetector;
+
+mod common;
+use common::{asks, config, market};
+
+/// Buy opportunity on `market` with YES at 40¢ and NO at 50¢
+fn opportunity(market: Market) -> ArbitrageOpportunity {
+    let detector = ArbitrageDetector::new(config());
+    let condition_id = market.condition_id.clone();
+    let (yes, no) = (market.tokens[0].token_id.clone(), market.tokens[1].token_id.clone());
+    detector.load_markets(&[market]);
+    let pair = detector.pair(&condition_id).unwrap();
+    detector.check_books(&pair, &asks(&yes, 40), &asks(&no, 50)).unwrap()
+}
+
+#[tokio::test]
+async fn funding_covers_fees() {
+    // 2% fees: 0.8¢ on YES and 1¢ on NO, so a set costs 91.8¢
+    let opp = opportunity(Market { taker_base_fee: Decimal::from(200), ..market(1) });
+    let exchange = Arc::new(MockExchange::new(Decimal::from(9)));
+    let executor = Executor::new(exchange);
+
+    // $9 covers the 90¢ notional of 10 sets but not their fees
+    let size = executor.fundable_size(&opp, Decimal::from(10)).await.unwrap();
+    assert_eq!(size, Decimal::new(980, 2));
+    assert!(opp.fill_cost(size) <= Decimal::from(9));
+}
+
+#[tokio::test]
+async fn funding_uses_the_allowance_of_the_market_exchange() {
+    let neg_risk = opportunity(Market {
+        neg_risk: true,
+        neg_risk_market_id: Some("0xgroup".to_string()),
+        ..market(1)
+    });
+    let standard = opportunity(market(2));
+
+    let exchange = Arc::new(MockExchange::new(Decimal::from(100)));
+    exchange.set_allowances(Decimal::MAX, Decimal::ZERO);
+    let executor = Executor::new(exchange.clone());
+
+    // A missing neg-risk approval doesn't hold back standard markets
+    assert_eq!(executor.fundable_size(&standard, Decimal::from(10)).await.unwrap(), Decimal::from(10));
+    assert!(executor.fundable_size(&neg_risk, Decimal::from(10)).await.unwrap().is_zero());
+
+    exchange.set_allowances(Decimal::from(5), Decimal::MAX);
+    let executor = Executor::new(exchange);
+    assert_eq!(executor.fundable_size(&neg_risk, Decimal::from(10)).await.unwrap(), Decimal::from(10));
+    assert_eq!(executor.fundable_size(&standard, Decimal::from(10)).await.unwrap(), Decimal::new(555, 2));
+}
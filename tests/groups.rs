@@ -0,0 +1,76 @@
+//! Neg-risk groups are only traded as complete sets
+
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use polymarket_arb::api::{Executor, Market, MockExchange};
+use polymarket_arb::config::Config;
+use polymarket_arb::scanner::{ArbitrageDetector, ArbitrageScanner};
+
+mod common;
+use common::market;
+
+fn config() -> Config {
+    Config { enable_multi_outcome: true, ..common::config() }
+}
+
+/// Binary neg-risk markets `1..=n` of group "0xgroup"
+fn members(n: usize) -> Vec<Market> {
+    (1..=n)
+        .map(|i| Market {
+            neg_risk: true,
+            neg_risk_market_id: Some("0xgroup".to_string()),
+            ..market(i)
+        })
+        .collect()
+}
+
+fn membership(markets: &[Market]) -> HashMap<String, Vec<String>> {
+    HashMap::from([(
+        "0xgroup".to_string(),
+        markets.iter().map(|m| m.condition_id.clone()).collect(),
+    )])
+}
+
+#[test]
+fn group_missing_a_member_is_skipped() {
+    let all = members(3);
+    let detector = ArbitrageDetector::new(config());
+    detector.set_group_members(membership(&all));
+
+    // Buying the YES of two of three outcomes isn't a complete set
+    detector.load_markets(&all[..2]);
+    assert!(detector.groups().is_empty());
+    assert_eq!(detector.pairs().len(), 2);
+
+    detector.load_markets(&all);
+    let groups = detector.groups();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].outcomes.len(), 3);
+}
+
+#[test]
+fn group_with_unknown_members_is_skipped() {
+    let detector = ArbitrageDetector::new(config());
+    detector.load_markets(&members(3));
+    assert!(detector.groups().is_empty());
+}
+
+#[tokio::test]
+async fn refresh_skips_groups_cut_by_max_markets() {
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(members(3));
+    let scanner = ArbitrageScanner::new(
+        Executor::new(exchange.clone()),
+        Config { max_markets: 2, ..config() },
+    );
+
+    scanner.refresh_markets().await.unwrap();
+    assert_eq!(scanner.detector().pairs().len(), 2);
+    assert!(scanner.detector().groups().is_empty());
+
+    let scanner = ArbitrageScanner::new(Executor::new(exchange), config());
+    scanner.refresh_markets().await.unwrap();
+    assert_eq!(scanner.detector().groups().len(), 1);
+}
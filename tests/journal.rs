@@ -0,0 +1,210 @@
+//! Journal writes read back from an in-memory database
+
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use polymarket_arb::api::{
+    ArbitrageOpportunity, BasketOpportunity, CtfReceipt, Market, OrderBook, OrderRequest, OrderResponse,
+    RecoveryAction, RecoveryReport, Side,
+};
+use polymarket_arb::config::Config;
+use polymarket_arb::journal::{Journal, TradeStatus};
+use polymarket_arb::redeemer::Redemption;
+use polymarket_arb::scanner::ArbitrageDetector;
+
+mod common;
+use common::{asks, config, market};
+
+fn journal() -> Journal {
+    Journal::open(":memory:").unwrap()
+}
+
+fn detector() -> ArbitrageDetector {
+    ArbitrageDetector::new(Config { enable_multi_outcome: true, ..config() })
+}
+
+/// Buy opportunity on market `n` with YES at 40¢ and NO at 50¢
+fn pair(n: usize) -> ArbitrageOpportunity {
+    let detector = detector();
+    let market = market(n);
+    detector.load_markets(std::slice::from_ref(&market));
+    let pair = detector.pair(&market.condition_id).unwrap();
+    detector
+        .check_books(&pair, &asks(&market.tokens[0].token_id, 40), &asks(&market.tokens[1].token_id, 50))
+        .unwrap()
+}
+
+/// Basket over a three-member neg-risk group with every YES at 30¢
+fn basket() -> BasketOpportunity {
+    let markets: Vec<Market> = (1..=3)
+        .map(|n| Market {
+            neg_risk: true,
+            neg_risk_market_id: Some("0xgroup".to_string()),
+            ..market(n)
+        })
+        .collect();
+    let detector = detector();
+    detector.set_group_members(HashMap::from([(
+        "0xgroup".to_string(),
+        markets.iter().map(|m| m.condition_id.clone()).collect(),
+    )]));
+    detector.load_markets(&markets);
+    let books: Vec<OrderBook> = ["11", "21", "31"].iter().map(|t| asks(t, 30)).collect();
+    detector.check_group_books(&detector.groups().pop().unwrap(), &books).unwrap()
+}
+
+/// Record a filled order of `shares` at `price` cents
+fn fill(journal: &Journal, opportunity_id: &str, condition_id: &str, token_id: &str, side: Side, shares: i64, price: i64) {
+    let (shares, usdc) = (Decimal::from(shares), Decimal::new(shares * price, 2));
+    let (making, taking) = match side {
+        Side::Buy => (usdc, shares),
+        Side::Sell => (shares, usdc),
+    };
+    journal.record_order(
+        opportunity_id,
+        condition_id,
+        &OrderRequest {
+            token_id: token_id.to_string(),
+            label: token_id.to_string(),
+            side,
+            price: Decimal::new(price, 2),
+            size: shares,
+        },
+        &OrderResponse {
+            success: true,
+            error_msg: None,
+            order_id: Some(format!("order-{}", token_id)),
+            transaction_hashes: None,
+            making_amount: Some(making),
+            taking_amount: Some(taking),
+        },
+    );
+}
+
+fn receipt(condition_id: &str, amount: i64) -> CtfReceipt {
+    CtfReceipt {
+        condition_id: condition_id.to_string(),
+        amount: Decimal::from(amount),
+        tx_hash: "0xabc".to_string(),
+        gas_used: 21_000,
+    }
+}
+
+fn recovery(action: RecoveryAction, realized_pnl: Decimal) -> RecoveryReport {
+    RecoveryReport {
+        action,
+        exposed_token_id: "11".to_string(),
+        size: Decimal::from(10),
+        attempts: 1,
+        realized_pnl,
+        orders: Vec::new(),
+    }
+}
+
+#[test]
+fn summary_totals_every_table() {
+    let journal = journal();
+    let (first, second) = (pair(1), pair(2));
+    journal.record_opportunity(&first);
+    journal.record_opportunity(&first); // Seen again on the next scan
+    journal.record_opportunity(&second);
+    journal.record_trade(&first.id, Decimal::from(10), TradeStatus::Filled, Decimal::ONE);
+    journal.record_trade(&second.id, Decimal::from(10), TradeStatus::Partial, Decimal::ONE);
+
+    journal.record_recovery(&second.id, &recovery(RecoveryAction::Unwound { price: Decimal::new(38, 2) }, Decimal::new(-2, 1)));
+    // Hedged sets realize when merged or redeemed, not on recovery
+    journal.record_recovery(&second.id, &recovery(RecoveryAction::Hedged { price: Decimal::new(52, 2) }, Decimal::new(5, 1)));
+    journal.record_merge(&first.id, &receipt(&first.market_id, 10), Decimal::ONE);
+    journal.record_redemption(&Redemption {
+        id: second.market_id.clone(),
+        description: "Yes / No".to_string(),
+        payout: Decimal::from(10),
+        cost: Decimal::new(95, 1),
+        opened_at: Utc::now(),
+        tx_hashes: vec!["0xdef".to_string()],
+    });
+
+    let summary = journal.summary().unwrap();
+    assert_eq!(summary.opportunities, 2);
+    assert_eq!(summary.trades, 2);
+    assert_eq!(summary.filled, 1);
+    assert_eq!(summary.expected_profit, Decimal::ONE);
+    assert_eq!(summary.realized_pnl, Decimal::new(13, 1));
+}
+
+#[test]
+fn realized_pnl_counts_only_since_the_given_time() {
+    let journal = journal();
+    let opp = pair(1);
+    journal.record_merge(&opp.id, &receipt(&opp.market_id, 10), Decimal::ONE);
+    journal.record_recovery(&opp.id, &recovery(RecoveryAction::Unwound { price: Decimal::new(38, 2) }, Decimal::new(-3, 1)));
+
+    assert_eq!(journal.realized_pnl_since(Utc::now() - Duration::hours(1)).unwrap(), Decimal::new(7, 1));
+    assert_eq!(journal.realized_pnl_since(Utc::now() + Duration::hours(1)).unwrap(), Decimal::ZERO);
+}
+
+#[test]
+fn held_positions_replay_fills_and_merges() {
+    let journal = journal();
+    let opp = pair(1);
+    journal.record_opportunity(&opp);
+    fill(&journal, &opp.id, &opp.market_id, "11", Side::Buy, 10, 40);
+    fill(&journal, &opp.id, &opp.market_id, "12", Side::Buy, 10, 50);
+    journal.record_merge(&opp.id, &receipt(&opp.market_id, 6), Decimal::new(6, 1));
+
+    let positions = journal.held_positions().unwrap();
+    assert_eq!(positions.len(), 1);
+    let position = &positions[0];
+    assert_eq!(position.id, opp.market_id);
+    let legs: Vec<(&str, Decimal)> = position.legs.iter().map(|l| (l.token_id.as_str(), l.shares)).collect();
+    assert_eq!(legs, vec![("11", Decimal::from(4)), ("12", Decimal::from(4))]);
+    assert_eq!(position.cost, Decimal::new(36, 1));
+}
+
+#[test]
+fn held_positions_keep_basket_legs_on_their_own_markets() {
+    let journal = journal();
+    let opp = basket();
+    journal.record_basket_opportunity(&opp);
+    for leg in &opp.legs {
+        fill(&journal, &opp.id, &leg.condition_id, &leg.token_id, Side::Buy, 10, 30);
+    }
+    // One leg was unwound by recovery
+    let unwound = &opp.legs[2];
+    fill(&journal, &opp.id, &unwound.condition_id, &unwound.token_id, Side::Sell, 10, 28);
+
+    let positions = journal.held_positions().unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].id, opp.group_id);
+    let legs: Vec<(&str, &str)> = positions[0]
+        .legs
+        .iter()
+        .map(|l| (l.condition_id.as_str(), l.token_id.as_str()))
+        .collect();
+    assert_eq!(legs, vec![(opp.legs[0].condition_id.as_str(), "11"), (opp.legs[1].condition_id.as_str(), "21")]);
+    assert_eq!(positions[0].cost, Decimal::from(6));
+}
+
+#[test]
+fn held_positions_skip_redeemed_and_closed_positions() {
+    let journal = journal();
+    let (redeemed, sold) = (pair(1), pair(2));
+    for opp in [&redeemed, &sold] {
+        journal.record_opportunity(opp);
+        fill(&journal, &opp.id, &opp.market_id, &opp.yes_token_id, Side::Buy, 10, 40);
+        fill(&journal, &opp.id, &opp.market_id, &opp.no_token_id, Side::Buy, 10, 50);
+    }
+    journal.record_redemption(&Redemption {
+        id: redeemed.market_id.clone(),
+        description: "Yes / No".to_string(),
+        payout: Decimal::from(10),
+        cost: Decimal::from(9),
+        opened_at: Utc::now(),
+        tx_hashes: Vec::new(),
+    });
+    fill(&journal, &sold.id, &sold.market_id, &sold.yes_token_id, Side::Sell, 10, 60);
+    fill(&journal, &sold.id, &sold.market_id, &sold.no_token_id, Side::Sell, 10, 45);
+
+    assert!(journal.held_positions().unwrap().is_empty());
+}
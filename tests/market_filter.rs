@@ -0,0 +1,141 @@
+//! Market selection filters, on their own and applied by the scanner
+
+use chrono::{Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+use polymarket_arb::api::{Executor, Market, MarketStats, MockExchange};
+use polymarket_arb::config::Config;
+use polymarket_arb::market_filter::{pattern, MarketFilter};
+use polymarket_arb::scanner::ArbitrageScanner;
+
+mod common;
+use common::market;
+
+fn asking(n: usize, question: &str) -> Market {
+    Market { question: Some(question.to_string()), ..market(n) }
+}
+
+fn kept(filter: &MarketFilter, markets: Vec<Market>) -> Vec<String> {
+    filter
+        .apply(markets, Utc::now())
+        .into_iter()
+        .filter_map(|m| m.question)
+        .collect()
+}
+
+#[test]
+fn crypto_filter_matches_whole_words() {
+    let markets = vec![
+        asking(1, "Will BTC be above $100k on Friday?"),
+        asking(2, "Ethereum Up or Down - 3PM ET"),
+        asking(3, "Will ETH flip BTC in 2030?"),
+        asking(4, "Will the method pass whether or not it rains?"),
+        asking(5, "Will solar capacity double by 2030?"),
+    ];
+
+    assert_eq!(
+        kept(&MarketFilter::crypto(), markets),
+        vec![
+            "Will BTC be above $100k on Friday?",
+            "Ethereum Up or Down - 3PM ET",
+            "Will ETH flip BTC in 2030?",
+        ]
+    );
+}
+
+#[test]
+fn text_filters_combine() {
+    let filter = MarketFilter {
+        question_include: Some(pattern("up or down").unwrap()),
+        question_exclude: Some(pattern(r"\bxrp\b").unwrap()),
+        slug_exclude: Some(pattern("^skip-").unwrap()),
+        ..MarketFilter::default()
+    };
+    let mut skipped = asking(3, "Solana Up or Down");
+    skipped.market_slug = Some("skip-this-one".to_string());
+
+    let markets = vec![
+        asking(1, "Bitcoin Up or Down"),
+        asking(2, "XRP Up or Down"),
+        skipped,
+        asking(4, "Will it rain?"),
+    ];
+    assert_eq!(kept(&filter, markets), vec!["Bitcoin Up or Down"]);
+}
+
+#[test]
+fn tags_and_category_filters() {
+    let filter = MarketFilter {
+        tags: vec!["crypto".to_string()],
+        exclude_tags: vec!["hide".to_string()],
+        ..MarketFilter::default()
+    };
+    let tagged = Market { tags: vec!["Crypto".to_string()], ..asking(1, "tagged") };
+    let categorized = Market { category: Some("crypto".to_string()), ..asking(2, "categorized") };
+    let hidden = Market { tags: vec!["crypto".to_string(), "hide".to_string()], ..asking(3, "hidden") };
+    let untagged = asking(4, "untagged");
+
+    assert_eq!(kept(&filter, vec![tagged, categorized, hidden, untagged]), vec!["tagged", "categorized"]);
+}
+
+#[test]
+fn end_date_window() {
+    let now = Utc.with_ymd_and_hms(2030, 1, 1, 12, 0, 0).unwrap();
+    let ending = |n: usize, end: Option<String>| Market { end_date_iso: end, ..asking(n, &n.to_string()) };
+    let filter = MarketFilter {
+        min_time_to_end: Some(Duration::minutes(5)),
+        max_time_to_end: Some(Duration::hours(24)),
+        ..MarketFilter::default()
+    };
+
+    let markets = vec![
+        ending(1, Some((now + Duration::minutes(2)).to_rfc3339())),
+        ending(2, Some((now + Duration::minutes(15)).to_rfc3339())),
+        ending(3, Some("2030-01-02".to_string())),
+        ending(4, Some("2031-01-01T00:00:00Z".to_string())),
+        ending(5, None),
+    ];
+    let kept: Vec<String> = filter.apply(markets, now).into_iter().filter_map(|m| m.question).collect();
+    assert_eq!(kept, vec!["2", "3"]);
+}
+
+#[test]
+fn allow_and_deny_lists() {
+    let markets: Vec<Market> = (1..=4).map(|n| asking(n, &n.to_string())).collect();
+    let id = |n: usize| market(n).condition_id.to_lowercase();
+
+    let deny = MarketFilter { deny: [id(2)].into_iter().collect(), ..MarketFilter::default() };
+    assert_eq!(kept(&deny, markets.clone()), vec!["1", "3", "4"]);
+
+    let allow = MarketFilter {
+        allow: [id(1), id(2)].into_iter().collect(),
+        deny: [id(2)].into_iter().collect(),
+        ..MarketFilter::default()
+    };
+    assert_eq!(kept(&allow, markets), vec!["1"]);
+}
+
+#[tokio::test]
+async fn scanner_applies_filters_and_stats() {
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets(vec![
+        asking(1, "Bitcoin Up or Down"),
+        asking(2, "Ethereum Up or Down"),
+        asking(3, "Will it rain whether or not?"),
+    ]);
+    for (n, liquidity) in [(1, 5_000), (2, 50)] {
+        exchange.set_market_stats(MarketStats {
+            condition_id: market(n).condition_id,
+            liquidity: Decimal::from(liquidity),
+            volume: Decimal::from(10_000),
+        });
+    }
+
+    let mut config = Config::default();
+    config.market_filter.min_liquidity = Some(Decimal::from(1_000));
+    let scanner = ArbitrageScanner::new(Executor::new(exchange), config);
+
+    assert_eq!(scanner.refresh_markets().await.unwrap(), 1);
+    assert_eq!(scanner.detector().pairs()[0].condition_id, market(1).condition_id);
+}
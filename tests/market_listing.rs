@@ -0,0 +1,60 @@
+//! Cursor pagination and incremental refresh of the markets listing
+
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+
+use polymarket_arb::api::{Executor, Market, MarketListing, MockExchange};
+
+mod common;
+use common::market;
+
+fn executor(exchange: &Arc<MockExchange>, max_pages: usize, full_refresh: Duration) -> Executor {
+    Executor::new(exchange.clone()).with_market_listing(MarketListing { max_pages, full_refresh })
+}
+
+#[tokio::test]
+async fn follows_the_cursor_across_pages() {
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets((0..7).map(market).collect());
+    exchange.set_page_size(3);
+
+    let markets = executor(&exchange, 10, Duration::from_secs(3600)).get_markets().await.unwrap();
+    assert_eq!(markets.len(), 7);
+}
+
+#[tokio::test]
+async fn page_cap_carries_on_at_the_next_refresh() {
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets((0..7).map(market).collect());
+    exchange.set_page_size(2);
+    let client = executor(&exchange, 2, Duration::from_secs(3600));
+
+    assert_eq!(client.get_markets().await.unwrap().len(), 4);
+    assert_eq!(client.get_markets().await.unwrap().len(), 7);
+}
+
+#[tokio::test]
+async fn incremental_refresh_picks_up_new_markets() {
+    let exchange = Arc::new(MockExchange::new(Decimal::ZERO));
+    exchange.set_markets((0..5).map(market).collect());
+    exchange.set_page_size(2);
+    let client = executor(&exchange, 10, Duration::from_secs(3600));
+    assert_eq!(client.get_markets().await.unwrap().len(), 5);
+
+    // An early market that closed keeps its old copy until the next full listing,
+    // but only the pages actually read are confirmed...
+    let mut markets: Vec<Market> = (0..6).map(market).collect();
+    markets[0].closed = true;
+    exchange.set_markets(markets);
+    let listed = client.get_markets().await.unwrap();
+    assert_eq!(listed.len(), 6);
+    assert!(listed.iter().any(|m| m.condition_id == market(5).condition_id));
+    let confirmed = client.confirmed_market_ids();
+    assert!(confirmed.contains(&market(5).condition_id));
+    assert!(!confirmed.contains(&market(0).condition_id));
+
+    // ...which a zero interval forces every time
+    let client = executor(&exchange, 10, Duration::ZERO);
+    assert_eq!(client.get_markets().await.unwrap().len(), 5);
+}
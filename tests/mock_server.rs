@@ -0,0 +1,210 @@
+//! The bundled mock server, and end-to-end runs of the bot against it in both feed modes
+
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use polymarket_arb::api::{Exchange, Side};
+use polymarket_arb::mock_server::{Fixture, MockServer};
+use polymarket_arb::orderbook::OrderBookStore;
+use polymarket_arb::websocket::WsClient;
+
+/// Well-known development key; never holds funds
+const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+const YES_TOKEN: &str = "1001";
+const NO_TOKEN: &str = "1002";
+
+fn fixture(name: &str) -> Fixture {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name);
+    Fixture::load(path).expect("fixture should load")
+}
+
+/// Run the bot against `server` until both legs of an arbitrage have been
+/// posted, then stop it through the kill file
+async fn run_bot(server: &MockServer, websocket: bool) {
+    let dir = std::env::temp_dir().join(format!("polymarket-arb-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let kill_file = dir.join("STOP");
+
+    let mut bot = Command::new(env!("CARGO_BIN_EXE_polymarket_arb"))
+        .current_dir(&dir)
+        .env_clear()
+        .env("POLYMARKET_PRIVATE_KEY", PRIVATE_KEY)
+        .env("CLOB_HOST", server.clob_host())
+        .env("DATA_API_HOST", server.data_api_host())
+        .env("WS_URL", server.ws_url())
+        .env("GAMMA_HOST", server.gamma_host())
+        .env("USE_WEBSOCKET", websocket.to_string())
+        .env("DRY_RUN", "false")
+        .env("CRYPTO_ONLY", "false")
+        .env("POLL_INTERVAL_MS", "200")
+        .env("ORDER_SIZE", "10")
+        .env("KILL_SWITCH_FILE", &kill_file)
+        .env("JOURNAL_PATH", dir.join("journal.db"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("bot should start");
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while server.exchange().orders().len() < 2 {
+        if let Some(status) = bot.try_wait().unwrap() {
+            panic!("bot exited before trading: {}", status);
+        }
+        assert!(Instant::now() < deadline, "bot didn't trade within 30s");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    std::fs::write(&kill_file, "").unwrap();
+    let status = tokio::time::timeout(Duration::from_secs(30), bot.wait())
+        .await
+        .expect("bot should stop after the kill file appears")
+        .unwrap();
+    assert!(status.success(), "bot exited with {}", status);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// Both legs bought, in matching size, paid for out of the balance
+fn assert_bought_sets(server: &MockServer) {
+    let exchange = server.exchange();
+    let filled: Vec<_> = exchange.orders().into_iter().filter(|(_, r)| r.success).collect();
+    for token in [YES_TOKEN, NO_TOKEN] {
+        assert!(
+            filled.iter().any(|(req, _)| req.token_id == token && req.side == Side::Buy),
+            "no filled buy of {}",
+            token
+        );
+    }
+    assert!(exchange.shares(YES_TOKEN) > Decimal::ZERO);
+    assert_eq!(exchange.shares(YES_TOKEN), exchange.shares(NO_TOKEN), "legs should be hedged");
+    assert!(exchange.balance() < Decimal::from(1000));
+}
+
+#[test]
+fn fixtures_load() {
+    for name in ["pair_arb.json", "pair_ws_move.json"] {
+        let fixture = fixture(name);
+        assert_eq!(fixture.markets.len(), 1, "{}", name);
+        assert_eq!(fixture.books.len(), 2, "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn serves_markets_and_books() {
+    let server = MockServer::start(fixture("pair_arb.json"), 0).await.unwrap();
+    let http = reqwest::Client::new();
+
+    let page: Value = http
+        .get(format!("{}/sampling-markets", server.clob_host()))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["data"].as_array().unwrap().len(), 1);
+    assert_eq!(page["data"][0]["tokens"][0]["token_id"], YES_TOKEN);
+
+    let book: Value = http
+        .get(format!("{}/book", server.clob_host()))
+        .query(&[("token_id", YES_TOKEN)])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(book["asks"][0]["price"], "0.45");
+
+    let missing = http
+        .get(format!("{}/book", server.clob_host()))
+        .query(&[("token_id", "404")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn posted_orders_fill_against_fixture_books() {
+    let server = MockServer::start(fixture("pair_arb.json"), 0).await.unwrap();
+
+    // 10 shares at 0.45: 4.5 USDC for 10 shares, in 6-decimal base units
+    let body = serde_json::json!({
+        "order": {
+            "tokenId": YES_TOKEN,
+            "makerAmount": "4500000",
+            "takerAmount": "10000000",
+            "side": "BUY",
+        },
+        "orderType": "FOK",
+    });
+    let response: Value = reqwest::Client::new()
+        .post(format!("{}/order", server.clob_host()))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(response["success"], true, "{}", response);
+    assert_eq!(server.exchange().shares(YES_TOKEN), Decimal::from(10));
+    assert_eq!(server.exchange().balance(), Decimal::from(1000) - Decimal::new(45, 1));
+}
+
+#[tokio::test]
+async fn market_channel_plays_script() {
+    let server = MockServer::start(fixture("pair_ws_move.json"), 0).await.unwrap();
+    let books = Arc::new(OrderBookStore::new());
+    let tokens = vec![YES_TOKEN.to_string(), NO_TOKEN.to_string()];
+    let mut ws = WsClient::connect(&server.ws_url(), tokens.clone(), books.clone(), None)
+        .await
+        .unwrap();
+
+    // Snapshot first, then the scripted move of the YES ask
+    let yes_ask = || books.best_prices(YES_TOKEN).and_then(|(_, ask)| ask).map(|a| a.price);
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while yes_ask() != Some(Decimal::new(45, 2)) {
+            ws.rx.recv().await.expect("feed should stay open");
+        }
+    })
+    .await
+    .expect("scripted price change should arrive");
+    assert!(books.get(NO_TOKEN).is_some());
+
+    // The server's own book moved too, so orders fill at the scripted price
+    let served = server.exchange().get_order_books(&tokens[..1]).await.unwrap();
+    assert_eq!(served[0].asks[0].price, Decimal::new(45, 2));
+
+    ws.shutdown().await;
+}
+
+#[tokio::test]
+async fn bot_trades_in_polling_mode() {
+    let server = MockServer::start(fixture("pair_arb.json"), 0).await.unwrap();
+    run_bot(&server, false).await;
+    assert_bought_sets(&server);
+}
+
+#[tokio::test]
+async fn bot_trades_in_websocket_mode() {
+    // Fairly priced until the scripted price_change drops the YES ask to 0.45
+    let server = MockServer::start(fixture("pair_ws_move.json"), 0).await.unwrap();
+    run_bot(&server, true).await;
+    assert_bought_sets(&server);
+
+    for (request, _) in server.exchange().orders() {
+        if request.token_id == YES_TOKEN {
+            assert!(request.price < Decimal::new(52, 2), "traded before the move at {}", request.price);
+        }
+    }
+}
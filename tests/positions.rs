@@ -0,0 +1,87 @@
+//! Matching held tokens into complete sets per market and per group
+
+use rust_decimal::Decimal;
+
+use polymarket_arb::api::Side;
+use polymarket_arb::positions::PositionLedger;
+
+fn buy(ledger: &PositionLedger, condition_id: &str, token_id: &str, shares: i64, price: i64) {
+    let shares = Decimal::from(shares);
+    ledger.record_fill(condition_id, token_id, token_id, Side::Buy, shares, shares * Decimal::new(price, 2));
+}
+
+fn tokens(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn pairs_match_both_outcomes() {
+    let ledger = PositionLedger::new();
+    ledger.register_outcomes("m1", tokens(&["y1", "n1"]));
+    buy(&ledger, "m1", "y1", 10, 40);
+    buy(&ledger, "m1", "n1", 12, 50);
+
+    let exposures = ledger.exposures();
+    assert_eq!(exposures.len(), 1);
+    assert_eq!(exposures[0].id, "m1");
+    assert_eq!(exposures[0].matched, Decimal::from(10));
+    assert_eq!(exposures[0].unmatched.len(), 1);
+    assert_eq!(exposures[0].unmatched[0].token_id, "n1");
+    assert_eq!(exposures[0].unmatched[0].shares, Decimal::from(2));
+    assert_eq!(exposures[0].cost, Decimal::from(10));
+}
+
+#[test]
+fn multi_outcome_markets_need_every_outcome() {
+    let ledger = PositionLedger::new();
+    ledger.register_outcomes("m1", tokens(&["a", "b", "c"]));
+    buy(&ledger, "m1", "a", 10, 30);
+    buy(&ledger, "m1", "b", 10, 30);
+
+    let exposures = ledger.exposures();
+    assert_eq!(exposures.len(), 1);
+    assert!(exposures[0].matched.is_zero());
+    assert!(exposures[0].is_unhedged());
+
+    buy(&ledger, "m1", "c", 10, 30);
+    assert!(ledger.unhedged().is_empty());
+}
+
+#[test]
+fn complete_baskets_are_hedged_across_markets() {
+    let ledger = PositionLedger::new();
+    for n in 1..=3 {
+        ledger.register_outcomes(&format!("m{}", n), tokens(&[&format!("y{}", n), &format!("n{}", n)]));
+    }
+    ledger.register_outcomes("group", tokens(&["y1", "y2", "y3"]));
+    for n in 1..=3 {
+        buy(&ledger, &format!("m{}", n), &format!("y{}", n), 10, 30);
+    }
+
+    let exposures = ledger.exposures();
+    assert_eq!(exposures.len(), 1);
+    assert_eq!(exposures[0].id, "group");
+    assert_eq!(exposures[0].matched, Decimal::from(10));
+    assert_eq!(exposures[0].condition_ids, vec!["m1", "m2", "m3"]);
+    assert!(ledger.unhedged().is_empty());
+
+    // A pair trade in one member matches on its own, after the basket
+    buy(&ledger, "m1", "y1", 5, 40);
+    buy(&ledger, "m1", "n1", 5, 55);
+    let pair = ledger.exposures().into_iter().find(|e| e.id == "m1").unwrap();
+    assert_eq!(pair.matched, Decimal::from(5));
+    assert!(ledger.unhedged().is_empty());
+}
+
+#[test]
+fn unregistered_tokens_are_unmatched() {
+    let ledger = PositionLedger::new();
+    buy(&ledger, "m1", "y1", 10, 40);
+    buy(&ledger, "m1", "n1", 10, 50);
+
+    let exposures = ledger.exposures();
+    assert_eq!(exposures.len(), 1);
+    assert_eq!(exposures[0].id, "m1");
+    assert!(exposures[0].matched.is_zero());
+    assert_eq!(exposures[0].unmatched.len(), 2);
+}
@@ -0,0 +1,59 @@
+//! Pre-trade risk limits against the position ledger
+
+use rust_decimal::Decimal;
+
+use polymarket_arb::api::Side;
+use polymarket_arb::positions::PositionLedger;
+use polymarket_arb::risk::{RiskLimits, RiskManager, RiskRejection, TradeCheck};
+
+fn limits() -> RiskLimits {
+    RiskLimits {
+        max_capital_deployed: Decimal::from(500),
+        max_market_exposure: Decimal::from(100),
+        max_unhedged_exposure: Decimal::from(5),
+        max_trades_per_minute: 2,
+        max_daily_loss: Decimal::from(50),
+    }
+}
+
+fn check(condition_id: &str) -> TradeCheck<'_> {
+    TradeCheck {
+        condition_ids: vec![condition_id],
+        size: Decimal::from(10),
+        cost: Decimal::from(9),
+        adds_exposure: true,
+    }
+}
+
+#[test]
+fn complete_baskets_dont_block_later_buys() {
+    let ledger = PositionLedger::new();
+    let members = ["m1", "m2", "m3"];
+    ledger.register_outcomes("group", members.iter().map(|m| format!("{}-yes", m)).collect());
+    for member in members {
+        ledger.record_fill(member, &format!("{}-yes", member), "Yes", Side::Buy, Decimal::from(20), Decimal::from(6));
+    }
+
+    let risk = RiskManager::new(limits());
+    assert_eq!(risk.approve(&ledger, &check("m9")).unwrap(), Decimal::from(10));
+
+    // An incomplete basket is exposed
+    ledger.register_outcomes("other", vec!["o1".to_string(), "o2".to_string()]);
+    ledger.record_fill("o", "o1", "Yes", Side::Buy, Decimal::from(20), Decimal::from(6));
+    assert!(matches!(risk.approve(&ledger, &check("m9")), Err(RiskRejection::Unhedged(_))));
+}
+
+#[test]
+fn only_submitted_trades_take_rate_slots() {
+    let ledger = PositionLedger::new();
+    let risk = RiskManager::new(limits());
+
+    // Approvals alone never use up the limit
+    for _ in 0..5 {
+        assert!(risk.approve(&ledger, &check("m1")).is_ok());
+    }
+
+    risk.record_trade();
+    risk.record_trade();
+    assert!(matches!(risk.approve(&ledger, &check("m1")), Err(RiskRejection::RateLimited(2))));
+}
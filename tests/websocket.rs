@@ -0,0 +1,27 @@
+//! Parsing market channel frames into local books
+
+use serde_json::json;
+
+use polymarket_arb::orderbook::OrderBookStore;
+use polymarket_arb::websocket::WsClient;
+
+#[test]
+fn large_frames_yield_every_update() {
+    // More events in one frame than any channel buffer between reader and scanner
+    let events: Vec<_> = (0..1500)
+        .map(|n| json!({
+            "event_type": "book",
+            "asset_id": n.to_string(),
+            "market": "0xmarket",
+            "bids": [{ "price": "0.40", "size": "10" }],
+            "asks": [{ "price": "0.45", "size": "10" }],
+        }))
+        .collect();
+
+    let books = OrderBookStore::new();
+    let updates = WsClient::handle_message(&json!(events).to_string(), &books).unwrap();
+
+    assert_eq!(updates.len(), 1500);
+    assert_eq!(updates[1499].asset_id, "1499");
+    assert!(books.get("1499").is_some());
+}